@@ -1,20 +1,41 @@
-use components::InventoryView;
+mod routing;
+
+use components::{FilterPanel, InventoryView};
+use data::filter::InventoryFilter;
 use data::inventory::TileInventory;
+use data::overrides::apply_overrides;
+use data::route::{Page, Route};
+use data::streets::modern_street_name;
 use gloo_console as console_logger;
-use utils::csv_parser::parse_csv_str;
+use std::collections::BTreeSet;
+use utils::csv_parser::{parse_csv_str, parse_overrides_str};
+use wasm_bindgen::{closure::Closure, JsCast};
 use yew::prelude::*;
 
-pub static INVENTORY_CSV_BYTES: &[u8] =
-    include_bytes!("../../2024-07-08-inventory-with-lat-long.csv");
+/// The survey's columns joined with the coordinates geocoded on 2024-07-08.
+pub static INVENTORY_CSV_BYTES: &[u8] = include_bytes!("../../inventory-with-lat-long.csv");
+
+/// Hand-corrected marker positions, maintained separately from the inventory CSV.
+pub static COORDINATE_OVERRIDES_CSV_BYTES: &[u8] =
+    include_bytes!("../../coordinate_overrides.csv");
 
 pub struct App {
     inventory: Vec<TileInventory>,
     selected_item: Option<TileInventory>,
+    filter: InventoryFilter,
+    /// The street named by a `/street/...` link. While the filter shows just that street's
+    /// signs the URL keeps naming it, rather than listing every sign it stands for.
+    street_page: Option<String>,
+    /// Follows the browser's back and forward buttons; kept so it isn't dropped.
+    _popstate_listener: Option<Closure<dyn Fn()>>,
 }
 
 pub enum Msg {
     InventoryLoaded(Vec<TileInventory>),
     ItemSelected(Option<TileInventory>),
+    FilterChanged(InventoryFilter),
+    /// The browser moved to another history entry.
+    RouteChanged(Route),
 }
 
 impl Component for App {
@@ -24,13 +45,50 @@ impl Component for App {
     fn create(ctx: &Context<Self>) -> Self {
         // Load inventory data
         ctx.link().send_future(async {
-            let inventory = parse_csv_str(INVENTORY_CSV_BYTES).expect("Failed to parse CSV data");
+            let mut inventory =
+                parse_csv_str(INVENTORY_CSV_BYTES).expect("Failed to parse CSV data");
+            // The markers are still worth showing where the survey put them.
+            let overrides =
+                parse_overrides_str(COORDINATE_OVERRIDES_CSV_BYTES).unwrap_or_else(|err| {
+                    console_logger::error!(
+                        "Failed to parse coordinate overrides:",
+                        err.to_string()
+                    );
+                    Vec::new()
+                });
+            let now = (js_sys::Date::now() / 1000.0) as u64;
+            let report = apply_overrides(&mut inventory, &overrides, now);
+            console_logger::log!("Coordinate overrides applied:", report.applied.len());
+            for id in report.unknown_ids {
+                console_logger::warn!("Coordinate override for unknown ID:", id);
+            }
+            for id in report.duplicate_ids {
+                console_logger::warn!("Coordinate override listed more than once for ID:", id);
+            }
             Msg::InventoryLoaded(inventory)
         });
 
+        let on_route_change = ctx
+            .link()
+            .callback(|_: ()| Msg::RouteChanged(routing::current_route()));
+        let popstate_listener =
+            Closure::wrap(Box::new(move || on_route_change.emit(())) as Box<dyn Fn()>);
+        let popstate_listener = web_sys::window().and_then(|window| {
+            window
+                .add_event_listener_with_callback(
+                    "popstate",
+                    popstate_listener.as_ref().unchecked_ref(),
+                )
+                .ok()?;
+            Some(popstate_listener)
+        });
+
         Self {
             inventory: vec![],
             selected_item: None,
+            filter: InventoryFilter::default(),
+            street_page: None,
+            _popstate_listener: popstate_listener,
         }
     }
 
@@ -39,8 +97,10 @@ impl Component for App {
             Msg::InventoryLoaded(inventory) => {
                 self.inventory = inventory;
 
-                // Select the first item from the inventory
-                self.selected_item = self.inventory.first().cloned();
+                // Start from the sign, street or filters in the URL, then tidy the URL up
+                // in case it named a sign that doesn't exist.
+                self.apply_route(routing::current_route());
+                routing::replace(&self.route());
 
                 if let Some(selected_item) = &self.selected_item {
                     console_logger::log!("Initially selected item ID:", selected_item.id);
@@ -60,6 +120,23 @@ impl Component for App {
                 } else {
                     console_logger::log!("No item selected");
                 }
+                routing::push(&self.route());
+                true
+            }
+            Msg::FilterChanged(filter) => {
+                self.filter = filter;
+                // Keep the selection consistent with the markers and selectors on display.
+                if let Some(selected_item) = &self.selected_item {
+                    if !self.filter.matches(selected_item) {
+                        console_logger::log!("Selected item filtered out:", selected_item.id);
+                        self.selected_item = None;
+                    }
+                }
+                routing::replace(&self.route());
+                true
+            }
+            Msg::RouteChanged(route) => {
+                self.apply_route(route);
                 true
             }
         }
@@ -71,15 +148,100 @@ impl Component for App {
             self.inventory.len()
         );
 
+        let filtered = self.filter.apply(&self.inventory);
         html! {
             <div class="container mt-4">
                 <h3 class="mb-4">{"Bowen Survey of Spanish Ceramic Signs"}</h3>
-                <InventoryView
-                    inventory={self.inventory.clone()}
-                    selected_item={self.selected_item.clone()}
-                    on_item_select={ctx.link().callback(Msg::ItemSelected)}
-                />
+                <div class="row">
+                    <div class="col-md-3">
+                        <FilterPanel
+                            inventory={self.inventory.clone()}
+                            filter={self.filter.clone()}
+                            matching={filtered.len()}
+                            on_change={ctx.link().callback(Msg::FilterChanged)}
+                        />
+                    </div>
+                    <div class="col-md-9">
+                        <InventoryView
+                            inventory={filtered}
+                            selected_item={self.selected_item.clone()}
+                            on_item_select={ctx.link().callback(Msg::ItemSelected)}
+                        />
+                    </div>
+                </div>
             </div>
         }
     }
 }
+
+impl App {
+    /// The URL for the current selection and filters.
+    fn route(&self) -> Route {
+        let selected = self.selected_item.as_ref().map(|item| item.id);
+        if let (None, Some(street)) = (selected, &self.street_page) {
+            if self.filter.streets == self.street_signs_named(street) {
+                let mut filter = self.filter.clone();
+                filter.streets.clear();
+                return Route {
+                    page: Page::Street(street.clone()),
+                    filter,
+                };
+            }
+        }
+        Route::new(selected, &self.filter)
+    }
+
+    fn apply_route(&mut self, route: Route) {
+        let mut filter = route.effective_filter();
+        filter.streets = filter
+            .streets
+            .iter()
+            .flat_map(|street| self.street_signs_named(street))
+            .collect();
+
+        self.street_page = match &route.page {
+            Page::Street(street) => Some(street.clone()),
+            Page::Home | Page::Sign(_) => None,
+        };
+        self.selected_item = match route.page {
+            Page::Sign(id) => {
+                let item = self.inventory.iter().find(|item| item.id == id).cloned();
+                if item.is_none() {
+                    console_logger::warn!("No sign with ID", id);
+                }
+                item
+            }
+            Page::Home | Page::Street(_) => None,
+        };
+        // A link to a sign always shows it, even if someone edited in filters that leave
+        // it out.
+        if let Some(selected_item) = &self.selected_item {
+            if !filter.matches(selected_item) {
+                filter = InventoryFilter::default();
+            }
+        }
+        self.filter = filter;
+    }
+
+    /// The inventory's spellings of a street named in a URL, which may differ in case or use
+    /// the street's modern name, as in `/street/bourbon street`. A modern name can stand for
+    /// several signs: both "Calle D'Chartres" and "Calle D'Conde" are on Chartres Street.
+    fn street_signs_named(&self, name: &str) -> BTreeSet<String> {
+        let signs: BTreeSet<String> = self
+            .inventory
+            .iter()
+            .map(|item| &item.street_sign)
+            .filter(|sign| {
+                sign.eq_ignore_ascii_case(name)
+                    || modern_street_name(sign)
+                        .is_some_and(|modern| modern.eq_ignore_ascii_case(name))
+            })
+            .cloned()
+            .collect();
+        if signs.is_empty() {
+            [name.to_string()].into()
+        } else {
+            signs
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use data::route::Route;
+use gloo_console as console_logger;
+use wasm_bindgen::JsValue;
+use web_sys::{History, Location, Url};
+
+fn location() -> Option<Location> {
+    web_sys::window().map(|window| window.location())
+}
+
+fn history() -> Option<History> {
+    web_sys::window().and_then(|window| window.history().ok())
+}
+
+/// The path the app is served from, from the page's `<base href>`; the site root without one.
+fn base_path() -> String {
+    web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.base_uri().ok().flatten())
+        .and_then(|base| Url::new(&base).ok())
+        .map_or_else(|| "/".to_string(), |base| base.pathname())
+}
+
+/// The route in the address bar.
+pub fn current_route() -> Route {
+    let Some(location) = location() else {
+        return Route::default();
+    };
+    Route::parse_under(
+        &base_path(),
+        &location.pathname().unwrap_or_default(),
+        &location.search().unwrap_or_default(),
+    )
+}
+
+fn current_url() -> Option<String> {
+    let location = location()?;
+    Some(format!(
+        "{}{}",
+        location.pathname().ok()?,
+        location.search().ok()?
+    ))
+}
+
+/// Adds a history entry for `route`, so the back button returns to the current one.
+pub fn push(route: &Route) {
+    let url = route.to_url_under(&base_path());
+    if current_url().as_deref() == Some(url.as_str()) {
+        return;
+    }
+    if let Some(Err(err)) =
+        history().map(|history| history.push_state_with_url(&JsValue::NULL, "", Some(&url)))
+    {
+        console_logger::warn!("Couldn't update the URL:", err);
+    }
+}
+
+/// Points the current history entry at `route` without adding one.
+pub fn replace(route: &Route) {
+    let url = route.to_url_under(&base_path());
+    if let Some(Err(err)) =
+        history().map(|history| history.replace_state_with_url(&JsValue::NULL, "", Some(&url)))
+    {
+        console_logger::warn!("Couldn't update the URL:", err);
+    }
+}
@@ -1,11 +1,18 @@
+use crate::js_bindings::write_clipboard_text;
+use data::analysis::analyze_inventory;
 use data::inventory::TileInventory;
-use web_sys::HtmlSelectElement;
+use data::overrides::OVERRIDE_PROVIDER;
+use data::photos::{fallback_src, srcset, thumbnail_path};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{HtmlImageElement, HtmlSelectElement};
 use yew::prelude::*;
 
 pub struct AnalysisDisplay {
     selected_street: Option<String>,
     selected_address: Option<String>,
     current_photo_index: usize,
+    /// Whether copying the selected sign's link worked, once it's been tried.
+    link_copied: Option<bool>,
 }
 
 #[derive(Properties, PartialEq)]
@@ -20,6 +27,9 @@ pub enum Msg {
     AddressSelected(String),
     NextPhoto,
     PreviousPhoto,
+    SelectPhoto(usize),
+    CopyLink,
+    LinkCopied(bool),
 }
 
 impl Component for AnalysisDisplay {
@@ -31,6 +41,7 @@ impl Component for AnalysisDisplay {
             selected_street: None,
             selected_address: None,
             current_photo_index: 0,
+            link_copied: None,
         }
     }
 
@@ -76,6 +87,32 @@ impl Component for AnalysisDisplay {
                     false
                 }
             }
+            Msg::SelectPhoto(index) => {
+                if self.current_photo_index != index {
+                    self.current_photo_index = index;
+                    true
+                } else {
+                    false
+                }
+            }
+            Msg::CopyLink => {
+                // The app keeps the address bar pointing at the selected sign.
+                let Some(url) = web_sys::window().and_then(|window| window.location().href().ok())
+                else {
+                    return false;
+                };
+                match write_clipboard_text(&url) {
+                    Ok(promise) => ctx.link().send_future(async move {
+                        Msg::LinkCopied(JsFuture::from(promise).await.is_ok())
+                    }),
+                    Err(_) => ctx.link().send_message(Msg::LinkCopied(false)),
+                }
+                false
+            }
+            Msg::LinkCopied(copied) => {
+                self.link_copied = Some(copied);
+                true
+            }
         }
     }
 
@@ -103,6 +140,7 @@ impl Component for AnalysisDisplay {
         html! {
             
             <div>
+                { self.render_summary(ctx) }
                 <h3>{"Select Specific Sign by Street and Address"}</h3>
                 <div>
                     <select value={self.selected_street.clone().unwrap_or_default()} onchange={ctx.link().callback(|e: Event| Msg::StreetSelected(e.target_unchecked_into::<HtmlSelectElement>().value()))}>
@@ -127,6 +165,7 @@ impl Component for AnalysisDisplay {
             self.selected_street = Some(item.street_sign.clone());
             self.selected_address = Some(item.street_address.clone());
             self.current_photo_index = 0;
+            self.link_copied = None;
             true
         } else {
             false
@@ -148,6 +187,24 @@ impl AnalysisDisplay {
         ctx.props().on_item_select.emit(selected_item);
     }
 
+    /// Totals for the signs on display, which follow the filter.
+    fn render_summary(&self, ctx: &Context<Self>) -> Html {
+        let analysis = analyze_inventory(&ctx.props().inventory);
+        if analysis.total_items == 0 {
+            return html! { <p class="inventory-summary">{"No signs match the filters"}</p> };
+        }
+        html! {
+            <p class="inventory-summary">
+                {format!(
+                    "{} signs, {} damaged tiles ({:.1} per sign)",
+                    analysis.total_items,
+                    analysis.total_damaged_tiles,
+                    analysis.average_damaged_tiles
+                )}
+            </p>
+        }
+    }
+
     fn render_selected_item_info(&self, ctx: &Context<Self>) -> Html {
         if let Some(item) = &ctx.props().selected_item {
             match (item.latitude, item.longitude) {
@@ -157,8 +214,10 @@ impl AnalysisDisplay {
                             <h4>{"Selected Item"}</h4>
                             <p>{format!("Street Sign: {}", item.street_sign)}</p>
                             <p>{format!("Address: {}", item.street_address)}</p>
-                            <p>{format!("Approximate Latitude: {}", latitude)}</p>
-                            <p>{format!("Approximate Longitude: {}", longitude)}</p>
+                            <p>{format!("Latitude: {}", latitude)}</p>
+                            <p>{format!("Longitude: {}", longitude)}</p>
+                            { self.render_geocode_quality(item) }
+                            { self.render_copy_link(ctx) }
                         </div>
                     }
                 }
@@ -169,6 +228,7 @@ impl AnalysisDisplay {
                             <p>{format!("Street Sign: {}", item.street_sign)}</p>
                             <p>{format!("Address: {}", item.street_address)}</p>
                             <p>{format!("No Latitude/Longitude value derived. :-( {}","")}</p>
+                            { self.render_copy_link(ctx) }
                         </div>
                     }
                 }
@@ -178,18 +238,81 @@ impl AnalysisDisplay {
         }
     }
 
+    fn render_copy_link(&self, ctx: &Context<Self>) -> Html {
+        let status = match self.link_copied {
+            None => "",
+            Some(true) => "Link copied",
+            Some(false) => "Couldn't copy; copy the address bar instead",
+        };
+        html! {
+            <p class="copy-link">
+                <button class="btn btn-sm btn-outline-secondary" onclick={ctx.link().callback(|_| Msg::CopyLink)}>
+                    {"Copy link"}
+                </button>
+                <span>{ status }</span>
+            </p>
+        }
+    }
+
+    fn render_geocode_quality(&self, item: &TileInventory) -> Html {
+        match item.geocode_result() {
+            Some(result) if result.provider == OVERRIDE_PROVIDER => html! {
+                <>
+                    <p>{format!("Location: {}", result.match_type.accuracy_label())}</p>
+                    <p class="geocode-provenance">
+                        {format!("Corrected by hand: {}", result.display_name.unwrap_or_default())}
+                    </p>
+                </>
+            },
+            Some(result) => html! {
+                <>
+                    <p>{format!("Location: {}", result.match_type.accuracy_label())}</p>
+                    <p class="geocode-provenance">
+                        {format!(
+                            "Matched {} via {} (confidence {:.0}%)",
+                            result.display_name.as_deref().unwrap_or("address"),
+                            result.provider,
+                            result.confidence * 100.0
+                        )}
+                    </p>
+                </>
+            },
+            // Coordinates geocoded before match types were recorded.
+            None => html! { <p>{"Location: approximate (match quality unknown)"}</p> },
+        }
+    }
+
     fn render_photo_viewer(&self, ctx: &Context<Self>) -> Html {
         if let Some(item) = &ctx.props().selected_item {
             let photos = self.get_photos(item);
             if !photos.is_empty() {
+                let photo = &photos[self.current_photo_index];
                 html! {
                     <div class="photo-viewer">
-                        <img src={photos[self.current_photo_index].clone()} alt="Tile inventory" />
+                        <img
+                            src={photo.clone()}
+                            srcset={srcset(photo)}
+                            sizes="(max-width: 768px) 100vw, 640px"
+                            alt="Tile inventory"
+                            onerror={fall_back_to_original(photo.clone())}
+                        />
                         <div>
                             <button onclick={ctx.link().callback(|_| Msg::PreviousPhoto)}>{"Previous"}</button>
                             <button onclick={ctx.link().callback(|_| Msg::NextPhoto)}>{"Next"}</button>
                         </div>
                         <p>{format!("Photo {} of {}", self.current_photo_index + 1, photos.len())}</p>
+                        <div class="photo-strip">
+                            { for photos.iter().enumerate().map(|(index, photo)| html! {
+                                <img
+                                    class={classes!("photo-thumbnail", (index == self.current_photo_index).then_some("selected"))}
+                                    src={thumbnail_path(photo)}
+                                    alt={format!("Photo {}", index + 1)}
+                                    loading="lazy"
+                                    onerror={fall_back_to_original(photo.clone())}
+                                    onclick={ctx.link().callback(move |_| Msg::SelectPhoto(index))}
+                                />
+                            }) }
+                        </div>
                     </div>
                 }
             } else {
@@ -214,3 +337,16 @@ impl AnalysisDisplay {
         .collect()
     }
 }
+
+/// Swaps a missing derivative for the full-size photo, so the viewer still works before
+/// `photo-tool derivatives` has been run.
+fn fall_back_to_original(original: String) -> Callback<Event> {
+    Callback::from(move |e: Event| {
+        let image: HtmlImageElement = e.target_unchecked_into();
+        let src = image.get_attribute("src").unwrap_or_default();
+        if let Some(original) = fallback_src(&original, &src, &image.srcset()) {
+            image.set_srcset("");
+            image.set_src(original);
+        }
+    })
+}
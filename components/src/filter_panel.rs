@@ -0,0 +1,235 @@
+use crate::marker_style::condition_color;
+use data::condition::ConditionLevel;
+use data::filter::{FilterOptions, InventoryFilter};
+use data::inventory::TileInventory;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+
+/// Sidebar narrowing what the map, the sign selectors and the statistics show. The filter
+/// itself belongs to the parent, which applies it; the panel only reports changes.
+pub struct FilterPanel;
+
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    /// The whole inventory, to offer every street and wall type whatever is filtered.
+    pub inventory: Vec<TileInventory>,
+    pub filter: InventoryFilter,
+    /// How many records the filter keeps.
+    pub matching: usize,
+    pub on_change: Callback<InventoryFilter>,
+}
+
+pub enum Msg {
+    ToggleCondition(ConditionLevel),
+    ToggleStreet(String),
+    MinDamaged(u32),
+    MaxDamaged(u32),
+    WallType(Option<String>),
+    GroutCondition(Option<String>),
+    HasPhotos(bool),
+    HasCoordinates(bool),
+    Clear,
+}
+
+impl Component for FilterPanel {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        let max_damaged = FilterOptions::from_inventory(&ctx.props().inventory).max_damaged;
+        let mut filter = ctx.props().filter.clone();
+        match msg {
+            Msg::ToggleCondition(level) => {
+                if !filter.conditions.remove(&level) {
+                    filter.conditions.insert(level);
+                }
+            }
+            Msg::ToggleStreet(street) => {
+                if !filter.streets.remove(&street) {
+                    filter.streets.insert(street);
+                }
+            }
+            // A bound at the end of the range doesn't restrict anything, so it's cleared
+            // rather than leaving out records without a damage count.
+            Msg::MinDamaged(min) => {
+                let max = filter.max_damaged.unwrap_or(max_damaged);
+                filter.min_damaged = Some(min.min(max)).filter(|&min| min > 0);
+            }
+            Msg::MaxDamaged(max) => {
+                let min = filter.min_damaged.unwrap_or_default();
+                filter.max_damaged = Some(max.max(min)).filter(|&max| max < max_damaged);
+            }
+            Msg::WallType(wall_type) => filter.wall_type = wall_type,
+            Msg::GroutCondition(grout_condition) => filter.grout_condition = grout_condition,
+            Msg::HasPhotos(has_photos) => filter.has_photos = has_photos,
+            Msg::HasCoordinates(has_coordinates) => filter.has_coordinates = has_coordinates,
+            Msg::Clear => filter = InventoryFilter::default(),
+        }
+        ctx.props().on_change.emit(filter);
+        false
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+        let options = FilterOptions::from_inventory(&props.inventory);
+
+        html! {
+            <div class="filter-panel">
+                <h4>{"Filter Signs"}</h4>
+                <p class="filter-count">
+                    {format!("Showing {} of {} signs", props.matching, props.inventory.len())}
+                </p>
+                <button
+                    class="btn btn-sm btn-outline-secondary"
+                    disabled={props.filter.is_empty()}
+                    onclick={ctx.link().callback(|_| Msg::Clear)}
+                >
+                    {"Clear filters"}
+                </button>
+                { self.render_conditions(ctx) }
+                { self.render_streets(ctx, &options) }
+                { self.render_damage_range(ctx, &options) }
+                { self.render_select(ctx, "Type of wall", &options.wall_types, props.filter.wall_type.as_ref(), Msg::WallType) }
+                { self.render_select(ctx, "Grout condition", &options.grout_conditions, props.filter.grout_condition.as_ref(), Msg::GroutCondition) }
+                <fieldset>
+                    <legend>{"Records"}</legend>
+                    <label class="filter-option">
+                        <input
+                            type="checkbox"
+                            checked={props.filter.has_photos}
+                            onchange={ctx.link().callback(|e: Event| Msg::HasPhotos(e.target_unchecked_into::<HtmlInputElement>().checked()))}
+                        />
+                        {"Has photos"}
+                    </label>
+                    <label class="filter-option">
+                        <input
+                            type="checkbox"
+                            checked={props.filter.has_coordinates}
+                            onchange={ctx.link().callback(|e: Event| Msg::HasCoordinates(e.target_unchecked_into::<HtmlInputElement>().checked()))}
+                        />
+                        {"Has coordinates"}
+                    </label>
+                </fieldset>
+            </div>
+        }
+    }
+}
+
+impl FilterPanel {
+    fn render_conditions(&self, ctx: &Context<Self>) -> Html {
+        let filter = &ctx.props().filter;
+        html! {
+            <fieldset>
+                <legend>{"Condition"}</legend>
+                { for ConditionLevel::ALL.iter().map(|&level| html! {
+                    <label class="filter-option">
+                        <input
+                            type="checkbox"
+                            checked={filter.conditions.contains(&level)}
+                            onchange={ctx.link().callback(move |_| Msg::ToggleCondition(level))}
+                        />
+                        <span class="legend-swatch" style={format!("background-color: {}", condition_color(level))}></span>
+                        { level.label() }
+                    </label>
+                }) }
+            </fieldset>
+        }
+    }
+
+    fn render_streets(&self, ctx: &Context<Self>, options: &FilterOptions) -> Html {
+        let filter = &ctx.props().filter;
+        html! {
+            <fieldset>
+                <legend>{"Street"}</legend>
+                <div class="filter-streets">
+                    { for options.streets.iter().map(|street| {
+                        let toggled = street.clone();
+                        html! {
+                            <label class="filter-option">
+                                <input
+                                    type="checkbox"
+                                    checked={filter.streets.contains(street)}
+                                    onchange={ctx.link().callback(move |_| Msg::ToggleStreet(toggled.clone()))}
+                                />
+                                { street }
+                            </label>
+                        }
+                    }) }
+                </div>
+            </fieldset>
+        }
+    }
+
+    fn render_damage_range(&self, ctx: &Context<Self>, options: &FilterOptions) -> Html {
+        let filter = &ctx.props().filter;
+        let min = filter.min_damaged.unwrap_or_default();
+        let max = filter.max_damaged.unwrap_or(options.max_damaged);
+        let parse = |e: InputEvent| {
+            e.target_unchecked_into::<HtmlInputElement>()
+                .value()
+                .parse()
+                .unwrap_or_default()
+        };
+        html! {
+            <fieldset>
+                <legend>{format!("Damaged tiles: {} to {}", min, max)}</legend>
+                <label class="filter-option">
+                    {"From"}
+                    <input
+                        type="range"
+                        min="0"
+                        max={options.max_damaged.to_string()}
+                        value={min.to_string()}
+                        oninput={ctx.link().callback(move |e| Msg::MinDamaged(parse(e)))}
+                    />
+                </label>
+                <label class="filter-option">
+                    {"To"}
+                    <input
+                        type="range"
+                        min="0"
+                        max={options.max_damaged.to_string()}
+                        value={max.to_string()}
+                        oninput={ctx.link().callback(move |e| Msg::MaxDamaged(parse(e)))}
+                    />
+                </label>
+            </fieldset>
+        }
+    }
+
+    /// A single-choice select with "Any" for no restriction. Hidden when the inventory has no
+    /// values for the column, as older exports don't.
+    fn render_select(
+        &self,
+        ctx: &Context<Self>,
+        label: &str,
+        values: &[String],
+        selected: Option<&String>,
+        msg: fn(Option<String>) -> Msg,
+    ) -> Html {
+        if values.is_empty() {
+            return html! {};
+        }
+        html! {
+            <fieldset>
+                <legend>{label}</legend>
+                <select
+                    class="form-select form-select-sm"
+                    onchange={ctx.link().callback(move |e: Event| {
+                        let value = e.target_unchecked_into::<HtmlSelectElement>().value();
+                        msg(Some(value).filter(|value| !value.is_empty()))
+                    })}
+                >
+                    <option value="" selected={selected.is_none()}>{"Any"}</option>
+                    { for values.iter().map(|value| html! {
+                        <option value={value.clone()} selected={Some(value) == selected}>{value}</option>
+                    }) }
+                </select>
+            </fieldset>
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use data::inventory::TileInventory;
+use data::table::{sort_rows, TableColumn};
+use gloo_console as console_logger;
+use utils::export::write_table_csv;
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{Blob, BlobPropertyBag, HtmlAnchorElement, HtmlInputElement, Url};
+use yew::prelude::*;
+
+const PAGE_SIZE: usize = 25;
+
+const DOWNLOAD_NAME: &str = "spanish-tiles-inventory.csv";
+
+/// Every record as a sortable, paged table. Clicking a row selects it, and selecting a sign
+/// elsewhere turns to the page it's on.
+pub struct InventoryTable {
+    sort_column: TableColumn,
+    ascending: bool,
+    /// Shown columns, in `TableColumn::ALL` order.
+    columns: Vec<TableColumn>,
+    page: usize,
+    choosing_columns: bool,
+}
+
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub inventory: Vec<TileInventory>,
+    pub selected_item: Option<TileInventory>,
+    pub on_item_select: Callback<Option<TileInventory>>,
+}
+
+pub enum Msg {
+    /// Sorts by the column, or reverses the order if it's already sorted by it.
+    SortBy(TableColumn),
+    ToggleColumn(TableColumn, bool),
+    ToggleColumnChooser,
+    Page(usize),
+    Download,
+}
+
+impl Component for InventoryTable {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let mut table = Self {
+            sort_column: TableColumn::Id,
+            ascending: true,
+            columns: TableColumn::ALL.to_vec(),
+            page: 0,
+            choosing_columns: false,
+        };
+        table.show_selected(ctx);
+        table
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::SortBy(column) => {
+                if self.sort_column == column {
+                    self.ascending = !self.ascending;
+                } else {
+                    self.sort_column = column;
+                    self.ascending = true;
+                }
+                self.page = 0;
+                self.show_selected(ctx);
+                true
+            }
+            Msg::ToggleColumn(column, shown) => {
+                self.columns = TableColumn::ALL
+                    .into_iter()
+                    .filter(|&other| {
+                        if other == column {
+                            shown
+                        } else {
+                            self.columns.contains(&other)
+                        }
+                    })
+                    .collect();
+                true
+            }
+            Msg::ToggleColumnChooser => {
+                self.choosing_columns = !self.choosing_columns;
+                true
+            }
+            Msg::Page(page) => {
+                let changed = self.page != page;
+                self.page = page;
+                changed
+            }
+            Msg::Download => {
+                let rows = self.sorted_rows(ctx);
+                let mut csv = Vec::new();
+                let result = write_table_csv(&mut csv, &rows, &self.columns)
+                    .map_err(|err| JsValue::from_str(&err.to_string()))
+                    .and_then(|_| {
+                        download(DOWNLOAD_NAME, "text/csv", &String::from_utf8_lossy(&csv))
+                    });
+                if let Err(err) = result {
+                    console_logger::error!("CSV download failed:", err);
+                }
+                false
+            }
+        }
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>, old_props: &Self::Properties) -> bool {
+        let selected_id = |props: &Props| props.selected_item.as_ref().map(|item| item.id);
+        if selected_id(ctx.props()) != selected_id(old_props) {
+            self.show_selected(ctx);
+        }
+        self.page = self.page.min(self.page_count(ctx) - 1);
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let rows = self.sorted_rows(ctx);
+        let page_count = self.page_count(ctx);
+        let first = self.page * PAGE_SIZE;
+        let selected_id = ctx.props().selected_item.as_ref().map(|item| item.id);
+
+        html! {
+            <div class="inventory-table">
+                <div class="table-toolbar">
+                    <button class="btn btn-sm btn-outline-secondary" onclick={ctx.link().callback(|_| Msg::ToggleColumnChooser)}>
+                        {"Columns"}
+                    </button>
+                    <button
+                        class="btn btn-sm btn-outline-secondary"
+                        disabled={rows.is_empty()}
+                        onclick={ctx.link().callback(|_| Msg::Download)}
+                    >
+                        {"Download CSV"}
+                    </button>
+                </div>
+                { self.render_column_chooser(ctx) }
+                <table class="table table-sm table-hover">
+                    <thead>
+                        <tr>
+                            { for self.columns.iter().map(|&column| self.render_header(ctx, column)) }
+                        </tr>
+                    </thead>
+                    <tbody>
+                        { for rows.iter().skip(first).take(PAGE_SIZE).map(|&item| {
+                            let row_item = item.clone();
+                            let on_item_select = ctx.props().on_item_select.clone();
+                            html! {
+                                <tr
+                                    class={classes!((selected_id == Some(item.id)).then_some("table-active"))}
+                                    onclick={move |_| on_item_select.emit(Some(row_item.clone()))}
+                                >
+                                    { for self.columns.iter().map(|column| html! { <td>{ column.text(item) }</td> }) }
+                                </tr>
+                            }
+                        }) }
+                    </tbody>
+                </table>
+                <div class="table-pager">
+                    <button
+                        class="btn btn-sm btn-outline-secondary"
+                        disabled={self.page == 0}
+                        onclick={ctx.link().callback({
+                            let page = self.page;
+                            move |_| Msg::Page(page.saturating_sub(1))
+                        })}
+                    >
+                        {"Previous"}
+                    </button>
+                    <span>
+                        {format!(
+                            "Page {} of {} ({} signs)",
+                            self.page + 1,
+                            page_count,
+                            rows.len()
+                        )}
+                    </span>
+                    <button
+                        class="btn btn-sm btn-outline-secondary"
+                        disabled={self.page + 1 >= page_count}
+                        onclick={ctx.link().callback({
+                            let page = self.page;
+                            move |_| Msg::Page(page + 1)
+                        })}
+                    >
+                        {"Next"}
+                    </button>
+                </div>
+            </div>
+        }
+    }
+}
+
+impl InventoryTable {
+    fn sorted_rows<'a>(&self, ctx: &'a Context<Self>) -> Vec<&'a TileInventory> {
+        let mut rows: Vec<&TileInventory> = ctx.props().inventory.iter().collect();
+        sort_rows(&mut rows, self.sort_column, self.ascending);
+        rows
+    }
+
+    /// At least one, so an empty table still has a page to be on.
+    fn page_count(&self, ctx: &Context<Self>) -> usize {
+        ctx.props().inventory.len().div_ceil(PAGE_SIZE).max(1)
+    }
+
+    /// Turns to the page with the selected sign, if it's in the table.
+    fn show_selected(&mut self, ctx: &Context<Self>) {
+        let Some(selected) = &ctx.props().selected_item else {
+            return;
+        };
+        if let Some(index) = self
+            .sorted_rows(ctx)
+            .iter()
+            .position(|item| item.id == selected.id)
+        {
+            self.page = index / PAGE_SIZE;
+        }
+    }
+
+    fn render_header(&self, ctx: &Context<Self>, column: TableColumn) -> Html {
+        let arrow = match (self.sort_column == column, self.ascending) {
+            (false, _) => "",
+            (true, true) => " ▲",
+            (true, false) => " ▼",
+        };
+        html! {
+            <th class="sortable" onclick={ctx.link().callback(move |_| Msg::SortBy(column))}>
+                { column.label() }{ arrow }
+            </th>
+        }
+    }
+
+    fn render_column_chooser(&self, ctx: &Context<Self>) -> Html {
+        if !self.choosing_columns {
+            return html! {};
+        }
+        html! {
+            <div class="column-chooser">
+                { for TableColumn::ALL.into_iter().map(|column| html! {
+                    <label class="filter-option">
+                        <input
+                            type="checkbox"
+                            checked={self.columns.contains(&column)}
+                            // The last column stays, so the table never ends up empty.
+                            disabled={self.columns == [column]}
+                            onchange={ctx.link().callback(move |e: Event| {
+                                Msg::ToggleColumn(column, e.target_unchecked_into::<HtmlInputElement>().checked())
+                            })}
+                        />
+                        { column.label() }
+                    </label>
+                }) }
+            </div>
+        }
+    }
+}
+
+/// How long a download's object URL is kept before it is revoked, as in FileSaver.js.
+const REVOKE_DELAY_MS: i32 = 40_000;
+
+/// Saves `contents` through the browser's download prompt.
+fn download(filename: &str, mime_type: &str, contents: &str) -> Result<(), JsValue> {
+    let parts = js_sys::Array::of1(&JsValue::from_str(contents));
+    let options = BlobPropertyBag::new();
+    options.set_type(mime_type);
+    let blob = Blob::new_with_str_sequence_and_options(&parts, &options)?;
+    let url = Url::create_object_url_with_blob(&blob)?;
+
+    let window = web_sys::window().ok_or_else(|| JsValue::from_str("no window"))?;
+    let document = window
+        .document()
+        .ok_or_else(|| JsValue::from_str("no document"))?;
+    let link: HtmlAnchorElement = document.create_element("a")?.dyn_into()?;
+    link.set_href(&url);
+    link.set_download(filename);
+    link.click();
+    // Some browsers only start reading the blob after the click handler returns, so revoking
+    // the URL straight away can cancel the download.
+    let revoke = Closure::once_into_js(move || {
+        if let Err(err) = Url::revoke_object_url(&url) {
+            console_logger::warn!("Failed to revoke download URL:", err);
+        }
+    });
+    window.set_timeout_with_callback_and_timeout_and_arguments_0(
+        revoke.unchecked_ref(),
+        REVOKE_DELAY_MS,
+    )?;
+    Ok(())
+}
@@ -8,4 +8,8 @@ extern "C" {
 
     #[wasm_bindgen(js_namespace = window)]
     pub fn geocodeAddress(address: &str) -> Promise;
+
+    /// Fails where the clipboard API isn't available, such as pages served over plain HTTP.
+    #[wasm_bindgen(catch, js_namespace = ["navigator", "clipboard"], js_name = writeText)]
+    pub fn write_clipboard_text(text: &str) -> Result<Promise, JsValue>;
 }
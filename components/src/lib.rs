@@ -1,9 +1,16 @@
 mod analysis_display;
+mod filter_panel;
+mod inventory_table;
 mod js_bindings;
 mod map_view;
+mod marker_style;
+mod search_box;
 
 pub use analysis_display::AnalysisDisplay;
+pub use filter_panel::FilterPanel;
+pub use inventory_table::InventoryTable;
 pub use map_view::MapView;
+pub use search_box::SearchBox;
 
 use data::inventory::TileInventory;
 use gloo_console as console_logger;
@@ -43,12 +50,21 @@ impl Component for InventoryView {
 
         html! {
             <div>
+                <SearchBox
+                    inventory={ctx.props().inventory.clone()}
+                    on_item_select={on_item_select.clone()}
+                />
                 <MapView
                     inventory={ctx.props().inventory.clone()}
                     selected_item={ctx.props().selected_item.clone()}
                     on_item_select={on_item_select.clone()}
                 />
                 <AnalysisDisplay
+                    inventory={ctx.props().inventory.clone()}
+                    selected_item={ctx.props().selected_item.clone()}
+                    on_item_select={on_item_select.clone()}
+                />
+                <InventoryTable
                     inventory={ctx.props().inventory.clone()}
                     selected_item={ctx.props().selected_item.clone()}
                     on_item_select={on_item_select}
@@ -1,17 +1,37 @@
+use crate::marker_style::{self, condition_color};
+use data::boundary::{Boundary, Placement, DEFAULT_EDGE_MARGIN_M};
+use data::cluster::{cluster_inventory, spiderfy, Cluster};
+use data::condition::ConditionLevel;
 use data::inventory::TileInventory;
 use gloo_console as console_logger;
 use leaflet::{
-    Icon, IconOptions, LatLng, Map, MapOptions, Marker, MarkerOptions, Point, TileLayer,
+    DivIcon, DivIconOptions, Icon, LatLng, Map, MapOptions, Marker, MarkerOptions, Point, TileLayer,
 };
 use std::collections::HashMap;
 use wasm_bindgen::{closure::Closure, JsCast};
 use web_sys::{Element, HtmlElement};
 use yew::prelude::*;
 
+/// Markers closer than this on screen are drawn as one cluster. Even the largest marker pins
+/// are under 45 px wide, so at this distance they overlap.
+const CLUSTER_RADIUS_PX: f64 = 40.0;
+
+/// Distance between spiderfied markers along their circle.
+const SPIDER_SPACING_PX: f64 = 50.0;
+
 pub struct MapView {
     map_ref: NodeRef,
     map: Option<Map>,
+    /// One marker per located record, on the map only while it isn't part of a cluster.
     markers: HashMap<u32, Marker>,
+    /// The count markers standing in for clusters at the current zoom, with their click
+    /// handlers so they are dropped together.
+    cluster_markers: Vec<(Marker, Closure<dyn Fn()>)>,
+    /// Member IDs of the cluster spread out around its center. Only set at maximum zoom,
+    /// where zooming in can't separate markers that share an address.
+    spiderfied: Option<Vec<u32>>,
+    zoom_listener: Option<Closure<dyn Fn()>>,
+    boundary: Boundary,
 }
 
 #[derive(Properties, PartialEq)]
@@ -24,6 +44,10 @@ pub struct Props {
 pub enum Msg {
     InitMap,
     UpdateMarkers,
+    /// The zoom changed, so markers have to be regrouped.
+    Recluster,
+    /// A cluster was clicked; carries its member IDs.
+    ClusterClicked(Vec<u32>),
 }
 
 impl Component for MapView {
@@ -40,6 +64,10 @@ impl Component for MapView {
             map_ref: NodeRef::default(),
             map: None,
             markers: HashMap::new(),
+            cluster_markers: Vec::new(),
+            spiderfied: None,
+            zoom_listener: None,
+            boundary: Boundary::french_quarter(),
         }
     }
 
@@ -55,6 +83,14 @@ impl Component for MapView {
                 self.update_markers(ctx);
                 true
             }
+            Msg::Recluster => {
+                self.recluster(ctx);
+                false
+            }
+            Msg::ClusterClicked(ids) => {
+                self.expand_cluster(ctx, ids);
+                false
+            }
         }
     }
 
@@ -64,16 +100,23 @@ impl Component for MapView {
             ctx.props().inventory.len()
         );
         html! {
-            <div ref={self.map_ref.clone()} style="width:100%;height:400px;"></div>
+            <div class="map-container">
+                <div ref={self.map_ref.clone()} style="width:100%;height:400px;"></div>
+                { marker_style::legend() }
+            </div>
         }
     }
 
-    fn changed(&mut self, ctx: &Context<Self>, _old_props: &Self::Properties) -> bool {
+    fn changed(&mut self, ctx: &Context<Self>, old_props: &Self::Properties) -> bool {
         console_logger::log!(
             "MapView changed, updating markers. Inventory count: ",
             ctx.props().inventory.len()
         );
         self.update_markers(ctx);
+        let selected_id = |props: &Props| props.selected_item.as_ref().map(|item| item.id);
+        if selected_id(ctx.props()) != selected_id(old_props) {
+            self.focus_selected(ctx);
+        }
         true
     }
 }
@@ -93,8 +136,14 @@ impl MapView {
         map.set_view(&center, 15.0);
 
         self.add_tile_layer(&map);
+
+        let recluster = ctx.link().callback(|_: ()| Msg::Recluster);
+        let zoom_listener = Closure::wrap(Box::new(move || recluster.emit(())) as Box<dyn Fn()>);
+        map.on("zoomend", zoom_listener.as_ref().unchecked_ref());
+        self.zoom_listener = Some(zoom_listener);
+
         self.map = Some(map);
-        self.add_markers(ctx);
+        self.update_markers(ctx);
         console_logger::log!("Map initialized");
     }
 
@@ -104,157 +153,250 @@ impl MapView {
         tile_layer.add_to(map);
     }
 
-    fn add_markers(&mut self, ctx: &Context<Self>) {
-        if let Some(map) = &self.map {
-            for item in &ctx.props().inventory {
-                // the following is add_marker
-                if let (Some(lat), Some(long)) = (item.latitude, item.longitude) {
-                    let lat_long = LatLng::new(lat, long);
-                    let icon_options = IconOptions::new();
-                    icon_options.set_icon_url("static/markers/marker-icon-blue.png".to_string());
-                    icon_options.set_icon_size(Point::new(50.0, 82.0));
-                    icon_options.set_icon_anchor(Point::new(25.0, 82.0));
-                    icon_options.set_popup_anchor(Point::new(0.0, -82.0));
-                    let icon = Icon::new(&icon_options);
-                    let marker_options = MarkerOptions::new();
-                    marker_options.set_icon(icon);
-                    let marker = Marker::new_with_options(&lat_long, &marker_options);
-
-                    let item_clone = item.clone();
-                    let on_item_select = ctx.props().on_item_select.clone();
-                    let closure = Closure::wrap(Box::new(move || {
-                        console_logger::log!("Marker clicked: ", item_clone.street_sign.clone());
-                        on_item_select.emit(Some(item_clone.clone()));
-                    }) as Box<dyn Fn()>);
-
-                    marker.on("click", closure.as_ref().unchecked_ref());
-                    closure.forget();
-
-                    marker.add_to(map);
-                    console_logger::log!(
-                        "Marker added: ",
-                        item.street_sign.clone(),
-                        " at ",
-                        lat,
-                        ", ",
-                        long
-                    );
-                    self.markers.insert(item.id, marker);
-                } else {
-                    console_logger::log!(
-                        "Skipping marker for ",
-                        item.street_sign.clone(),
-                        " due to missing coordinates"
-                    );
-                }
-                // the above is add_marker
-            }
-        }
+    fn create_marker(
+        &self,
+        ctx: &Context<Self>,
+        item: &TileInventory,
+        lat_long: &LatLng,
+    ) -> Marker {
+        let marker_options = MarkerOptions::new();
+        marker_options.set_icon(self.marker_icon(item, false));
+        let marker = Marker::new_with_options(lat_long, &marker_options);
+
+        let item_clone = item.clone();
+        let on_item_select = ctx.props().on_item_select.clone();
+        let closure = Closure::wrap(Box::new(move || {
+            console_logger::log!("Marker clicked: ", item_clone.street_sign.clone());
+            on_item_select.emit(Some(item_clone.clone()));
+        }) as Box<dyn Fn()>);
+
+        marker.on("click", closure.as_ref().unchecked_ref());
+        closure.forget();
+        marker
+    }
+
+    fn marker_icon(&self, item: &TileInventory, is_selected: bool) -> Icon {
+        marker_style::marker_icon(item, self.is_outside(item), is_selected)
     }
 
-    // keeping the function signatures just in case
-    // fn add_marker(&mut self, ctx: &Context<Self>, map: &Map, item: &TileInventory)
-    // fn update_marker(&mut self, ctx: &Context<Self>, map: &Map, item: &TileInventory)
+    /// Whether the coordinate falls outside the French Quarter and is probably a bad geocode.
+    fn is_outside(&self, item: &TileInventory) -> bool {
+        item.latitude
+            .zip(item.longitude)
+            .map_or(false, |(lat, long)| {
+                self.boundary.classify(lat, long, DEFAULT_EDGE_MARGIN_M) == Placement::Outside
+            })
+    }
 
+    /// Brings the per-record markers in line with the inventory and selection, then
+    /// regroups them.
     fn update_markers(&mut self, ctx: &Context<Self>) {
-        if let Some(map) = &self.map {
-            for item in &ctx.props().inventory {
-                // the following is update_marker
-                if let (Some(lat), Some(long)) = (item.latitude, item.longitude) {
-                    let lat_long = LatLng::new(lat, long);
-
-                    if let Some(marker) = self.markers.get(&item.id) {
-                        marker.set_lat_lng(&lat_long);
-                    } else {
-                        // the following is add_marker
-                        if let (Some(lat), Some(long)) = (item.latitude, item.longitude) {
-                            let lat_long = LatLng::new(lat, long);
-                            let icon_options = IconOptions::new();
-                            icon_options
-                                .set_icon_url("static/markers/marker-icon-blue.png".to_string());
-                            icon_options.set_icon_size(Point::new(50.0, 82.0));
-                            icon_options.set_icon_anchor(Point::new(25.0, 82.0));
-                            icon_options.set_popup_anchor(Point::new(0.0, -82.0));
-                            let icon = Icon::new(&icon_options);
-                            let marker_options = MarkerOptions::new();
-                            marker_options.set_icon(icon);
-                            let marker = Marker::new_with_options(&lat_long, &marker_options);
-
-                            let item_clone = item.clone();
-                            let on_item_select = ctx.props().on_item_select.clone();
-                            let closure = Closure::wrap(Box::new(move || {
-                                console_logger::log!(
-                                    "Marker clicked: ",
-                                    item_clone.street_sign.clone()
-                                );
-                                on_item_select.emit(Some(item_clone.clone()));
-                            })
-                                as Box<dyn Fn()>);
-
-                            marker.on("click", closure.as_ref().unchecked_ref());
-                            closure.forget();
-
-                            marker.add_to(map);
-                            console_logger::log!(
-                                "Marker added: ",
-                                item.street_sign.clone(),
-                                " at ",
-                                lat,
-                                ", ",
-                                long
-                            );
-                            self.markers.insert(item.id, marker);
-                        } else {
-                            console_logger::log!(
-                                "Skipping marker for ",
-                                item.street_sign.clone(),
-                                " due to missing coordinates"
-                            );
-                        }
-                        // the above is add_marker
-                    }
+        if self.map.is_none() {
+            return;
+        }
+        let inventory = &ctx.props().inventory;
+        let selected_id = ctx
+            .props()
+            .selected_item
+            .as_ref()
+            .map(|selected| selected.id);
 
-                    let is_selected = ctx
-                        .props()
-                        .selected_item
-                        .as_ref()
-                        .map_or(false, |selected| selected.id == item.id);
-                    let icon_options = IconOptions::new();
-                    let icon_url = if is_selected {
-                        "static/markers/marker-icon-green.png"
-                    } else {
-                        "static/markers/marker-icon-blue.png"
-                    };
-                    icon_options.set_icon_url(icon_url.to_string());
-                    icon_options.set_icon_size(Point::new(50.0, 82.0));
-                    icon_options.set_icon_anchor(Point::new(25.0, 82.0));
-                    icon_options.set_popup_anchor(Point::new(0.0, -82.0));
-                    let icon = Icon::new(&icon_options);
-
-                    if let Some(marker) = self.markers.get(&item.id) {
-                        marker.set_icon(&icon);
-                    }
+        self.markers.retain(|id, marker| {
+            let located = inventory
+                .iter()
+                .any(|item| item.id == *id && item.latitude.is_some() && item.longitude.is_some());
+            if !located {
+                marker.remove();
+                console_logger::log!("Removed marker for ID ", *id);
+            }
+            located
+        });
 
-                    if is_selected {
-                        console_logger::log!(
-                            "Selected marker: ",
-                            item.street_sign.clone(),
-                            " with icon: ",
-                            icon_url
-                        );
-                    }
+        for item in inventory {
+            let (Some(lat), Some(long)) = (item.latitude, item.longitude) else {
+                console_logger::log!(
+                    "Skipping marker for ",
+                    item.street_sign.clone(),
+                    " due to missing coordinates"
+                );
+                continue;
+            };
+            let lat_long = LatLng::new(lat, long);
+            if !self.markers.contains_key(&item.id) {
+                let marker = self.create_marker(ctx, item, &lat_long);
+                self.markers.insert(item.id, marker);
+            }
+            let is_selected = selected_id == Some(item.id);
+            if let Some(marker) = self.markers.get(&item.id) {
+                marker.set_lat_lng(&lat_long);
+                marker.set_icon(&self.marker_icon(item, is_selected));
+            }
+        }
+
+        self.recluster(ctx);
+    }
+
+    /// Groups overlapping markers at the current zoom. Single markers and a spiderfied
+    /// cluster's members go on the map themselves; every other group gets a count marker.
+    fn recluster(&mut self, ctx: &Context<Self>) {
+        let Some(map) = &self.map else {
+            return;
+        };
+        for (marker, _) in self.cluster_markers.drain(..) {
+            marker.remove();
+        }
+        for marker in self.markers.values() {
+            marker.remove();
+        }
+
+        let zoom = map.get_zoom();
+        if zoom < map.get_max_zoom() {
+            self.spiderfied = None;
+        }
+        let inventory = &ctx.props().inventory;
+        let selected_id = ctx
+            .props()
+            .selected_item
+            .as_ref()
+            .map(|selected| selected.id);
+
+        for cluster in cluster_inventory(inventory, zoom, CLUSTER_RADIUS_PX) {
+            let spread = self.spiderfied.as_ref() == Some(&cluster.ids);
+            if cluster.len() == 1 || spread {
+                let positions = if spread {
+                    spiderfy(
+                        (cluster.latitude, cluster.longitude),
+                        cluster.len(),
+                        zoom,
+                        SPIDER_SPACING_PX,
+                    )
                 } else {
-                    if let Some(marker) = self.markers.remove(&item.id) {
-                        marker.remove();
+                    vec![(cluster.latitude, cluster.longitude)]
+                };
+                for (id, (lat, long)) in cluster.ids.iter().zip(positions) {
+                    if let Some(marker) = self.markers.get(id) {
+                        marker.set_lat_lng(&LatLng::new(lat, long));
+                        marker.add_to(map);
                     }
-                    console_logger::log!(
-                        "Removed marker for ",
-                        item.street_sign.clone(),
-                        " due to missing coordinates"
-                    );
                 }
-                // the above is update_marker
+                continue;
             }
+
+            let level = ConditionLevel::worst(
+                inventory
+                    .iter()
+                    .filter(|item| cluster.ids.contains(&item.id)),
+            );
+            let contains_selected = selected_id.map_or(false, |id| cluster.ids.contains(&id));
+            let marker_options = MarkerOptions::new();
+            marker_options.set_icon(cluster_icon(&cluster, level, contains_selected));
+            let marker = Marker::new_with_options(
+                &LatLng::new(cluster.latitude, cluster.longitude),
+                &marker_options,
+            );
+
+            let on_click = ctx.link().callback(Msg::ClusterClicked);
+            let ids = cluster.ids.clone();
+            let closure =
+                Closure::wrap(Box::new(move || on_click.emit(ids.clone())) as Box<dyn Fn()>);
+            marker.on("click", closure.as_ref().unchecked_ref());
+            marker.add_to(map);
+            self.cluster_markers.push((marker, closure));
+        }
+    }
+
+    /// Zooms in on a clicked cluster, or at maximum zoom spreads its markers out (and
+    /// gathers them back up on a second click).
+    fn expand_cluster(&mut self, ctx: &Context<Self>, ids: Vec<u32>) {
+        let Some(map) = &self.map else {
+            return;
+        };
+        let zoom = map.get_zoom();
+        let max_zoom = map.get_max_zoom();
+        if zoom >= max_zoom {
+            self.spiderfied = if self.spiderfied.as_ref() == Some(&ids) {
+                None
+            } else {
+                Some(ids)
+            };
+            self.recluster(ctx);
+            return;
+        }
+
+        let members: Vec<(f64, f64)> = ctx
+            .props()
+            .inventory
+            .iter()
+            .filter(|item| ids.contains(&item.id))
+            .filter_map(|item| item.latitude.zip(item.longitude))
+            .collect();
+        if members.is_empty() {
+            return;
+        }
+        let count = members.len() as f64;
+        let center = LatLng::new(
+            members.iter().map(|point| point.0).sum::<f64>() / count,
+            members.iter().map(|point| point.1).sum::<f64>() / count,
+        );
+        // The "zoomend" listener regroups the markers once the map gets there.
+        map.set_view(&center, (zoom + 2.0).min(max_zoom));
+    }
+
+    /// Brings the selected sign into view: pans to it, zooming in far enough that it isn't
+    /// hidden in a cluster, or spreading its cluster out when even maximum zoom can't.
+    fn focus_selected(&mut self, ctx: &Context<Self>) {
+        let Some(map) = &self.map else {
+            return;
+        };
+        let Some(item) = &ctx.props().selected_item else {
+            return;
+        };
+        let Some((lat, long)) = item.latitude.zip(item.longitude) else {
+            return;
+        };
+        let inventory = &ctx.props().inventory;
+        let cluster_at = |zoom: f64| {
+            cluster_inventory(inventory, zoom, CLUSTER_RADIUS_PX)
+                .into_iter()
+                .find(|cluster| cluster.ids.contains(&item.id))
+        };
+
+        let zoom = map.get_zoom();
+        let max_zoom = map.get_max_zoom();
+        let mut target = zoom;
+        while target < max_zoom && cluster_at(target).is_some_and(|cluster| cluster.len() > 1) {
+            target += 1.0;
+        }
+        self.spiderfied = cluster_at(target)
+            .filter(|cluster| cluster.len() > 1)
+            .map(|cluster| cluster.ids);
+
+        if target == zoom {
+            map.pan_to(&LatLng::new(lat, long));
+            self.recluster(ctx);
+        } else {
+            // The "zoomend" listener regroups the markers once the map gets there.
+            map.set_view(&LatLng::new(lat, long), target);
         }
     }
 }
+
+/// A round count badge in the color of the worst condition among the cluster's signs.
+fn cluster_icon(cluster: &Cluster, level: ConditionLevel, contains_selected: bool) -> Icon {
+    let size = if cluster.len() < 10 { 36.0 } else { 44.0 };
+    let options = DivIconOptions::new();
+    options.set_html(format!(
+        r#"<div style="background-color: {}" title="{} signs, worst: {}"><span>{}</span></div>"#,
+        condition_color(level),
+        cluster.len(),
+        level,
+        cluster.len()
+    ));
+    options.set_class_name(if contains_selected {
+        "marker-cluster selected".to_string()
+    } else {
+        "marker-cluster".to_string()
+    });
+    options.set_icon_size(Point::new(size, size));
+    DivIcon::new(&options).into()
+}
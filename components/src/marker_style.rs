@@ -0,0 +1,114 @@
+use data::condition::ConditionLevel;
+use data::inventory::TileInventory;
+use leaflet::{DivIcon, DivIconOptions, Icon, Point};
+use yew::prelude::*;
+
+/// Marker height for a sign without damaged tiles.
+const BASE_HEIGHT_PX: f64 = 41.0;
+
+/// Damaged tiles at which a marker reaches its largest size.
+const FULL_DAMAGE_TILES: u32 = 20;
+
+/// How much taller than the base the most damaged signs' markers are.
+const MAX_GROWTH: f64 = 0.6;
+
+/// Colors from green for good through red for falling apart. Signs that aren't a state of
+/// repair get colors off that ramp.
+pub(crate) fn condition_color(level: ConditionLevel) -> &'static str {
+    match level {
+        ConditionLevel::Unknown => "#757575",
+        ConditionLevel::PossibleCopy => "#6a1b9a",
+        ConditionLevel::Good => "#2e7d32",
+        ConditionLevel::Fair => "#9e9d24",
+        ConditionLevel::Faded => "#f9a825",
+        ConditionLevel::Graffiti => "#ef6c00",
+        ConditionLevel::FallingApart => "#c62828",
+        ConditionLevel::Missing => "#212121",
+    }
+}
+
+/// The symbol drawn in the marker's head: a cross for a missing sign, a question mark for a
+/// suspected copy, a dot otherwise.
+fn glyph(level: ConditionLevel) -> Option<&'static str> {
+    match level {
+        ConditionLevel::Missing => Some("✕"),
+        ConditionLevel::PossibleCopy => Some("?"),
+        _ => None,
+    }
+}
+
+/// Marker height, growing with the number of damaged tiles up to `FULL_DAMAGE_TILES`.
+fn marker_height(item: &TileInventory) -> f64 {
+    let damaged = item.number_of_tiles_damaged.unwrap_or_default();
+    let share = damaged.min(FULL_DAMAGE_TILES) as f64 / FULL_DAMAGE_TILES as f64;
+    (BASE_HEIGHT_PX * (1.0 + MAX_GROWTH * share)).round()
+}
+
+/// A pin in the sign's condition color, sized by its damage. `outside` gives it a dashed
+/// outline for a coordinate outside the French Quarter; `selected` a green outline and glow.
+pub(crate) fn marker_icon(item: &TileInventory, outside: bool, selected: bool) -> Icon {
+    let level = ConditionLevel::of(item);
+    let height = marker_height(item);
+    let width = (height * 2.0 / 3.0).round();
+
+    let options = DivIconOptions::new();
+    options.set_html(pin_svg(level, outside, selected));
+    let mut class_name = "tile-marker".to_string();
+    if selected {
+        class_name.push_str(" selected");
+    }
+    options.set_class_name(class_name);
+    options.set_icon_size(Point::new(width, height));
+    options.set_icon_anchor(Point::new(width / 2.0, height));
+    options.set_popup_anchor(Point::new(0.0, -height));
+    DivIcon::new(&options).into()
+}
+
+fn pin_svg(level: ConditionLevel, outside: bool, selected: bool) -> String {
+    let (stroke, stroke_width, dash) = if selected {
+        ("#28a745", 2.5, "")
+    } else if outside {
+        ("#000000", 1.5, r#" stroke-dasharray="3 2""#)
+    } else {
+        ("#ffffff", 1.5, "")
+    };
+    let head = match glyph(level) {
+        Some(glyph) => format!(
+            r##"<text x="12" y="16" text-anchor="middle" font-size="12" font-weight="bold" fill="#ffffff">{}</text>"##,
+            glyph
+        ),
+        None => r##"<circle cx="12" cy="12" r="4" fill="#ffffff"/>"##.to_string(),
+    };
+    format!(
+        r#"<svg viewBox="0 0 24 36" width="100%" height="100%"><path d="M12 1C6 1 1 6 1 12c0 8.5 11 23 11 23s11-14.5 11-23C23 6 18 1 12 1z" fill="{}" stroke="{}" stroke-width="{}"{}/>{}</svg>"#,
+        condition_color(level),
+        stroke,
+        stroke_width,
+        dash,
+        head
+    )
+}
+
+/// The key to marker colors, symbols and sizes, drawn in the map's corner.
+pub(crate) fn legend() -> Html {
+    let levels = ConditionLevel::ALL.iter().rev().map(|&level| {
+        let symbol = glyph(level).unwrap_or("●");
+        html! {
+            <li>
+                <span class="legend-swatch" style={format!("background-color: {}", condition_color(level))}>
+                    { symbol }
+                </span>
+                { level.label() }
+            </li>
+        }
+    });
+    html! {
+        <div class="map-legend leaflet-control">
+            <ul>
+                { for levels }
+                <li><span class="legend-swatch legend-outside"></span>{ "Outside the French Quarter" }</li>
+            </ul>
+            <div class="legend-note">{ "Taller markers have more damaged tiles. Circles count the signs they group, colored by the worst." }</div>
+        </div>
+    }
+}
@@ -0,0 +1,187 @@
+use data::inventory::TileInventory;
+use data::search::{SearchField, SearchHit, SearchIndex};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+/// Results shown in the dropdown.
+const MAX_RESULTS: usize = 8;
+
+/// A search box with a dropdown of ranked matches. Choosing one selects the sign, which pans
+/// the map to it and shows its photos.
+pub struct SearchBox {
+    index: SearchIndex,
+    query: String,
+    hits: Vec<SearchHit>,
+    /// The result the arrow keys have moved to.
+    highlighted: usize,
+    open: bool,
+}
+
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub inventory: Vec<TileInventory>,
+    pub on_item_select: Callback<Option<TileInventory>>,
+}
+
+pub enum Msg {
+    QueryChanged(String),
+    Highlight(usize),
+    MoveHighlight(isize),
+    Choose(usize),
+    ChooseHighlighted,
+    Close,
+}
+
+impl Component for SearchBox {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        Self {
+            index: SearchIndex::new(&ctx.props().inventory),
+            query: String::new(),
+            hits: Vec::new(),
+            highlighted: 0,
+            open: false,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::QueryChanged(query) => {
+                self.query = query;
+                self.search();
+                self.open = !self.query.trim().is_empty();
+                true
+            }
+            Msg::Highlight(index) => {
+                let changed = self.highlighted != index;
+                self.highlighted = index;
+                changed
+            }
+            Msg::MoveHighlight(step) => {
+                if self.hits.is_empty() {
+                    return false;
+                }
+                self.open = true;
+                let count = self.hits.len() as isize;
+                self.highlighted = (self.highlighted as isize + step).rem_euclid(count) as usize;
+                true
+            }
+            Msg::Choose(index) => {
+                self.choose(ctx, index);
+                true
+            }
+            Msg::ChooseHighlighted => {
+                self.choose(ctx, self.highlighted);
+                true
+            }
+            Msg::Close => {
+                let changed = self.open;
+                self.open = false;
+                changed
+            }
+        }
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>, old_props: &Self::Properties) -> bool {
+        if ctx.props().inventory != old_props.inventory {
+            self.index = SearchIndex::new(&ctx.props().inventory);
+            self.search();
+        }
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let onkeydown = ctx.link().batch_callback(|e: KeyboardEvent| {
+            let msg = match e.key().as_str() {
+                "ArrowDown" => Msg::MoveHighlight(1),
+                "ArrowUp" => Msg::MoveHighlight(-1),
+                "Enter" => Msg::ChooseHighlighted,
+                "Escape" => Msg::Close,
+                _ => return None,
+            };
+            e.prevent_default();
+            Some(msg)
+        });
+
+        html! {
+            <div class="search-box">
+                <input
+                    type="search"
+                    class="form-control"
+                    placeholder="Search signs, addresses, streets or occupants"
+                    value={self.query.clone()}
+                    oninput={ctx.link().callback(|e: InputEvent| Msg::QueryChanged(e.target_unchecked_into::<HtmlInputElement>().value()))}
+                    {onkeydown}
+                    onblur={ctx.link().callback(|_| Msg::Close)}
+                />
+                { self.render_results(ctx) }
+            </div>
+        }
+    }
+}
+
+impl SearchBox {
+    fn search(&mut self) {
+        self.hits = self.index.search(&self.query, MAX_RESULTS);
+        self.highlighted = 0;
+    }
+
+    fn choose(&mut self, ctx: &Context<Self>, index: usize) {
+        let Some(hit) = self.hits.get(index) else {
+            return;
+        };
+        let item = ctx
+            .props()
+            .inventory
+            .iter()
+            .find(|item| item.id == hit.id)
+            .cloned();
+        if let Some(item) = &item {
+            self.query = item.street_address.clone();
+        }
+        self.open = false;
+        ctx.props().on_item_select.emit(item);
+    }
+
+    fn render_results(&self, ctx: &Context<Self>) -> Html {
+        if !self.open {
+            return html! {};
+        }
+        if self.hits.is_empty() {
+            return html! {
+                <ul class="search-results"><li class="search-empty">{"No matching signs"}</li></ul>
+            };
+        }
+        let inventory = &ctx.props().inventory;
+        html! {
+            <ul class="search-results">
+                { for self.hits.iter().enumerate().filter_map(|(index, hit)| {
+                    let item = inventory.iter().find(|item| item.id == hit.id)?;
+                    let detail = match hit.field {
+                        SearchField::BuildingOccupant => item.building_occupant.clone(),
+                        _ => None,
+                    };
+                    Some(html! {
+                        <li
+                            class={classes!((index == self.highlighted).then_some("highlighted"))}
+                            // Chosen on mousedown, before the input's blur closes the list.
+                            onmousedown={ctx.link().callback(move |e: MouseEvent| {
+                                e.prevent_default();
+                                Msg::Choose(index)
+                            })}
+                            onmouseenter={ctx.link().callback(move |_| Msg::Highlight(index))}
+                        >
+                            <span class="search-sign">{ &item.street_sign }</span>
+                            <span class="search-address">{ &item.street_address }</span>
+                            if let Some(detail) = detail {
+                                <span class="search-detail">{ detail }</span>
+                            }
+                        </li>
+                    })
+                }) }
+            </ul>
+        }
+    }
+}
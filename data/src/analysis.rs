@@ -1,4 +1,6 @@
 use crate::inventory::TileInventory;
+use serde::Serialize;
+use std::collections::BTreeMap;
 
 pub struct InventoryAnalysis {
     pub total_items: usize,
@@ -20,3 +22,56 @@ pub fn analyze_inventory(inventory: &[TileInventory]) -> InventoryAnalysis {
         average_damaged_tiles,
     }
 }
+
+/// Counts for reporting, beyond what the web app's summary shows.
+#[derive(Debug, Default, Serialize)]
+pub struct InventoryStats {
+    pub total_items: usize,
+    pub total_damaged_tiles: u32,
+    pub average_damaged_tiles: f64,
+    /// Records per sign condition, with "(none)" for records without one.
+    pub by_condition: BTreeMap<String, usize>,
+    pub by_street_sign: BTreeMap<String, usize>,
+    /// Records per geocode match type, with "(unknown)" for coordinates without provenance.
+    pub by_match_type: BTreeMap<String, usize>,
+    pub with_coordinates: usize,
+    pub with_photos: usize,
+    pub total_photos: usize,
+}
+
+pub fn inventory_stats(inventory: &[TileInventory]) -> InventoryStats {
+    let analysis = analyze_inventory(inventory);
+    let mut stats = InventoryStats {
+        total_items: analysis.total_items,
+        total_damaged_tiles: analysis.total_damaged_tiles,
+        average_damaged_tiles: analysis.average_damaged_tiles,
+        ..InventoryStats::default()
+    };
+    for item in inventory {
+        let condition = item
+            .sign_condition
+            .as_deref()
+            .filter(|condition| !condition.is_empty())
+            .unwrap_or("(none)");
+        *stats.by_condition.entry(condition.to_string()).or_default() += 1;
+        *stats
+            .by_street_sign
+            .entry(item.street_sign.clone())
+            .or_default() += 1;
+
+        if item.latitude.is_some() && item.longitude.is_some() {
+            stats.with_coordinates += 1;
+            let match_type = item
+                .geocode_match_type
+                .map_or("(unknown)".to_string(), |match_type| match_type.to_string());
+            *stats.by_match_type.entry(match_type).or_default() += 1;
+        }
+
+        let photos = item.photos().count();
+        if photos > 0 {
+            stats.with_photos += 1;
+        }
+        stats.total_photos += photos;
+    }
+    stats
+}
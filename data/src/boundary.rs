@@ -0,0 +1,343 @@
+use crate::geo::to_local_plane;
+use crate::inventory::TileInventory;
+use serde_json::Value;
+use std::fmt;
+
+/// The French Quarter, bounded by Canal Street, Esplanade Avenue, North Rampart Street and the
+/// river. The corners are approximate, so signs on the boundary streets themselves can fall a
+/// few meters either side of it.
+pub const BUNDLED_BOUNDARY: &str = include_str!("../boundaries/french_quarter.geojson");
+
+/// Records within this many meters of the boundary are reported as near the edge rather than
+/// inside or outside, since a sign across the street from the Quarter is still plausible.
+pub const DEFAULT_EDGE_MARGIN_M: f64 = 50.0;
+
+#[derive(thiserror::Error, Debug)]
+pub enum BoundaryError {
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("no Polygon or MultiPolygon geometry found")]
+    NoPolygon,
+    #[error("malformed coordinates: {0}")]
+    Coordinates(String),
+}
+
+/// Where a coordinate falls relative to a boundary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Placement {
+    Inside,
+    /// Within the edge margin, on either side.
+    NearEdge,
+    Outside,
+}
+
+impl fmt::Display for Placement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Placement::Inside => write!(f, "inside"),
+            Placement::NearEdge => write!(f, "near edge"),
+            Placement::Outside => write!(f, "outside"),
+        }
+    }
+}
+
+/// A polygon's outer ring and any holes, as (latitude, longitude) points.
+#[derive(Debug, Clone, PartialEq)]
+struct Polygon {
+    exterior: Vec<(f64, f64)>,
+    holes: Vec<Vec<(f64, f64)>>,
+}
+
+impl Polygon {
+    fn contains(&self, point: (f64, f64)) -> bool {
+        ring_contains(&self.exterior, point)
+            && !self.holes.iter().any(|hole| ring_contains(hole, point))
+    }
+
+    fn rings(&self) -> impl Iterator<Item = &[(f64, f64)]> {
+        std::iter::once(self.exterior.as_slice()).chain(self.holes.iter().map(Vec::as_slice))
+    }
+}
+
+/// A district outline read from GeoJSON.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Boundary {
+    /// The feature's `name` property, if it has one.
+    pub name: Option<String>,
+    polygons: Vec<Polygon>,
+}
+
+impl Boundary {
+    pub fn french_quarter() -> Self {
+        Self::from_geojson(BUNDLED_BOUNDARY).expect("bundled boundary is valid GeoJSON")
+    }
+
+    /// Reads every Polygon and MultiPolygon in a GeoJSON geometry, Feature or
+    /// FeatureCollection. Coordinates are in GeoJSON's longitude, latitude order.
+    pub fn from_geojson(geojson: &str) -> Result<Self, BoundaryError> {
+        let value: Value = serde_json::from_str(geojson)?;
+        let mut boundary = Boundary {
+            name: None,
+            polygons: Vec::new(),
+        };
+        boundary.collect(&value)?;
+        if boundary.polygons.is_empty() {
+            return Err(BoundaryError::NoPolygon);
+        }
+        Ok(boundary)
+    }
+
+    fn collect(&mut self, value: &Value) -> Result<(), BoundaryError> {
+        match value.get("type").and_then(Value::as_str) {
+            Some("FeatureCollection") => {
+                for feature in value
+                    .get("features")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                {
+                    self.collect(feature)?;
+                }
+            }
+            Some("Feature") => {
+                if self.name.is_none() {
+                    self.name = value
+                        .pointer("/properties/name")
+                        .and_then(Value::as_str)
+                        .map(str::to_string);
+                }
+                if let Some(geometry) = value.get("geometry") {
+                    self.collect(geometry)?;
+                }
+            }
+            Some("GeometryCollection") => {
+                for geometry in value
+                    .get("geometries")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                {
+                    self.collect(geometry)?;
+                }
+            }
+            Some("Polygon") => self.polygons.push(parse_polygon(coordinates(value)?)?),
+            Some("MultiPolygon") => {
+                for polygon in as_array(coordinates(value)?)? {
+                    self.polygons.push(parse_polygon(polygon)?);
+                }
+            }
+            // Points and lines don't enclose anything.
+            _ => {}
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, latitude: f64, longitude: f64) -> bool {
+        self.polygons
+            .iter()
+            .any(|polygon| polygon.contains((latitude, longitude)))
+    }
+
+    /// Meters from the coordinate to the nearest edge of any ring, inside or out.
+    pub fn distance_to_edge_m(&self, latitude: f64, longitude: f64) -> f64 {
+        let origin = (latitude, longitude);
+        self.polygons
+            .iter()
+            .flat_map(Polygon::rings)
+            .flat_map(|ring| ring.windows(2))
+            .map(|edge| {
+                segment_distance(
+                    to_local_plane(origin, edge[0]),
+                    to_local_plane(origin, edge[1]),
+                )
+            })
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    /// Inside or outside, unless the coordinate is within `edge_margin_m` of the boundary.
+    pub fn classify(&self, latitude: f64, longitude: f64, edge_margin_m: f64) -> Placement {
+        if self.distance_to_edge_m(latitude, longitude) <= edge_margin_m {
+            Placement::NearEdge
+        } else if self.contains(latitude, longitude) {
+            Placement::Inside
+        } else {
+            Placement::Outside
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundaryCheck {
+    pub id: u32,
+    pub placement: Placement,
+    /// Meters to the nearest edge.
+    pub distance_m: f64,
+}
+
+/// Classifies every record that has coordinates; records without are left out.
+pub fn classify_inventory(
+    boundary: &Boundary,
+    inventory: &[TileInventory],
+    edge_margin_m: f64,
+) -> Vec<BoundaryCheck> {
+    inventory
+        .iter()
+        .filter_map(|item| {
+            let (latitude, longitude) = item.latitude.zip(item.longitude)?;
+            Some(BoundaryCheck {
+                id: item.id,
+                placement: boundary.classify(latitude, longitude, edge_margin_m),
+                distance_m: boundary.distance_to_edge_m(latitude, longitude),
+            })
+        })
+        .collect()
+}
+
+fn coordinates(geometry: &Value) -> Result<&Value, BoundaryError> {
+    geometry
+        .get("coordinates")
+        .ok_or_else(|| BoundaryError::Coordinates("geometry has no coordinates".to_string()))
+}
+
+fn as_array(value: &Value) -> Result<&Vec<Value>, BoundaryError> {
+    value
+        .as_array()
+        .ok_or_else(|| BoundaryError::Coordinates(format!("expected an array, found {}", value)))
+}
+
+fn parse_polygon(value: &Value) -> Result<Polygon, BoundaryError> {
+    let mut rings = as_array(value)?
+        .iter()
+        .map(parse_ring)
+        .collect::<Result<Vec<_>, _>>()?;
+    if rings.is_empty() {
+        return Err(BoundaryError::Coordinates(
+            "polygon has no rings".to_string(),
+        ));
+    }
+    let exterior = rings.remove(0);
+    Ok(Polygon {
+        exterior,
+        holes: rings,
+    })
+}
+
+fn parse_ring(value: &Value) -> Result<Vec<(f64, f64)>, BoundaryError> {
+    let mut ring = as_array(value)?
+        .iter()
+        .map(|position| {
+            match as_array(position)?.as_slice() {
+                [longitude, latitude, ..] => longitude.as_f64().zip(latitude.as_f64()),
+                _ => None,
+            }
+            .map(|(longitude, latitude)| (latitude, longitude))
+            .ok_or_else(|| BoundaryError::Coordinates(format!("bad position {}", position)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if ring.len() < 3 {
+        return Err(BoundaryError::Coordinates(
+            "ring needs at least three positions".to_string(),
+        ));
+    }
+    // GeoJSON rings repeat the first position at the end, but don't rely on it.
+    if ring.first() != ring.last() {
+        ring.push(ring[0]);
+    }
+    Ok(ring)
+}
+
+/// Even-odd ray casting along the line of latitude through `point`.
+fn ring_contains(ring: &[(f64, f64)], (latitude, longitude): (f64, f64)) -> bool {
+    let mut inside = false;
+    for edge in ring.windows(2) {
+        let ((lat_a, lon_a), (lat_b, lon_b)) = (edge[0], edge[1]);
+        if (lat_a > latitude) != (lat_b > latitude) {
+            let crossing = lon_a + (latitude - lat_a) / (lat_b - lat_a) * (lon_b - lon_a);
+            if longitude < crossing {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+/// Distance from the plane's origin to the segment from `a` to `b`.
+fn segment_distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length_sq = dx * dx + dy * dy;
+    let t = if length_sq == 0.0 {
+        0.0
+    } else {
+        (-(a.0 * dx + a.1 * dy) / length_sq).clamp(0.0, 1.0)
+    };
+    (a.0 + t * dx).hypot(a.1 + t * dy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A square about a kilometer across with a 200 m square hole in the middle.
+    const SQUARE_WITH_HOLE: &str = r#"{
+        "type": "Feature",
+        "properties": { "name": "Square" },
+        "geometry": {
+            "type": "Polygon",
+            "coordinates": [
+                [[-90.07, 29.95], [-90.06, 29.95], [-90.06, 29.96], [-90.07, 29.96], [-90.07, 29.95]],
+                [[-90.066, 29.954], [-90.064, 29.954], [-90.064, 29.956], [-90.066, 29.956], [-90.066, 29.954]]
+            ]
+        }
+    }"#;
+
+    #[test]
+    fn classifies_against_the_edge_margin() {
+        let boundary = Boundary::from_geojson(SQUARE_WITH_HOLE).unwrap();
+        assert_eq!(boundary.name.as_deref(), Some("Square"));
+        assert_eq!(boundary.classify(29.952, -90.068, 50.0), Placement::Inside);
+        assert_eq!(boundary.classify(29.94, -90.065, 50.0), Placement::Outside);
+        // About 11 m inside the southern edge.
+        assert_eq!(
+            boundary.classify(29.9501, -90.065, 50.0),
+            Placement::NearEdge
+        );
+        assert_eq!(boundary.classify(29.9501, -90.065, 5.0), Placement::Inside);
+        // About 11 m outside it.
+        assert_eq!(
+            boundary.classify(29.9499, -90.065, 50.0),
+            Placement::NearEdge
+        );
+        assert_eq!(boundary.classify(29.9499, -90.065, 5.0), Placement::Outside);
+    }
+
+    #[test]
+    fn holes_are_outside() {
+        let boundary = Boundary::from_geojson(SQUARE_WITH_HOLE).unwrap();
+        assert!(!boundary.contains(29.955, -90.065));
+        assert_eq!(boundary.classify(29.955, -90.065, 50.0), Placement::Outside);
+    }
+
+    #[test]
+    fn french_quarter() {
+        let boundary = Boundary::french_quarter();
+        // Jackson Square.
+        assert_eq!(
+            boundary.classify(29.9574, -90.0629, DEFAULT_EDGE_MARGIN_M),
+            Placement::Inside
+        );
+        // Metairie.
+        assert_eq!(
+            boundary.classify(29.984, -90.153, DEFAULT_EDGE_MARGIN_M),
+            Placement::Outside
+        );
+    }
+
+    #[test]
+    fn rejects_geometry_without_polygons() {
+        let point = r#"{ "type": "Point", "coordinates": [-90.06, 29.95] }"#;
+        assert!(matches!(
+            Boundary::from_geojson(point),
+            Err(BoundaryError::NoPolygon)
+        ));
+    }
+}
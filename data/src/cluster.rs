@@ -0,0 +1,197 @@
+use crate::inventory::TileInventory;
+use std::f64::consts::PI;
+
+/// Leaflet's tile size; at zoom `z` the world is `TILE_SIZE * 2^z` pixels wide.
+const TILE_SIZE: f64 = 256.0;
+
+/// Markers close enough on screen to be drawn as one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cluster {
+    /// Member record IDs, in inventory order.
+    pub ids: Vec<u32>,
+    /// Mean position of the members.
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl Cluster {
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+}
+
+/// Groups located records whose markers would land within `radius_px` screen pixels of a
+/// cluster's first member at `zoom`. Records without coordinates are left out. Walking the
+/// inventory in order keeps clusters stable between redraws.
+pub fn cluster_inventory(inventory: &[TileInventory], zoom: f64, radius_px: f64) -> Vec<Cluster> {
+    struct Group {
+        anchor: (f64, f64),
+        points: Vec<(f64, f64)>,
+        ids: Vec<u32>,
+    }
+
+    let mut groups: Vec<Group> = Vec::new();
+    for item in inventory {
+        let Some((latitude, longitude)) = item.latitude.zip(item.longitude) else {
+            continue;
+        };
+        let (x, y) = project(latitude, longitude, zoom);
+        let near = groups
+            .iter_mut()
+            .find(|group| (x - group.anchor.0).hypot(y - group.anchor.1) <= radius_px);
+        match near {
+            Some(group) => {
+                group.points.push((latitude, longitude));
+                group.ids.push(item.id);
+            }
+            None => groups.push(Group {
+                anchor: (x, y),
+                points: vec![(latitude, longitude)],
+                ids: vec![item.id],
+            }),
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|group| {
+            let count = group.points.len() as f64;
+            Cluster {
+                ids: group.ids,
+                latitude: group.points.iter().map(|point| point.0).sum::<f64>() / count,
+                longitude: group.points.iter().map(|point| point.1).sum::<f64>() / count,
+            }
+        })
+        .collect()
+}
+
+/// Positions on a circle around `center` for `count` markers that can't be separated by
+/// zooming in, spaced about `spacing_px` screen pixels apart at `zoom`.
+pub fn spiderfy(center: (f64, f64), count: usize, zoom: f64, spacing_px: f64) -> Vec<(f64, f64)> {
+    let (center_x, center_y) = project(center.0, center.1, zoom);
+    // Leave room for a couple more markers than there are, so small groups aren't cramped.
+    let radius = spacing_px * (count + 2) as f64 / (2.0 * PI);
+    (0..count)
+        .map(|index| {
+            let angle = 2.0 * PI * index as f64 / count as f64;
+            unproject(
+                center_x + radius * angle.cos(),
+                center_y + radius * angle.sin(),
+                zoom,
+            )
+        })
+        .collect()
+}
+
+/// Web Mercator pixel coordinates of a point at `zoom`, as Leaflet lays out its tiles.
+fn project(latitude: f64, longitude: f64, zoom: f64) -> (f64, f64) {
+    let scale = TILE_SIZE * zoom.exp2();
+    let sin = latitude.to_radians().sin();
+    (
+        (longitude + 180.0) / 360.0 * scale,
+        (0.5 - ((1.0 + sin) / (1.0 - sin)).ln() / (4.0 * PI)) * scale,
+    )
+}
+
+fn unproject(x: f64, y: f64, zoom: f64) -> (f64, f64) {
+    let scale = TILE_SIZE * zoom.exp2();
+    let n = PI - 2.0 * PI * y / scale;
+    (n.sinh().atan().to_degrees(), x / scale * 360.0 - 180.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn located(id: u32, coordinate: Option<(f64, f64)>) -> TileInventory {
+        TileInventory {
+            latitude: coordinate.map(|(latitude, _)| latitude),
+            longitude: coordinate.map(|(_, longitude)| longitude),
+            ..TileInventory::new(id, "Rue Bourbon", "700 Bourbon St")
+        }
+    }
+
+    /// Three signs along one block of Bourbon, about 20 m apart, a record that was never
+    /// geocoded and one a few blocks away.
+    fn inventory() -> Vec<TileInventory> {
+        vec![
+            located(1, Some((29.9584, -90.0644))),
+            located(2, None),
+            located(3, Some((29.9584, -90.0640))),
+            located(4, Some((29.9600, -90.0600))),
+            located(5, Some((29.9584, -90.0642))),
+        ]
+    }
+
+    fn ids(clusters: &[Cluster]) -> Vec<Vec<u32>> {
+        clusters.iter().map(|cluster| cluster.ids.clone()).collect()
+    }
+
+    #[test]
+    fn groups_nearby_markers_when_zoomed_out() {
+        // About 4 m a pixel, so the block fits in 20 px.
+        let clusters = cluster_inventory(&inventory(), 15.0, 20.0);
+        assert_eq!(ids(&clusters), [vec![1, 3, 5], vec![4]]);
+        assert_eq!(clusters[0].len(), 3);
+        assert!((clusters[0].latitude - 29.9584).abs() < 1e-9);
+        assert!((clusters[0].longitude - -90.0642).abs() < 1e-9);
+        assert_eq!(
+            (clusters[1].latitude, clusters[1].longitude),
+            (29.9600, -90.0600)
+        );
+    }
+
+    #[test]
+    fn separates_them_when_zoomed_in() {
+        // About half a meter a pixel, so 20 m is 40 px.
+        let clusters = cluster_inventory(&inventory(), 18.0, 20.0);
+        assert_eq!(ids(&clusters), [vec![1], vec![3], vec![4], vec![5]]);
+        assert!(clusters.iter().all(|cluster| cluster.len() == 1));
+    }
+
+    #[test]
+    fn skips_records_without_coordinates() {
+        let unlocated = [located(1, None), located(2, Some((29.9584, -90.0644)))];
+        assert_eq!(ids(&cluster_inventory(&unlocated, 15.0, 20.0)), [vec![2]]);
+        assert!(cluster_inventory(&[located(1, None)], 15.0, 20.0).is_empty());
+    }
+
+    #[test]
+    fn members_keep_inventory_order() {
+        let mut reversed = inventory();
+        reversed.reverse();
+        let clusters = cluster_inventory(&reversed, 15.0, 20.0);
+        assert_eq!(ids(&clusters), [vec![5, 3, 1], vec![4]]);
+        assert_eq!(cluster_inventory(&reversed, 15.0, 20.0), clusters);
+    }
+
+    #[test]
+    fn projection_round_trips() {
+        assert_eq!(project(0.0, 0.0, 0.0), (128.0, 128.0));
+        assert_eq!(project(0.0, 180.0, 1.0).0, 512.0);
+        for zoom in [0.0, 12.0, 15.5, 19.0] {
+            let (x, y) = project(29.9584, -90.0644, zoom);
+            let (latitude, longitude) = unproject(x, y, zoom);
+            assert!((latitude - 29.9584).abs() < 1e-9, "{}", latitude);
+            assert!((longitude - -90.0644).abs() < 1e-9, "{}", longitude);
+        }
+    }
+
+    #[test]
+    fn spiderfies_onto_a_circle() {
+        let center = (29.9584, -90.0644);
+        let positions = spiderfy(center, 4, 18.0, 30.0);
+        assert_eq!(positions.len(), 4);
+        let (center_x, center_y) = project(center.0, center.1, 18.0);
+        let expected = 30.0 * 6.0 / (2.0 * PI);
+        for (latitude, longitude) in positions {
+            let (x, y) = project(latitude, longitude, 18.0);
+            let radius = (x - center_x).hypot(y - center_y);
+            assert!((radius - expected).abs() < 1e-6, "{}", radius);
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use crate::inventory::TileInventory;
+use crate::validation::canonical_condition;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A sign's survey condition as a level, ordered from least to most in need of repair.
+///
+/// Suspected copies aren't a state of repair, so they sort with signs whose condition is
+/// unknown, below "Good Condition". A missing sign is the worst case; the survey form has no
+/// such condition, so it is only recognized here, for signs noted as "Missing" since.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConditionLevel {
+    Unknown,
+    PossibleCopy,
+    Good,
+    Fair,
+    Faded,
+    Graffiti,
+    FallingApart,
+    Missing,
+}
+
+impl ConditionLevel {
+    pub const ALL: [ConditionLevel; 8] = [
+        ConditionLevel::Unknown,
+        ConditionLevel::PossibleCopy,
+        ConditionLevel::Good,
+        ConditionLevel::Fair,
+        ConditionLevel::Faded,
+        ConditionLevel::Graffiti,
+        ConditionLevel::FallingApart,
+        ConditionLevel::Missing,
+    ];
+
+    /// Reads any spelling `canonical_condition` accepts, and "Missing"; anything else is
+    /// `Unknown`.
+    pub fn from_condition(condition: Option<&str>) -> Self {
+        if condition.is_some_and(|condition| condition.trim().eq_ignore_ascii_case("missing")) {
+            return ConditionLevel::Missing;
+        }
+        match condition.and_then(canonical_condition) {
+            Some("Good Condition") => ConditionLevel::Good,
+            Some("Fair Condition") => ConditionLevel::Fair,
+            Some("Faded/Cracked Polish") => ConditionLevel::Faded,
+            Some("Graffiti") => ConditionLevel::Graffiti,
+            Some("Falling Apart") => ConditionLevel::FallingApart,
+            Some(_) => ConditionLevel::PossibleCopy,
+            None => ConditionLevel::Unknown,
+        }
+    }
+
+    pub fn of(item: &TileInventory) -> Self {
+        Self::from_condition(item.sign_condition.as_deref())
+    }
+
+    /// The worst level among `items`, or `Unknown` for none.
+    pub fn worst<'a>(items: impl IntoIterator<Item = &'a TileInventory>) -> Self {
+        items
+            .into_iter()
+            .map(Self::of)
+            .max()
+            .unwrap_or(ConditionLevel::Unknown)
+    }
+
+    /// A short lowercase name for URLs, the same as the serialized form.
+    pub fn slug(self) -> &'static str {
+        match self {
+            ConditionLevel::Unknown => "unknown",
+            ConditionLevel::PossibleCopy => "possible_copy",
+            ConditionLevel::Good => "good",
+            ConditionLevel::Fair => "fair",
+            ConditionLevel::Faded => "faded",
+            ConditionLevel::Graffiti => "graffiti",
+            ConditionLevel::FallingApart => "falling_apart",
+            ConditionLevel::Missing => "missing",
+        }
+    }
+
+    pub fn from_slug(slug: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|level| level.slug() == slug)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ConditionLevel::Unknown => "Not recorded",
+            ConditionLevel::PossibleCopy => "Possible non-Spanish copy",
+            ConditionLevel::Good => "Good",
+            ConditionLevel::Fair => "Fair",
+            ConditionLevel::Faded => "Faded/cracked polish",
+            ConditionLevel::Graffiti => "Graffiti",
+            ConditionLevel::FallingApart => "Falling apart",
+            ConditionLevel::Missing => "Missing",
+        }
+    }
+}
+
+impl fmt::Display for ConditionLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
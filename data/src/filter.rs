@@ -0,0 +1,257 @@
+use crate::condition::ConditionLevel;
+use crate::inventory::TileInventory;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Criteria narrowing the inventory. An empty set or `None` doesn't restrict anything, so the
+/// default filter keeps every record.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InventoryFilter {
+    /// Condition levels to keep; aliases and misspellings count as the level they stand for.
+    pub conditions: BTreeSet<ConditionLevel>,
+    /// Street signs to keep, as spelled in the inventory.
+    pub streets: BTreeSet<String>,
+    /// Inclusive bounds on the number of damaged tiles. Records without a count are left out
+    /// once either bound is set.
+    pub min_damaged: Option<u32>,
+    pub max_damaged: Option<u32>,
+    pub wall_type: Option<String>,
+    pub grout_condition: Option<String>,
+    pub has_photos: bool,
+    pub has_coordinates: bool,
+}
+
+impl InventoryFilter {
+    /// Whether the filter keeps every record.
+    pub fn is_empty(&self) -> bool {
+        *self == InventoryFilter::default()
+    }
+
+    pub fn matches(&self, item: &TileInventory) -> bool {
+        if !self.conditions.is_empty() && !self.conditions.contains(&ConditionLevel::of(item)) {
+            return false;
+        }
+        if !self.streets.is_empty() && !self.streets.contains(item.street_sign.trim()) {
+            return false;
+        }
+        if self.min_damaged.is_some() || self.max_damaged.is_some() {
+            let Some(damaged) = item.number_of_tiles_damaged else {
+                return false;
+            };
+            if self.min_damaged.is_some_and(|min| damaged < min)
+                || self.max_damaged.is_some_and(|max| damaged > max)
+            {
+                return false;
+            }
+        }
+        if !matches_text(self.wall_type.as_deref(), item.wall_type.as_deref())
+            || !matches_text(
+                self.grout_condition.as_deref(),
+                item.grout_condition.as_deref(),
+            )
+        {
+            return false;
+        }
+        if self.has_photos && item.photos().next().is_none() {
+            return false;
+        }
+        if self.has_coordinates && (item.latitude.is_none() || item.longitude.is_none()) {
+            return false;
+        }
+        true
+    }
+
+    /// The records the filter keeps, in inventory order.
+    pub fn apply(&self, inventory: &[TileInventory]) -> Vec<TileInventory> {
+        inventory
+            .iter()
+            .filter(|item| self.matches(item))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Survey values are typed by hand, so case and surrounding spaces are ignored.
+fn matches_text(wanted: Option<&str>, value: Option<&str>) -> bool {
+    match wanted {
+        None => true,
+        Some(wanted) => value.is_some_and(|value| value.trim().eq_ignore_ascii_case(wanted.trim())),
+    }
+}
+
+/// The values an inventory offers for each filter, to build the filter's controls from.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FilterOptions {
+    pub streets: Vec<String>,
+    pub wall_types: Vec<String>,
+    pub grout_conditions: Vec<String>,
+    /// The most damaged tiles on any sign, the top of the damage range.
+    pub max_damaged: u32,
+}
+
+impl FilterOptions {
+    pub fn from_inventory(inventory: &[TileInventory]) -> Self {
+        let distinct = |field: fn(&TileInventory) -> Option<&String>| -> Vec<String> {
+            inventory
+                .iter()
+                .filter_map(field)
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect()
+        };
+        FilterOptions {
+            streets: distinct(|item| Some(&item.street_sign)),
+            wall_types: distinct(|item| item.wall_type.as_ref()),
+            grout_conditions: distinct(|item| item.grout_condition.as_ref()),
+            max_damaged: inventory
+                .iter()
+                .filter_map(|item| item.number_of_tiles_damaged)
+                .max()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn inventory() -> Vec<TileInventory> {
+        vec![
+            TileInventory {
+                sign_condition: Some("Good Condition".to_string()),
+                number_of_tiles_damaged: Some(0),
+                wall_type: Some("Brick".to_string()),
+                grout_condition: Some("Intact".to_string()),
+                photo_1: Some(PathBuf::from("Inventory_Images/1.Photo 1.134027.jpg")),
+                latitude: Some(29.9584),
+                longitude: Some(-90.0644),
+                ..TileInventory::new(1, "Rue Bourbon", "700 Bourbon St")
+            },
+            TileInventory {
+                sign_condition: Some("grafitti".to_string()),
+                number_of_tiles_damaged: Some(4),
+                wall_type: Some(" brick ".to_string()),
+                ..TileInventory::new(2, " Calle Real ", "301 Royal St")
+            },
+            TileInventory {
+                sign_condition: Some("Falling Apart".to_string()),
+                number_of_tiles_damaged: Some(11),
+                wall_type: Some("Stucco".to_string()),
+                grout_condition: Some("Missing".to_string()),
+                photo_3: Some(PathBuf::from("Inventory_Images/3.Photo 3.101500.jpg")),
+                ..TileInventory::new(3, "Rue Bourbon", "900 Bourbon St")
+            },
+            TileInventory::new(4, "Calle de Toulouse", "500 Toulouse St"),
+        ]
+    }
+
+    fn kept(filter: &InventoryFilter) -> Vec<u32> {
+        filter
+            .apply(&inventory())
+            .iter()
+            .map(|item| item.id)
+            .collect()
+    }
+
+    #[test]
+    fn the_empty_filter_keeps_everything() {
+        let filter = InventoryFilter::default();
+        assert!(filter.is_empty());
+        assert_eq!(kept(&filter), [1, 2, 3, 4]);
+        assert!(filter.apply(&[]).is_empty());
+        assert!(!InventoryFilter {
+            has_photos: true,
+            ..InventoryFilter::default()
+        }
+        .is_empty());
+    }
+
+    #[test]
+    fn each_criterion_alone() {
+        let conditions = InventoryFilter {
+            // "grafitti" is an alias, and a missing condition is `Unknown`.
+            conditions: [ConditionLevel::Graffiti, ConditionLevel::Unknown].into(),
+            ..InventoryFilter::default()
+        };
+        assert_eq!(kept(&conditions), [2, 4]);
+
+        let streets = InventoryFilter {
+            streets: ["Calle Real".to_string()].into(),
+            ..InventoryFilter::default()
+        };
+        assert_eq!(kept(&streets), [2]);
+
+        let at_least = InventoryFilter {
+            min_damaged: Some(4),
+            ..InventoryFilter::default()
+        };
+        assert_eq!(kept(&at_least), [2, 3]);
+        let at_most = InventoryFilter {
+            max_damaged: Some(4),
+            ..InventoryFilter::default()
+        };
+        // Record 4 has no count, so it can't be in range.
+        assert_eq!(kept(&at_most), [1, 2]);
+
+        let walls = InventoryFilter {
+            wall_type: Some("BRICK".to_string()),
+            ..InventoryFilter::default()
+        };
+        assert_eq!(kept(&walls), [1, 2]);
+        let grout = InventoryFilter {
+            grout_condition: Some("missing".to_string()),
+            ..InventoryFilter::default()
+        };
+        assert_eq!(kept(&grout), [3]);
+
+        let photos = InventoryFilter {
+            has_photos: true,
+            ..InventoryFilter::default()
+        };
+        assert_eq!(kept(&photos), [1, 3]);
+        let located = InventoryFilter {
+            has_coordinates: true,
+            ..InventoryFilter::default()
+        };
+        assert_eq!(kept(&located), [1]);
+    }
+
+    #[test]
+    fn criteria_combine_with_and() {
+        let filter = InventoryFilter {
+            streets: ["Rue Bourbon".to_string()].into(),
+            min_damaged: Some(1),
+            max_damaged: Some(20),
+            has_photos: true,
+            ..InventoryFilter::default()
+        };
+        assert_eq!(kept(&filter), [3]);
+
+        let nothing = InventoryFilter {
+            has_coordinates: true,
+            conditions: [ConditionLevel::FallingApart].into(),
+            ..InventoryFilter::default()
+        };
+        assert!(kept(&nothing).is_empty());
+    }
+
+    #[test]
+    fn options_list_distinct_trimmed_values() {
+        let options = FilterOptions::from_inventory(&inventory());
+        assert_eq!(
+            options.streets,
+            ["Calle Real", "Calle de Toulouse", "Rue Bourbon"]
+        );
+        // Case differences are kept; matching ignores them.
+        assert_eq!(options.wall_types, ["Brick", "Stucco", "brick"]);
+        assert_eq!(options.grout_conditions, ["Intact", "Missing"]);
+        assert_eq!(options.max_damaged, 11);
+        assert_eq!(FilterOptions::from_inventory(&[]), FilterOptions::default());
+    }
+}
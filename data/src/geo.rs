@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const EARTH_RADIUS_M: f64 = 6_371_008.8;
+
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// Where a coordinate came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CoordinateSource {
+    /// Looked up from the street address.
+    Geocoded,
+    /// Read from the GPS tags of the survey photos.
+    Exif,
+}
+
+impl fmt::Display for CoordinateSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoordinateSource::Geocoded => write!(f, "geocoded"),
+            CoordinateSource::Exif => write!(f, "exif"),
+        }
+    }
+}
+
+/// How precisely a geocoder's match pins down the address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchType {
+    /// The building itself.
+    House,
+    /// Somewhere along the street, usually its midpoint.
+    Street,
+    /// A named place such as Jackson Square.
+    Landmark,
+    /// The corner of two streets, where the signs are mounted, found on approximate street
+    /// centerlines.
+    Intersection,
+    /// Placed by hand from a coordinate override.
+    Manual,
+}
+
+impl MatchType {
+    /// How the coordinate should be described to a reader.
+    pub fn accuracy_label(&self) -> &'static str {
+        match self {
+            MatchType::House => "exact (house)",
+            MatchType::Street => "approximate (street-level)",
+            MatchType::Landmark => "approximate (landmark)",
+            MatchType::Intersection => "approximate (street corner)",
+            MatchType::Manual => "exact (hand-placed)",
+        }
+    }
+}
+
+impl fmt::Display for MatchType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatchType::House => write!(f, "house"),
+            MatchType::Street => write!(f, "street"),
+            MatchType::Landmark => write!(f, "landmark"),
+            MatchType::Intersection => write!(f, "intersection"),
+            MatchType::Manual => write!(f, "manual"),
+        }
+    }
+}
+
+/// Provenance of a geocoded coordinate: who matched it, to what, and how sure they were.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GeocodeResult {
+    /// Short provider name, e.g. "nominatim".
+    pub provider: String,
+    /// The provider's name for what it matched, e.g. "813, Bienville Street, French Quarter, ...".
+    pub display_name: Option<String>,
+    pub match_type: MatchType,
+    /// 0.0 to 1.0.
+    pub confidence: f64,
+    /// Seconds since the Unix epoch when the provider answered.
+    pub timestamp: u64,
+}
+
+/// Great-circle distance in meters between two `(latitude, longitude)` points.
+pub fn haversine_distance_m(a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (lat1, lon1) = (a.0.to_radians(), a.1.to_radians());
+    let (lat2, lon2) = (b.0.to_radians(), b.1.to_radians());
+    let h = ((lat2 - lat1) / 2.0).sin().powi(2)
+        + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_M * h.sqrt().asin()
+}
+
+/// Local equirectangular projection of `point` in meters east and north of `origin`; plenty
+/// for a district.
+pub fn to_local_plane(origin: (f64, f64), point: (f64, f64)) -> (f64, f64) {
+    let scale = origin.0.to_radians().cos();
+    (
+        (point.1 - origin.1) * scale * METERS_PER_DEGREE,
+        (point.0 - origin.0) * METERS_PER_DEGREE,
+    )
+}
+
+/// Undoes `to_local_plane`.
+pub fn from_local_plane(origin: (f64, f64), (x, y): (f64, f64)) -> (f64, f64) {
+    let scale = origin.0.to_radians().cos();
+    (
+        origin.0 + y / METERS_PER_DEGREE,
+        origin.1 + x / (scale * METERS_PER_DEGREE),
+    )
+}
+
+/// Component-wise median of `(latitude, longitude)` points, which ignores a single stray
+/// fix better than the mean does.
+pub fn median_coordinate(points: &[(f64, f64)]) -> Option<(f64, f64)> {
+    fn median(mut values: Vec<f64>) -> f64 {
+        values.sort_by(f64::total_cmp);
+        let mid = values.len() / 2;
+        if values.len().is_multiple_of(2) {
+            (values[mid - 1] + values[mid]) / 2.0
+        } else {
+            values[mid]
+        }
+    }
+
+    if points.is_empty() {
+        return None;
+    }
+    Some((
+        median(points.iter().map(|point| point.0).collect()),
+        median(points.iter().map(|point| point.1).collect()),
+    ))
+}
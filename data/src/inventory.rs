@@ -1,8 +1,9 @@
+use crate::geo::{GeocodeResult, MatchType};
 use serde::{Deserialize, Serialize};
 use std::hash::{Hash, Hasher};
 use std::path::PathBuf;
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
 pub struct TileInventory {
     #[serde(rename = "ID")]
     pub id: u32,
@@ -14,6 +15,15 @@ pub struct TileInventory {
     pub sign_condition: Option<String>,
     #[serde(rename = "Number of Tiles Damaged")]
     pub number_of_tiles_damaged: Option<u32>,
+    /// Survey columns that older exports of the inventory leave out.
+    #[serde(rename = "Grout Condition", default)]
+    pub grout_condition: Option<String>,
+    #[serde(rename = "Type of Wall", default)]
+    pub wall_type: Option<String>,
+    #[serde(rename = "Building Occupant", default)]
+    pub building_occupant: Option<String>,
+    #[serde(rename = "Title Owner on Builder", default)]
+    pub title_owner: Option<String>,
     #[serde(rename = "Photo 1", deserialize_with = "deserialize_optional_path")]
     pub photo_1: Option<PathBuf>,
     #[serde(rename = "Photo 2", deserialize_with = "deserialize_optional_path")]
@@ -28,6 +38,84 @@ pub struct TileInventory {
     pub latitude: Option<f64>,
     #[serde(default)]
     pub longitude: Option<f64>,
+    /// Median of the photos' GPS tags, kept beside the geocoded `latitude`/`longitude`.
+    #[serde(default)]
+    pub exif_latitude: Option<f64>,
+    #[serde(default)]
+    pub exif_longitude: Option<f64>,
+    /// Provenance of `latitude`/`longitude`, flattened into columns; see `geocode_result`.
+    #[serde(default)]
+    pub geocode_provider: Option<String>,
+    #[serde(default)]
+    pub geocode_display_name: Option<String>,
+    #[serde(default)]
+    pub geocode_match_type: Option<MatchType>,
+    #[serde(default)]
+    pub geocode_confidence: Option<f64>,
+    #[serde(default)]
+    pub geocode_timestamp: Option<u64>,
+    /// Free-form remarks from volunteers, e.g. "sign partly hidden by a balcony".
+    #[serde(rename = "Notes", default)]
+    pub notes: Option<String>,
+}
+
+impl TileInventory {
+    /// A record with just its identifying columns filled in.
+    pub fn new(id: u32, street_sign: impl Into<String>, street_address: impl Into<String>) -> Self {
+        Self {
+            id,
+            street_sign: street_sign.into(),
+            street_address: street_address.into(),
+            ..Self::default()
+        }
+    }
+
+    /// The five photo columns in order, including empty slots.
+    pub fn photo_slots(&self) -> [&Option<PathBuf>; 5] {
+        [
+            &self.photo_1,
+            &self.photo_2,
+            &self.photo_3,
+            &self.photo_4,
+            &self.photo_5,
+        ]
+    }
+
+    pub fn photo_slots_mut(&mut self) -> [&mut Option<PathBuf>; 5] {
+        [
+            &mut self.photo_1,
+            &mut self.photo_2,
+            &mut self.photo_3,
+            &mut self.photo_4,
+            &mut self.photo_5,
+        ]
+    }
+
+    /// Referenced photo paths in column order, skipping empty slots.
+    pub fn photos(&self) -> impl Iterator<Item = &PathBuf> {
+        self.photo_slots().into_iter().flatten()
+    }
+
+    /// How the coordinates were obtained, if they were geocoded with provenance tracking.
+    pub fn geocode_result(&self) -> Option<GeocodeResult> {
+        Some(GeocodeResult {
+            provider: self.geocode_provider.clone()?,
+            display_name: self.geocode_display_name.clone(),
+            match_type: self.geocode_match_type?,
+            confidence: self.geocode_confidence.unwrap_or_default(),
+            timestamp: self.geocode_timestamp.unwrap_or_default(),
+        })
+    }
+
+    pub fn set_geocode_result(&mut self, result: Option<GeocodeResult>) {
+        self.geocode_provider = result.as_ref().map(|result| result.provider.clone());
+        self.geocode_display_name = result
+            .as_ref()
+            .and_then(|result| result.display_name.clone());
+        self.geocode_match_type = result.as_ref().map(|result| result.match_type);
+        self.geocode_confidence = result.as_ref().map(|result| result.confidence);
+        self.geocode_timestamp = result.as_ref().map(|result| result.timestamp);
+    }
 }
 
 fn deserialize_optional_path<'de, D>(deserializer: D) -> Result<Option<PathBuf>, D::Error>
@@ -1,2 +1,15 @@
 pub mod analysis;
+pub mod boundary;
+pub mod cluster;
+pub mod condition;
+pub mod filter;
+pub mod geo;
 pub mod inventory;
+pub mod overrides;
+pub mod photos;
+pub mod projection;
+pub mod route;
+pub mod search;
+pub mod streets;
+pub mod table;
+pub mod validation;
\ No newline at end of file
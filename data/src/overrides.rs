@@ -0,0 +1,215 @@
+use crate::geo::{haversine_distance_m, GeocodeResult, MatchType};
+use crate::inventory::TileInventory;
+use serde::{Deserialize, Serialize};
+
+/// Provider name recorded for coordinates that came from an override.
+pub const OVERRIDE_PROVIDER: &str = "override";
+
+/// A hand-corrected marker position, kept apart from the inventory CSV so the field team can
+/// maintain it without touching the main data.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CoordinateOverride {
+    pub id: u32,
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Why the position was corrected, e.g. "sign is on the Chartres side of the building".
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppliedOverride {
+    pub id: u32,
+    pub reason: String,
+    /// The coordinate the record had before, if any.
+    pub previous: Option<(f64, f64)>,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl AppliedOverride {
+    /// Meters between the previous and overriding coordinate.
+    pub fn moved_m(&self) -> Option<f64> {
+        self.previous
+            .map(|previous| haversine_distance_m(previous, (self.latitude, self.longitude)))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct OverrideReport {
+    /// One entry per record, from the override that won.
+    pub applied: Vec<AppliedOverride>,
+    /// Override IDs with no matching record.
+    pub unknown_ids: Vec<u32>,
+    /// IDs listed more than once, each reported once.
+    pub duplicate_ids: Vec<u32>,
+}
+
+/// Replaces the coordinates of every record with an override, whatever they were, and marks
+/// them as hand-placed. Later overrides for the same ID win. `timestamp` (seconds since the
+/// Unix epoch) is recorded as when the override was applied, unless the record already holds
+/// the same override, in which case its original timestamp is kept.
+pub fn apply_overrides(
+    inventory: &mut [TileInventory],
+    overrides: &[CoordinateOverride],
+    timestamp: u64,
+) -> OverrideReport {
+    let mut report = OverrideReport::default();
+    for (index, coordinate) in overrides.iter().enumerate() {
+        let later = &overrides[index + 1..];
+        if later.iter().any(|other| other.id == coordinate.id) {
+            if !report.duplicate_ids.contains(&coordinate.id) {
+                report.duplicate_ids.push(coordinate.id);
+            }
+            continue;
+        }
+        let Some(item) = inventory.iter_mut().find(|item| item.id == coordinate.id) else {
+            report.unknown_ids.push(coordinate.id);
+            continue;
+        };
+        let previous = item.latitude.zip(item.longitude);
+        report.applied.push(AppliedOverride {
+            id: item.id,
+            reason: coordinate.reason.clone(),
+            previous,
+            latitude: coordinate.latitude,
+            longitude: coordinate.longitude,
+        });
+        let mut result = GeocodeResult {
+            provider: OVERRIDE_PROVIDER.to_string(),
+            display_name: Some(coordinate.reason.clone()),
+            match_type: MatchType::Manual,
+            confidence: 1.0,
+            timestamp,
+        };
+        // Re-applying the same override isn't a new correction.
+        if let Some(existing) = item.geocode_result() {
+            let unchanged = previous == Some((coordinate.latitude, coordinate.longitude))
+                && existing
+                    == GeocodeResult {
+                        timestamp: existing.timestamp,
+                        ..result.clone()
+                    };
+            if unchanged {
+                result.timestamp = existing.timestamp;
+            }
+        }
+        item.latitude = Some(coordinate.latitude);
+        item.longitude = Some(coordinate.longitude);
+        item.set_geocode_result(Some(result));
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn correction(id: u32, latitude: f64, longitude: f64, reason: &str) -> CoordinateOverride {
+        CoordinateOverride {
+            id,
+            latitude,
+            longitude,
+            reason: reason.to_string(),
+        }
+    }
+
+    fn records() -> Vec<TileInventory> {
+        vec![
+            TileInventory {
+                latitude: Some(29.9557),
+                longitude: Some(-90.0686),
+                ..TileInventory::new(1, "Calle D'Bienville", "813 Bienville St")
+            },
+            TileInventory::new(2, "Calle de Chartres", "600 Chartres St"),
+        ]
+    }
+
+    #[test]
+    fn overrides_win_and_are_marked_manual() {
+        let mut inventory = records();
+        let overrides = [
+            correction(1, 29.9560, -90.0686, "sign is on the corner"),
+            correction(2, 29.9570, -90.0640, "geocoder missed it"),
+            correction(9, 29.9500, -90.0600, "record was deleted"),
+        ];
+        let report = apply_overrides(&mut inventory, &overrides, 1_700_000_000);
+
+        assert_eq!(report.unknown_ids, [9]);
+        assert!(report.duplicate_ids.is_empty());
+        assert_eq!(report.applied.len(), 2);
+        assert_eq!(report.applied[0].previous, Some((29.9557, -90.0686)));
+        let moved = report.applied[0].moved_m().unwrap();
+        assert!((moved - 33.4).abs() < 0.5, "{}", moved);
+        assert_eq!(report.applied[1].previous, None);
+        assert_eq!(report.applied[1].moved_m(), None);
+
+        assert_eq!(
+            (inventory[1].latitude, inventory[1].longitude),
+            (Some(29.9570), Some(-90.0640))
+        );
+        assert_eq!(
+            inventory[1].geocode_result(),
+            Some(GeocodeResult {
+                provider: OVERRIDE_PROVIDER.to_string(),
+                display_name: Some("geocoder missed it".to_string()),
+                match_type: MatchType::Manual,
+                confidence: 1.0,
+                timestamp: 1_700_000_000,
+            })
+        );
+    }
+
+    #[test]
+    fn later_duplicates_win_and_are_reported_once() {
+        let mut inventory = records();
+        let overrides = [
+            correction(2, 29.9570, -90.0640, "first try"),
+            correction(9, 29.9500, -90.0600, "unknown"),
+            correction(2, 29.9571, -90.0641, "second try"),
+            correction(9, 29.9500, -90.0600, "unknown"),
+            correction(2, 29.9572, -90.0642, "third try"),
+        ];
+        let report = apply_overrides(&mut inventory, &overrides, 1_700_000_000);
+
+        assert_eq!(report.duplicate_ids, [2, 9]);
+        assert_eq!(report.unknown_ids, [9]);
+        assert_eq!(report.applied.len(), 1);
+        assert_eq!(report.applied[0].reason, "third try");
+        assert_eq!(report.applied[0].previous, None);
+        assert_eq!(inventory[1].latitude, Some(29.9572));
+    }
+
+    #[test]
+    fn reapplying_an_override_keeps_its_timestamp() {
+        let mut inventory = records();
+        let overrides = [correction(2, 29.9570, -90.0640, "geocoder missed it")];
+        apply_overrides(&mut inventory, &overrides, 1_700_000_000);
+        apply_overrides(&mut inventory, &overrides, 1_800_000_000);
+        assert_eq!(inventory[1].geocode_timestamp, Some(1_700_000_000));
+
+        // A new position or reason is a new correction.
+        let moved = [correction(2, 29.9571, -90.0640, "geocoder missed it")];
+        apply_overrides(&mut inventory, &moved, 1_800_000_000);
+        assert_eq!(inventory[1].geocode_timestamp, Some(1_800_000_000));
+        let reworded = [correction(2, 29.9571, -90.0640, "sign faces Toulouse")];
+        apply_overrides(&mut inventory, &reworded, 1_900_000_000);
+        assert_eq!(inventory[1].geocode_timestamp, Some(1_900_000_000));
+
+        // Coordinates from a geocoder are replaced even when they agree with the override.
+        let mut geocoded = records();
+        geocoded[0].set_geocode_result(Some(GeocodeResult {
+            provider: "nominatim".to_string(),
+            display_name: None,
+            match_type: MatchType::House,
+            confidence: 0.9,
+            timestamp: 1_600_000_000,
+        }));
+        let same_place = [correction(1, 29.9557, -90.0686, "checked on site")];
+        apply_overrides(&mut geocoded, &same_place, 1_700_000_000);
+        assert_eq!(
+            geocoded[0].geocode_provider.as_deref(),
+            Some(OVERRIDE_PROVIDER)
+        );
+        assert_eq!(geocoded[0].geocode_timestamp, Some(1_700_000_000));
+    }
+}
@@ -0,0 +1,101 @@
+//! Naming convention for the resized copies of `Inventory_Images` photos, shared by the
+//! derivative generator and the web viewer so neither needs the other's manifest.
+
+/// Where derivatives are written, relative to the repo root (and to `dist` once copied).
+pub const DERIVATIVES_DIR: &str = "Inventory_Images/derivatives";
+pub const THUMBNAIL_WIDTH: u32 = 160;
+/// Widths offered to the browser through `srcset`.
+pub const DERIVATIVE_WIDTHS: [u32; 3] = [320, 640, 1280];
+
+fn derived_path(original: &str, suffix: &str) -> String {
+    let file_name = original.rsplit('/').next().unwrap_or(original);
+    let stem = file_name
+        .rsplit_once('.')
+        .map_or(file_name, |(stem, _extension)| stem);
+    format!("{}/{}.{}.jpg", DERIVATIVES_DIR, stem, suffix)
+}
+
+/// `Inventory_Images/1.Photo 1.134027.jpg` -> `Inventory_Images/derivatives/1.Photo 1.134027.thumb.jpg`
+pub fn thumbnail_path(original: &str) -> String {
+    derived_path(original, "thumb")
+}
+
+/// `Inventory_Images/1.Photo 1.134027.jpg` -> `Inventory_Images/derivatives/1.Photo 1.134027.w640.jpg`
+pub fn derivative_path(original: &str, width: u32) -> String {
+    derived_path(original, &format!("w{}", width))
+}
+
+/// A `srcset` value listing every derivative width. Spaces in the file names are
+/// percent-encoded, since `srcset` uses whitespace to separate a URL from its descriptor.
+pub fn srcset(original: &str) -> String {
+    DERIVATIVE_WIDTHS
+        .iter()
+        .map(|width| {
+            let url = derivative_path(original, *width)
+                .replace(' ', "%20")
+                .replace(',', "%2C");
+            format!("{} {}w", url, width)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// What an `<img>` showing a derivative of `original` should load once it fails: the
+/// original, unless that is what just failed.
+pub fn fallback_src<'a>(original: &'a str, src: &str, srcset: &str) -> Option<&'a str> {
+    if srcset.is_empty() && src == original {
+        None
+    } else {
+        Some(original)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derived_paths_keep_the_stem() {
+        let original = "Inventory_Images/1.Photo 1.134027.jpg";
+        assert_eq!(
+            thumbnail_path(original),
+            "Inventory_Images/derivatives/1.Photo 1.134027.thumb.jpg"
+        );
+        assert_eq!(
+            derivative_path(original, 640),
+            "Inventory_Images/derivatives/1.Photo 1.134027.w640.jpg"
+        );
+        // Only the last extension goes, and PNGs come out as JPEGs.
+        assert_eq!(
+            derivative_path("scans/sign.v2.PNG", 320),
+            "Inventory_Images/derivatives/sign.v2.w320.jpg"
+        );
+        assert_eq!(
+            thumbnail_path("no-extension"),
+            "Inventory_Images/derivatives/no-extension.thumb.jpg"
+        );
+    }
+
+    #[test]
+    fn srcset_lists_every_width_with_escaped_urls() {
+        assert_eq!(
+            srcset("Inventory_Images/7.Photo 1,a.jpg"),
+            "Inventory_Images/derivatives/7.Photo%201%2Ca.w320.jpg 320w, \
+             Inventory_Images/derivatives/7.Photo%201%2Ca.w640.jpg 640w, \
+             Inventory_Images/derivatives/7.Photo%201%2Ca.w1280.jpg 1280w"
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_original_once() {
+        let original = "Inventory_Images/1.Photo 1.134027.jpg";
+        let thumbnail = thumbnail_path(original);
+        assert_eq!(fallback_src(original, &thumbnail, ""), Some(original));
+        assert_eq!(
+            fallback_src(original, original, &srcset(original)),
+            Some(original)
+        );
+        // The original is missing too, so stop rather than loop on errors.
+        assert_eq!(fallback_src(original, original, ""), None);
+    }
+}
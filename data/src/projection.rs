@@ -0,0 +1,438 @@
+use crate::inventory::TileInventory;
+use std::f64::consts::FRAC_PI_4;
+use std::fmt;
+use std::str::FromStr;
+
+/// Meters in a US survey foot, the unit of the Louisiana state plane.
+const US_SURVEY_FOOT_M: f64 = 1200.0 / 3937.0;
+
+/// Semi-major axis shared by GRS80 (NAD83) and WGS84.
+const SEMI_MAJOR_AXIS_M: f64 = 6_378_137.0;
+const GRS80_INVERSE_FLATTENING: f64 = 298.257_222_101;
+const WGS84_INVERSE_FLATTENING: f64 = 298.257_223_563;
+
+/// A coordinate reference system that inventory coordinates can be exported in.
+///
+/// NAD83 and WGS84 are treated as the same datum. They differ by about a meter around New
+/// Orleans, well under the accuracy of a geocoded sign.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Crs {
+    /// Latitude and longitude in degrees (EPSG:4326), what the inventory stores.
+    Wgs84,
+    /// NAD83 / Louisiana South (ftUS) (EPSG:3452), the city's GIS system.
+    LouisianaSouth,
+    /// WGS 84 / UTM zone 15N (EPSG:32615), in meters.
+    Utm15N,
+}
+
+impl Crs {
+    pub fn epsg(&self) -> u32 {
+        match self {
+            Crs::Wgs84 => 4326,
+            Crs::LouisianaSouth => 3452,
+            Crs::Utm15N => 32615,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Crs::Wgs84 => "WGS 84",
+            Crs::LouisianaSouth => "NAD83 / Louisiana South (ftUS)",
+            Crs::Utm15N => "WGS 84 / UTM zone 15N",
+        }
+    }
+
+    /// The OGC URN that names this CRS, as used by GeoJSON's `crs` member.
+    pub fn urn(&self) -> String {
+        format!("urn:ogc:def:crs:EPSG::{}", self.epsg())
+    }
+
+    /// Column names for the x and y ordinates, carrying the unit.
+    pub fn axis_names(&self) -> (&'static str, &'static str) {
+        match self {
+            Crs::Wgs84 => ("longitude", "latitude"),
+            Crs::LouisianaSouth => ("easting_ftus", "northing_ftus"),
+            Crs::Utm15N => ("easting_m", "northing_m"),
+        }
+    }
+
+    /// Projects a latitude and longitude in degrees to (x, y) in this CRS. For WGS84 that is
+    /// (longitude, latitude), the usual GIS axis order.
+    pub fn from_wgs84(&self, latitude: f64, longitude: f64) -> (f64, f64) {
+        match self {
+            Crs::Wgs84 => (longitude, latitude),
+            Crs::LouisianaSouth => {
+                let (x, y) = louisiana_south().forward(latitude, longitude);
+                (x / US_SURVEY_FOOT_M, y / US_SURVEY_FOOT_M)
+            }
+            Crs::Utm15N => utm_15n().forward(latitude, longitude),
+        }
+    }
+
+    /// The inverse of `from_wgs84`: (latitude, longitude) in degrees.
+    pub fn to_wgs84(&self, x: f64, y: f64) -> (f64, f64) {
+        match self {
+            Crs::Wgs84 => (y, x),
+            Crs::LouisianaSouth => {
+                louisiana_south().inverse(x * US_SURVEY_FOOT_M, y * US_SURVEY_FOOT_M)
+            }
+            Crs::Utm15N => utm_15n().inverse(x, y),
+        }
+    }
+}
+
+impl fmt::Display for Crs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EPSG:{}", self.epsg())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownCrs(pub String);
+
+impl fmt::Display for UnknownCrs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown CRS {:?}, expected EPSG:4326, EPSG:3452 or EPSG:32615",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for UnknownCrs {}
+
+impl FromStr for Crs {
+    type Err = UnknownCrs;
+
+    /// Accepts EPSG codes with or without the `EPSG:` prefix, and a few common names.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let key = s.trim().to_ascii_lowercase().replace(['-', '_', ' '], "");
+        let key = key.strip_prefix("epsg:").unwrap_or(&key);
+        match key {
+            "4326" | "wgs84" | "latlong" | "latlon" => Ok(Crs::Wgs84),
+            "3452" | "stateplane" | "louisianasouth" => Ok(Crs::LouisianaSouth),
+            "32615" | "utm15n" | "utm15" => Ok(Crs::Utm15N),
+            _ => Err(UnknownCrs(s.to_string())),
+        }
+    }
+}
+
+/// A record's coordinate projected into a CRS.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProjectedCoordinate {
+    pub id: u32,
+    pub x: f64,
+    pub y: f64,
+}
+
+/// Projects every record that has coordinates; records without are left out.
+pub fn project_inventory(inventory: &[TileInventory], crs: Crs) -> Vec<ProjectedCoordinate> {
+    inventory
+        .iter()
+        .filter_map(|item| {
+            let (latitude, longitude) = item.latitude.zip(item.longitude)?;
+            let (x, y) = crs.from_wgs84(latitude, longitude);
+            Some(ProjectedCoordinate { id: item.id, x, y })
+        })
+        .collect()
+}
+
+fn eccentricity(inverse_flattening: f64) -> f64 {
+    let flattening = 1.0 / inverse_flattening;
+    (flattening * (2.0 - flattening)).sqrt()
+}
+
+/// Lambert Conformal Conic with two standard parallels (EPSG method 9802), following Snyder,
+/// "Map Projections: A Working Manual", pp. 107-109. Works in meters.
+struct LambertConformalConic {
+    e: f64,
+    n: f64,
+    /// a·F in Snyder's notation.
+    af: f64,
+    rho_0: f64,
+    lon_0: f64,
+    false_easting: f64,
+    false_northing: f64,
+}
+
+/// EPSG:3452 parameters: parallels 30°42' and 29°18', origin 28°30'N 91°20'W, false easting
+/// 3,280,833.3333 ftUS (1,000,000 m).
+fn louisiana_south() -> LambertConformalConic {
+    LambertConformalConic::new(
+        (SEMI_MAJOR_AXIS_M, GRS80_INVERSE_FLATTENING),
+        (30.7, 29.3),
+        28.5,
+        -(91.0 + 20.0 / 60.0),
+        (1_000_000.0, 0.0),
+    )
+}
+
+impl LambertConformalConic {
+    fn new(
+        (semi_major_axis, inverse_flattening): (f64, f64),
+        (lat_1, lat_2): (f64, f64),
+        lat_0: f64,
+        lon_0: f64,
+        (false_easting, false_northing): (f64, f64),
+    ) -> Self {
+        let e = eccentricity(inverse_flattening);
+        let m = |latitude: f64| {
+            let phi = latitude.to_radians();
+            phi.cos() / (1.0 - (e * phi.sin()).powi(2)).sqrt()
+        };
+        let t = |latitude: f64| lcc_t(e, latitude.to_radians());
+        let n = (m(lat_1).ln() - m(lat_2).ln()) / (t(lat_1).ln() - t(lat_2).ln());
+        let af = semi_major_axis * m(lat_1) / (n * t(lat_1).powf(n));
+        Self {
+            e,
+            n,
+            af,
+            rho_0: af * t(lat_0).powf(n),
+            lon_0: lon_0.to_radians(),
+            false_easting,
+            false_northing,
+        }
+    }
+
+    fn forward(&self, latitude: f64, longitude: f64) -> (f64, f64) {
+        let rho = self.af * lcc_t(self.e, latitude.to_radians()).powf(self.n);
+        let theta = self.n * (longitude.to_radians() - self.lon_0);
+        (
+            self.false_easting + rho * theta.sin(),
+            self.false_northing + self.rho_0 - rho * theta.cos(),
+        )
+    }
+
+    fn inverse(&self, x: f64, y: f64) -> (f64, f64) {
+        let dx = x - self.false_easting;
+        let dy = self.rho_0 - (y - self.false_northing);
+        let rho = dx.hypot(dy).copysign(self.n);
+        let theta = (dx * self.n.signum()).atan2(dy * self.n.signum());
+        let t = (rho / self.af).powf(1.0 / self.n);
+
+        // Latitude has no closed form; this converges to well under a millimeter in a
+        // handful of iterations.
+        let mut phi = std::f64::consts::FRAC_PI_2 - 2.0 * t.atan();
+        for _ in 0..15 {
+            let esin = self.e * phi.sin();
+            let next = std::f64::consts::FRAC_PI_2
+                - 2.0 * (t * ((1.0 - esin) / (1.0 + esin)).powf(self.e / 2.0)).atan();
+            let done = (next - phi).abs() < 1e-12;
+            phi = next;
+            if done {
+                break;
+            }
+        }
+        (phi.to_degrees(), (theta / self.n + self.lon_0).to_degrees())
+    }
+}
+
+fn lcc_t(e: f64, phi: f64) -> f64 {
+    let esin = e * phi.sin();
+    (FRAC_PI_4 - phi / 2.0).tan() / ((1.0 - esin) / (1.0 + esin)).powf(e / 2.0)
+}
+
+/// Transverse Mercator using Krüger's series to fourth order in n (Karney, "Transverse
+/// Mercator with an accuracy of a few nanometers", 2011), good to well under a millimeter
+/// within a UTM zone. Works in meters.
+struct TransverseMercator {
+    e: f64,
+    /// k0·A, the scaled rectifying radius.
+    k0a: f64,
+    alpha: [f64; 4],
+    beta: [f64; 4],
+    delta: [f64; 4],
+    lon_0: f64,
+    false_easting: f64,
+    false_northing: f64,
+}
+
+/// UTM zone 15N: central meridian 93°W, scale 0.9996, false easting 500,000 m.
+fn utm_15n() -> TransverseMercator {
+    TransverseMercator::new(WGS84_INVERSE_FLATTENING, 0.9996, -93.0, (500_000.0, 0.0))
+}
+
+impl TransverseMercator {
+    fn new(
+        inverse_flattening: f64,
+        k0: f64,
+        lon_0: f64,
+        (false_easting, false_northing): (f64, f64),
+    ) -> Self {
+        let f = 1.0 / inverse_flattening;
+        let n = f / (2.0 - f);
+        let (n2, n3, n4) = (n * n, n * n * n, n * n * n * n);
+        Self {
+            e: eccentricity(inverse_flattening),
+            k0a: k0 * SEMI_MAJOR_AXIS_M / (1.0 + n) * (1.0 + n2 / 4.0 + n4 / 64.0),
+            alpha: [
+                n / 2.0 - 2.0 / 3.0 * n2 + 5.0 / 16.0 * n3 + 41.0 / 180.0 * n4,
+                13.0 / 48.0 * n2 - 3.0 / 5.0 * n3 + 557.0 / 1440.0 * n4,
+                61.0 / 240.0 * n3 - 103.0 / 140.0 * n4,
+                49561.0 / 161280.0 * n4,
+            ],
+            beta: [
+                n / 2.0 - 2.0 / 3.0 * n2 + 37.0 / 96.0 * n3 - 1.0 / 360.0 * n4,
+                1.0 / 48.0 * n2 + 1.0 / 15.0 * n3 - 437.0 / 1440.0 * n4,
+                17.0 / 480.0 * n3 - 37.0 / 840.0 * n4,
+                4397.0 / 161280.0 * n4,
+            ],
+            delta: [
+                2.0 * n - 2.0 / 3.0 * n2 - 2.0 * n3 + 116.0 / 45.0 * n4,
+                7.0 / 3.0 * n2 - 8.0 / 5.0 * n3 - 227.0 / 45.0 * n4,
+                56.0 / 15.0 * n3 - 136.0 / 35.0 * n4,
+                4279.0 / 630.0 * n4,
+            ],
+            lon_0: lon_0.to_radians(),
+            false_easting,
+            false_northing,
+        }
+    }
+
+    fn forward(&self, latitude: f64, longitude: f64) -> (f64, f64) {
+        let phi = latitude.to_radians();
+        let lambda = longitude.to_radians() - self.lon_0;
+        // Conformal latitude, via its tangent.
+        let tau = (phi.sin().atanh() - self.e * (self.e * phi.sin()).atanh()).sinh();
+        let xi_prime = tau.atan2(lambda.cos());
+        let eta_prime = (lambda.sin() / tau.hypot(1.0)).atanh();
+
+        let (mut xi, mut eta) = (xi_prime, eta_prime);
+        for (j, alpha) in (1..).zip(self.alpha) {
+            let k = 2.0 * j as f64;
+            xi += alpha * (k * xi_prime).sin() * (k * eta_prime).cosh();
+            eta += alpha * (k * xi_prime).cos() * (k * eta_prime).sinh();
+        }
+        (
+            self.false_easting + self.k0a * eta,
+            self.false_northing + self.k0a * xi,
+        )
+    }
+
+    fn inverse(&self, x: f64, y: f64) -> (f64, f64) {
+        let xi = (y - self.false_northing) / self.k0a;
+        let eta = (x - self.false_easting) / self.k0a;
+
+        let (mut xi_prime, mut eta_prime) = (xi, eta);
+        for (j, beta) in (1..).zip(self.beta) {
+            let k = 2.0 * j as f64;
+            xi_prime -= beta * (k * xi).sin() * (k * eta).cosh();
+            eta_prime -= beta * (k * xi).cos() * (k * eta).sinh();
+        }
+        let chi = (xi_prime.sin() / eta_prime.cosh()).asin();
+        let mut phi = chi;
+        for (j, delta) in (1..).zip(self.delta) {
+            phi += delta * (2.0 * j as f64 * chi).sin();
+        }
+        (
+            phi.to_degrees(),
+            (self.lon_0 + eta_prime.sinh().atan2(xi_prime.cos())).to_degrees(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: (f64, f64), expected: (f64, f64), tolerance: f64) {
+        assert!(
+            (actual.0 - expected.0).abs() < tolerance && (actual.1 - expected.1).abs() < tolerance,
+            "{:?} is not within {} of {:?}",
+            actual,
+            tolerance,
+            expected
+        );
+    }
+
+    #[test]
+    fn lambert_conformal_conic_matches_the_epsg_example() {
+        // EPSG Guidance Note 7-2, Lambert Conic Conformal (2SP): NAD27 / Texas South Central
+        // on the Clarke 1866 ellipsoid, in US survey feet.
+        let texas_south_central = LambertConformalConic::new(
+            (6_378_206.4, 294.978_698_2),
+            (30.0 + 17.0 / 60.0, 28.0 + 23.0 / 60.0),
+            27.0 + 50.0 / 60.0,
+            -99.0,
+            (2_000_000.0 * US_SURVEY_FOOT_M, 0.0),
+        );
+        let (x, y) = texas_south_central.forward(28.5, -96.0);
+        assert_close(
+            (x / US_SURVEY_FOOT_M, y / US_SURVEY_FOOT_M),
+            (2_963_503.91, 254_759.80),
+            0.01,
+        );
+        assert_close(texas_south_central.inverse(x, y), (28.5, -96.0), 1e-9);
+    }
+
+    #[test]
+    fn louisiana_south_origin_is_the_false_origin() {
+        let origin = Crs::LouisianaSouth.from_wgs84(28.5, -(91.0 + 20.0 / 60.0));
+        assert_close(origin, (3_280_833.333_3, 0.0), 0.001);
+    }
+
+    #[test]
+    fn utm_central_meridian() {
+        assert_close(Crs::Utm15N.from_wgs84(0.0, -93.0), (500_000.0, 0.0), 0.001);
+        // The WGS84 meridian arc to 45°N is 4,984,944.378 m, scaled by k0.
+        assert_close(
+            Crs::Utm15N.from_wgs84(45.0, -93.0),
+            (500_000.0, 0.9996 * 4_984_944.378),
+            0.001,
+        );
+    }
+
+    // Reference values for a point on Bourbon Street (29.9584, -90.0644), from
+    // PROJ's own formulas as ported by proj4rs 0.1.10:
+    //   +proj=lcc +lat_0=28.5 +lon_0=-91.3333333333333 +lat_1=30.7 +lat_2=29.3
+    //     +x_0=999999.999996 +y_0=0 +ellps=GRS80 +units=us-ft
+    //   +proj=utm +zone=15 +datum=WGS84 +units=m
+
+    #[test]
+    fn louisiana_south_matches_proj() {
+        let (x, y) = Crs::LouisianaSouth.from_wgs84(29.9584, -90.0644);
+        assert_close((x, y), (3_682_650.525_5, 532_588.968_8), 0.01);
+        assert_close(
+            Crs::LouisianaSouth.to_wgs84(x, y),
+            (29.9584, -90.0644),
+            1e-9,
+        );
+        // West of the central meridian, between the standard parallels.
+        assert_close(
+            Crs::LouisianaSouth.from_wgs84(30.0, -91.0),
+            (3_386_343.817_4, 545_645.851_6),
+            0.01,
+        );
+    }
+
+    #[test]
+    fn utm_off_the_central_meridian_matches_proj() {
+        // The Quarter is nearly 3° east of zone 15's meridian, where the series terms matter.
+        let (x, y) = Crs::Utm15N.from_wgs84(29.9584, -90.0644);
+        assert_close((x, y), (783_312.422_3, 3_317_802.131_9), 0.01);
+        assert_close(Crs::Utm15N.to_wgs84(x, y), (29.9584, -90.0644), 1e-9);
+        assert_close(
+            Crs::Utm15N.from_wgs84(30.0, -91.0),
+            (692_915.105_2, 3_320_469.286_5),
+            0.01,
+        );
+    }
+
+    #[test]
+    fn round_trips_french_quarter_coordinates() {
+        for crs in [Crs::Wgs84, Crs::LouisianaSouth, Crs::Utm15N] {
+            for (latitude, longitude) in [(29.9574, -90.0629), (29.9641741, -90.0623384)] {
+                let (x, y) = crs.from_wgs84(latitude, longitude);
+                assert_close(crs.to_wgs84(x, y), (latitude, longitude), 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn parses_codes_and_names() {
+        assert_eq!("EPSG:3452".parse::<Crs>().unwrap(), Crs::LouisianaSouth);
+        assert_eq!("32615".parse::<Crs>().unwrap(), Crs::Utm15N);
+        assert!("EPSG:2272".parse::<Crs>().is_err());
+    }
+}
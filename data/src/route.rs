@@ -0,0 +1,301 @@
+use crate::condition::ConditionLevel;
+use crate::filter::InventoryFilter;
+use std::fmt::Write;
+
+/// What the web app's URL points at.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Page {
+    #[default]
+    Home,
+    /// `/sign/{id}`: one sign, selected.
+    Sign(u32),
+    /// `/street/{name}`: the signs of one street.
+    Street(String),
+}
+
+/// A web app URL: the page, and the filters in its query string.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Route {
+    pub page: Page,
+    pub filter: InventoryFilter,
+}
+
+impl Route {
+    /// The route for a selection and filter. A filter on a single street, with nothing
+    /// selected, gets that street's page.
+    pub fn new(selected: Option<u32>, filter: &InventoryFilter) -> Self {
+        let mut filter = filter.clone();
+        let page = match selected {
+            Some(id) => Page::Sign(id),
+            None if filter.streets.len() == 1 => {
+                let street = filter.streets.pop_first().unwrap_or_default();
+                Page::Street(street)
+            }
+            None => Page::Home,
+        };
+        Route { page, filter }
+    }
+
+    /// Reads a path and query string (with or without its leading `?`). Anything it doesn't
+    /// recognize, such as an unknown path or a malformed filter value, is ignored.
+    pub fn parse(path: &str, query: &str) -> Self {
+        let segments: Vec<String> = path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(decode)
+            .collect();
+        let page = match segments.as_slice() {
+            [kind, id] if kind == "sign" => id.parse().map_or(Page::Home, Page::Sign),
+            [kind, name] if kind == "street" && !name.trim().is_empty() => {
+                Page::Street(name.trim().to_string())
+            }
+            _ => Page::Home,
+        };
+
+        let mut filter = InventoryFilter::default();
+        for pair in query.trim_start_matches('?').split('&') {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = decode(&value.replace('+', " "));
+            match key {
+                "condition" => filter.conditions.extend(
+                    value
+                        .split(',')
+                        .filter_map(|slug| ConditionLevel::from_slug(slug.trim())),
+                ),
+                "street" if !value.trim().is_empty() => {
+                    filter.streets.insert(value.trim().to_string());
+                }
+                "min_damaged" => filter.min_damaged = value.parse().ok(),
+                "max_damaged" => filter.max_damaged = value.parse().ok(),
+                "wall" if !value.is_empty() => filter.wall_type = Some(value),
+                "grout" if !value.is_empty() => filter.grout_condition = Some(value),
+                "photos" => filter.has_photos = value == "1",
+                "located" => filter.has_coordinates = value == "1",
+                _ => {}
+            }
+        }
+        Route { page, filter }
+    }
+
+    /// The filter the route shows: its query-string filter, narrowed to its street on a
+    /// street page.
+    pub fn effective_filter(&self) -> InventoryFilter {
+        let mut filter = self.filter.clone();
+        if let Page::Street(street) = &self.page {
+            filter.streets = [street.clone()].into();
+        }
+        filter
+    }
+
+    /// Like `parse`, for an app served from `base` (e.g. `/tiles/`) rather than the site root.
+    /// Paths outside `base` are the home page.
+    pub fn parse_under(base: &str, path: &str, query: &str) -> Self {
+        let base = base.trim_end_matches('/');
+        let path = match path.strip_prefix(base) {
+            Some(rest) if rest.is_empty() || rest.starts_with('/') => rest,
+            _ => "/",
+        };
+        Self::parse(path, query)
+    }
+
+    /// `to_url` for an app served from `base`, e.g. `/tiles/sign/12`.
+    pub fn to_url_under(&self, base: &str) -> String {
+        format!("{}{}", base.trim_end_matches('/'), self.to_url())
+    }
+
+    /// The path and query string, e.g. `/sign/12?condition=good,fair`.
+    pub fn to_url(&self) -> String {
+        let mut url = match &self.page {
+            Page::Home => "/".to_string(),
+            Page::Sign(id) => format!("/sign/{}", id),
+            Page::Street(street) => format!("/street/{}", encode(street)),
+        };
+
+        let filter = &self.filter;
+        let mut query: Vec<(&str, String)> = Vec::new();
+        if !filter.conditions.is_empty() {
+            let slugs: Vec<&str> = filter.conditions.iter().map(|level| level.slug()).collect();
+            query.push(("condition", slugs.join(",")));
+        }
+        for street in &filter.streets {
+            query.push(("street", street.clone()));
+        }
+        if let Some(min) = filter.min_damaged {
+            query.push(("min_damaged", min.to_string()));
+        }
+        if let Some(max) = filter.max_damaged {
+            query.push(("max_damaged", max.to_string()));
+        }
+        if let Some(wall_type) = &filter.wall_type {
+            query.push(("wall", wall_type.clone()));
+        }
+        if let Some(grout_condition) = &filter.grout_condition {
+            query.push(("grout", grout_condition.clone()));
+        }
+        if filter.has_photos {
+            query.push(("photos", "1".to_string()));
+        }
+        if filter.has_coordinates {
+            query.push(("located", "1".to_string()));
+        }
+
+        for (index, (key, value)) in query.iter().enumerate() {
+            let separator = if index == 0 { '?' } else { '&' };
+            // Writing to a String can't fail.
+            let _ = write!(url, "{}{}={}", separator, key, encode(value));
+        }
+        url
+    }
+}
+
+/// Percent-encodes everything but unreserved characters, which is safe in a path segment
+/// and a query value alike. Commas are kept too, for readable condition lists.
+fn encode(text: &str) -> String {
+    urlencoding::encode(text).replace("%2C", ",")
+}
+
+/// Undoes percent-encoding, leaving malformed escapes as they are.
+fn decode(text: &str) -> String {
+    String::from_utf8_lossy(&urlencoding::decode_binary(text.as_bytes())).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn filter() -> InventoryFilter {
+        InventoryFilter {
+            conditions: [ConditionLevel::Faded, ConditionLevel::FallingApart].into(),
+            streets: ["Calle D'Chartres".to_string(), "Calle D'Conde".to_string()].into(),
+            min_damaged: Some(5),
+            max_damaged: Some(20),
+            wall_type: Some("Masonry with Stucco".to_string()),
+            grout_condition: Some("Cracked & missing".to_string()),
+            has_photos: true,
+            has_coordinates: true,
+        }
+    }
+
+    fn round_trip(route: &Route) -> Route {
+        let url = route.to_url();
+        let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+        Route::parse(path, query)
+    }
+
+    #[test]
+    fn round_trips() {
+        for page in [
+            Page::Home,
+            Page::Sign(12),
+            Page::Street("Calle D'San Luis".to_string()),
+        ] {
+            for filter in [InventoryFilter::default(), filter()] {
+                let route = Route {
+                    page: page.clone(),
+                    filter,
+                };
+                assert_eq!(round_trip(&route), route);
+            }
+        }
+    }
+
+    #[test]
+    fn percent_encodes_paths_and_values() {
+        let route = Route {
+            page: Page::Street("Calle D'Conde".to_string()),
+            filter: InventoryFilter {
+                wall_type: Some("Brick & stucco/paint".to_string()),
+                ..InventoryFilter::default()
+            },
+        };
+        assert_eq!(
+            route.to_url(),
+            "/street/Calle%20D%27Conde?wall=Brick%20%26%20stucco%2Fpaint"
+        );
+        let route = Route::parse("/street/Calle%20Real", "");
+        assert_eq!(route.page, Page::Street("Calle Real".to_string()));
+    }
+
+    #[test]
+    fn plus_is_a_space_only_in_the_query() {
+        let route = Route::parse("/street/Rue+Royale", "?street=Calle+Real&wall=A%2BB");
+        assert_eq!(route.page, Page::Street("Rue+Royale".to_string()));
+        assert_eq!(
+            route.filter.streets,
+            BTreeSet::from(["Calle Real".to_string()])
+        );
+        assert_eq!(route.filter.wall_type.as_deref(), Some("A+B"));
+    }
+
+    #[test]
+    fn keeps_malformed_escapes() {
+        assert_eq!(decode("100%"), "100%");
+        assert_eq!(decode("%zz%4"), "%zz%4");
+        assert_eq!(decode("%+1"), "%+1");
+        assert_eq!(decode("caf%C3%A9"), "café");
+        // An escape that isn't UTF-8 becomes a replacement character rather than an error.
+        assert_eq!(decode("%FF"), "\u{FFFD}");
+    }
+
+    #[test]
+    fn ignores_what_it_doesnt_recognize() {
+        let route = Route::parse(
+            "/sign/twelve",
+            "condition=good,shiny&min_damaged=lots&x=1&&",
+        );
+        assert_eq!(route.page, Page::Home);
+        assert_eq!(route.filter.conditions, [ConditionLevel::Good].into());
+        assert_eq!(route.filter.min_damaged, None);
+        assert_eq!(Route::parse("/street/%20", "").page, Page::Home);
+        assert_eq!(Route::parse("/sign/3/photos", "").page, Page::Home);
+    }
+
+    #[test]
+    fn a_single_street_gets_its_page() {
+        let single = InventoryFilter {
+            streets: ["Calle Real".to_string()].into(),
+            has_photos: true,
+            ..InventoryFilter::default()
+        };
+        let route = Route::new(None, &single);
+        assert_eq!(route.page, Page::Street("Calle Real".to_string()));
+        assert!(route.filter.streets.is_empty());
+        assert_eq!(route.to_url(), "/street/Calle%20Real?photos=1");
+        assert_eq!(route.effective_filter(), single);
+
+        // Two streets, or a selected sign, stay in the query string.
+        let route = Route::new(None, &filter());
+        assert_eq!(route.page, Page::Home);
+        assert_eq!(route.effective_filter(), filter());
+        let route = Route::new(Some(7), &single);
+        assert_eq!(route.page, Page::Sign(7));
+        assert_eq!(route.effective_filter(), single);
+    }
+
+    #[test]
+    fn the_street_page_overrides_street_filters() {
+        let route = Route::parse("/street/Calle Real", "street=Calle D'Chartres&photos=1");
+        let filter = route.effective_filter();
+        assert_eq!(filter.streets, BTreeSet::from(["Calle Real".to_string()]));
+        assert!(filter.has_photos);
+    }
+
+    #[test]
+    fn serves_from_a_base_path() {
+        let route = Route {
+            page: Page::Sign(12),
+            filter: InventoryFilter::default(),
+        };
+        assert_eq!(route.to_url_under("/"), "/sign/12");
+        assert_eq!(route.to_url_under("/tiles/"), "/tiles/sign/12");
+        assert_eq!(Route::parse_under("/tiles/", "/tiles/sign/12", ""), route);
+        assert_eq!(Route::parse_under("/tiles", "/tiles/sign/12", ""), route);
+        assert_eq!(Route::parse_under("/", "/sign/12", ""), route);
+        assert_eq!(Route::parse_under("/tiles/", "/tiles", "").page, Page::Home);
+        assert_eq!(
+            Route::parse_under("/tiles/", "/tilesets/sign/12", "").page,
+            Page::Home
+        );
+    }
+}
@@ -0,0 +1,333 @@
+use crate::inventory::TileInventory;
+use crate::streets::modern_street_name;
+use serde::Serialize;
+
+/// Word similarity below which a query word doesn't match.
+const MIN_WORD_SCORE: f64 = 0.7;
+
+/// Query words this short only match the start of a word; one typo in them is too many.
+const MIN_FUZZY_LEN: usize = 3;
+
+/// The text a search hit matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchField {
+    StreetSign,
+    StreetAddress,
+    ModernStreet,
+    BuildingOccupant,
+}
+
+impl SearchField {
+    /// How much a match on the field counts, so a street name outranks a business that
+    /// happens to share a word with it.
+    fn weight(self) -> f64 {
+        match self {
+            SearchField::StreetSign | SearchField::StreetAddress => 1.0,
+            SearchField::ModernStreet => 0.95,
+            SearchField::BuildingOccupant => 0.9,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SearchField::StreetSign => "sign",
+            SearchField::StreetAddress => "address",
+            SearchField::ModernStreet => "modern street",
+            SearchField::BuildingOccupant => "occupant",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchHit {
+    pub id: u32,
+    /// From 0 to 1, where 1 means every query word appears exactly.
+    pub score: f64,
+    /// The field of the best-matching word.
+    pub field: SearchField,
+}
+
+struct IndexedRecord {
+    id: u32,
+    words: Vec<(String, SearchField)>,
+}
+
+/// Tolerant search over sign names, addresses, the streets the signs name today and building
+/// occupants. Every query word has to match a word of the record, either as the start of it
+/// or with a typo or two, in any order: "bienvile 813" finds 813 Bienville St.
+pub struct SearchIndex {
+    records: Vec<IndexedRecord>,
+}
+
+impl SearchIndex {
+    pub fn new(inventory: &[TileInventory]) -> Self {
+        let records = inventory
+            .iter()
+            .map(|item| {
+                let fields = [
+                    (Some(item.street_sign.as_str()), SearchField::StreetSign),
+                    (
+                        Some(item.street_address.as_str()),
+                        SearchField::StreetAddress,
+                    ),
+                    (
+                        modern_street_name(&item.street_sign),
+                        SearchField::ModernStreet,
+                    ),
+                    (
+                        item.building_occupant.as_deref(),
+                        SearchField::BuildingOccupant,
+                    ),
+                ];
+                let words = fields
+                    .into_iter()
+                    .filter_map(|(text, field)| text.map(|text| (text, field)))
+                    .flat_map(|(text, field)| {
+                        words(text).into_iter().map(move |word| (word, field))
+                    })
+                    .collect();
+                IndexedRecord { id: item.id, words }
+            })
+            .collect();
+        SearchIndex { records }
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Up to `limit` records matching `query`, best first; records that score the same keep
+    /// their inventory order. A query without words matches nothing.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchHit> {
+        let query = words(query);
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let mut hits: Vec<SearchHit> = self
+            .records
+            .iter()
+            .filter_map(|record| {
+                let mut total = 0.0;
+                let mut best: Option<(f64, SearchField)> = None;
+                for wanted in &query {
+                    let (score, field) = record
+                        .words
+                        .iter()
+                        .map(|&(ref word, field)| {
+                            (word_score(wanted, word) * field.weight(), field)
+                        })
+                        .max_by(|a, b| a.0.total_cmp(&b.0))?;
+                    if score < MIN_WORD_SCORE {
+                        return None;
+                    }
+                    total += score;
+                    if best.is_none_or(|(best, _)| score > best) {
+                        best = Some((score, field));
+                    }
+                }
+                best.map(|(_, field)| SearchHit {
+                    id: record.id,
+                    score: total / query.len() as f64,
+                    field,
+                })
+            })
+            .collect();
+        // A stable sort, so ties stay in inventory order.
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+        hits.truncate(limit);
+        hits
+    }
+}
+
+/// Lowercased words with accents and punctuation dropped: "Arnaud’s" gives "arnaud" and "s".
+fn words(text: &str) -> Vec<String> {
+    text.chars()
+        .map(fold)
+        .collect::<String>()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn fold(c: char) -> char {
+    match c.to_lowercase().next().unwrap_or(c) {
+        'á' | 'à' | 'â' | 'ä' | 'ã' => 'a',
+        'é' | 'è' | 'ê' | 'ë' => 'e',
+        'í' | 'ì' | 'î' | 'ï' => 'i',
+        'ó' | 'ò' | 'ô' | 'ö' | 'õ' => 'o',
+        'ú' | 'ù' | 'û' | 'ü' => 'u',
+        'ñ' => 'n',
+        'ç' => 'c',
+        c => c,
+    }
+}
+
+/// How well a query word matches a word of the record: 1 when equal, 0.9 when it starts the
+/// word, otherwise the edit similarity to the whole word or, for a word still being typed, to
+/// its start. House numbers and short words don't match fuzzily.
+fn word_score(wanted: &str, word: &str) -> f64 {
+    if wanted == word {
+        return 1.0;
+    }
+    if word.starts_with(wanted) {
+        return 0.9;
+    }
+    let wanted_len = wanted.chars().count();
+    if wanted_len < MIN_FUZZY_LEN || wanted.chars().any(|c| c.is_ascii_digit()) {
+        return 0.0;
+    }
+    let whole = similarity(wanted, word);
+    let start: String = word.chars().take(wanted_len).collect();
+    let partial = similarity(wanted, &start) * 0.85;
+    whole.max(partial)
+}
+
+/// 1 minus the edit distance over the longer length, counting a swap of neighboring letters
+/// as one edit.
+fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - edit_distance(&a, &b) as f64 / longest as f64
+}
+
+/// Optimal string alignment distance.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = distance;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(id: u32, sign: &str, address: &str, occupant: &str) -> TileInventory {
+        TileInventory {
+            building_occupant: Some(occupant.to_string()),
+            ..TileInventory::new(id, sign, address)
+        }
+    }
+
+    fn inventory() -> Vec<TileInventory> {
+        vec![
+            record(
+                1,
+                "Calle D'Bienville",
+                "813 Bienville St, New Orleans, LA 70112",
+                "Arnaud’s Restaurant & Jazz Bistro",
+            ),
+            record(
+                2,
+                "Calle D'Bienville",
+                "301 Royal St, New Orleans, LA 70130",
+                "Curio Restaurant & Bar",
+            ),
+            record(
+                70,
+                "Calle Real",
+                "621 St Louis St, New Orleans, LA 70130",
+                "Omni Royal Orleans Hotel",
+            ),
+        ]
+    }
+
+    fn ids(hits: &[SearchHit]) -> Vec<u32> {
+        hits.iter().map(|hit| hit.id).collect()
+    }
+
+    #[test]
+    fn tolerates_typos_and_word_order() {
+        let hits = SearchIndex::new(&inventory()).search("bienvile 813", 10);
+        assert_eq!(ids(&hits), [1]);
+        assert_eq!(hits[0].field, SearchField::StreetAddress);
+    }
+
+    #[test]
+    fn ties_keep_inventory_order() {
+        let hits = SearchIndex::new(&inventory()).search("bienville", 10);
+        assert_eq!(ids(&hits), [1, 2]);
+        assert_eq!(hits[0].score, hits[1].score);
+
+        let mut reversed = inventory();
+        reversed.reverse();
+        let hits = SearchIndex::new(&reversed).search("bienville", 10);
+        assert_eq!(ids(&hits), [2, 1]);
+    }
+
+    #[test]
+    fn the_address_outranks_the_modern_street_name() {
+        // Royal Street is record 2's address and the modern name of record 70's Calle Real.
+        let hits = SearchIndex::new(&inventory()).search("royal", 10);
+        assert_eq!(ids(&hits), [2, 70]);
+        assert_eq!(hits[0].field, SearchField::StreetAddress);
+        assert_eq!(hits[1].field, SearchField::ModernStreet);
+        assert!(hits[0].score > hits[1].score);
+    }
+
+    #[test]
+    fn finds_occupants() {
+        let hits = SearchIndex::new(&inventory()).search("arnauds jazz", 10);
+        assert_eq!(ids(&hits), [1]);
+        assert_eq!(hits[0].field, SearchField::BuildingOccupant);
+    }
+
+    #[test]
+    fn every_word_has_to_match() {
+        let index = SearchIndex::new(&inventory());
+        assert!(index.search("bienville chartres", 10).is_empty());
+        assert!(index.search(" ,. ", 10).is_empty());
+        assert_eq!(index.search("new orleans", 2).len(), 2);
+    }
+
+    #[test]
+    fn house_numbers_match_exactly_or_by_their_start() {
+        assert_eq!(word_score("813", "813"), 1.0);
+        assert_eq!(word_score("81", "813"), 0.9);
+        assert_eq!(word_score("814", "813"), 0.0);
+        assert_eq!(word_score("13", "813"), 0.0);
+    }
+
+    #[test]
+    fn short_words_only_match_the_start() {
+        assert_eq!(word_score("ro", "royal"), 0.9);
+        assert_eq!(word_score("ry", "royal"), 0.0);
+        assert!(word_score("ryoal", "royal") >= MIN_WORD_SCORE);
+    }
+
+    #[test]
+    fn typos_and_swapped_letters() {
+        assert!(word_score("bienvile", "bienville") >= MIN_WORD_SCORE);
+        assert_eq!(similarity("royla", "royal"), 0.8);
+        assert!(word_score("chartres", "bienville") < MIN_WORD_SCORE);
+        // A word still being typed is compared with the start of the record's word.
+        assert!(word_score("bienvl", "bienville") >= MIN_WORD_SCORE);
+    }
+}
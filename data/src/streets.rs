@@ -0,0 +1,43 @@
+/// The Spanish colonial names on the tiles and the streets they name today.
+pub const HISTORICAL_STREETS: &[(&str, &str)] = &[
+    ("Avda D' La Explanada", "Esplanade Avenue"),
+    // Customhouse Street until 1901.
+    ("Calle D La Auduana", "Iberville Street"),
+    ("Calle D'Bienville", "Bienville Street"),
+    ("Calle D'Bourbon", "Bourbon Street"),
+    ("Calle D'Chartres", "Chartres Street"),
+    // Rue de Condé, the stretch of Chartres below Jackson Square.
+    ("Calle D'Conde", "Chartres Street"),
+    ("Calle D'Conti", "Conti Street"),
+    ("Calle D'Orleans", "Orleans Street"),
+    ("Calle D'San Felipe", "Saint Philip Street"),
+    ("Calle D'San Luis", "Saint Louis Street"),
+    ("Calle D'San Pedro", "Saint Peter Street"),
+    ("Calle D'Santa Ana", "Saint Ann Street"),
+    ("Calle Del Arsenal y de las Ursulinas", "Ursulines Avenue"),
+    ("Calle Del Cuartel", "Barracks Street"),
+    ("Calle Del Hospital", "Governor Nicholls Street"),
+    ("Calle Del Maine", "Dumaine Street"),
+    ("Calle Del Tolosa", "Toulouse Street"),
+    ("Calle Real", "Royal Street"),
+    ("Camino Del Bayona", "Dauphine Street"),
+    ("Camino Real Muelle", "Decatur Street"),
+    ("Plaza D'Armas", "Jackson Square"),
+];
+
+/// The modern street a tile sign names, ignoring case and punctuation.
+pub fn modern_street_name(street_sign: &str) -> Option<&'static str> {
+    let key = sign_key(street_sign);
+    HISTORICAL_STREETS
+        .iter()
+        .find(|(historical, _)| sign_key(historical) == key)
+        .map(|&(_, modern)| modern)
+}
+
+fn sign_key(sign: &str) -> String {
+    sign.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
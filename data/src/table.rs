@@ -0,0 +1,238 @@
+use crate::condition::ConditionLevel;
+use crate::geo::MatchType;
+use crate::inventory::TileInventory;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+/// A column of the inventory table, shared by the web app's table and its CSV download.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TableColumn {
+    Id,
+    StreetSign,
+    StreetAddress,
+    Condition,
+    DamagedTiles,
+    Photos,
+    Coordinates,
+}
+
+impl TableColumn {
+    pub const ALL: [TableColumn; 7] = [
+        TableColumn::Id,
+        TableColumn::StreetSign,
+        TableColumn::StreetAddress,
+        TableColumn::Condition,
+        TableColumn::DamagedTiles,
+        TableColumn::Photos,
+        TableColumn::Coordinates,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TableColumn::Id => "ID",
+            TableColumn::StreetSign => "Street Sign",
+            TableColumn::StreetAddress => "Street Address",
+            TableColumn::Condition => "Condition",
+            TableColumn::DamagedTiles => "Damaged Tiles",
+            TableColumn::Photos => "Photos",
+            TableColumn::Coordinates => "Coordinates",
+        }
+    }
+
+    /// The cell as displayed; empty when the record has no value.
+    pub fn text(self, item: &TileInventory) -> String {
+        match self {
+            TableColumn::Id => item.id.to_string(),
+            TableColumn::StreetSign => item.street_sign.clone(),
+            TableColumn::StreetAddress => item.street_address.clone(),
+            TableColumn::Condition => item.sign_condition.clone().unwrap_or_default(),
+            TableColumn::DamagedTiles => item
+                .number_of_tiles_damaged
+                .map(|count| count.to_string())
+                .unwrap_or_default(),
+            TableColumn::Photos => item.photos().count().to_string(),
+            TableColumn::Coordinates => coordinate_status(item).to_string(),
+        }
+    }
+
+    /// Orders records by the column's value rather than its text, so 10 damaged tiles sort
+    /// after 9 and conditions run from good to falling apart. Empty values come first.
+    pub fn compare(self, a: &TileInventory, b: &TileInventory) -> Ordering {
+        match self {
+            TableColumn::Id => a.id.cmp(&b.id),
+            TableColumn::StreetSign => compare_text(&a.street_sign, &b.street_sign),
+            TableColumn::StreetAddress => compare_text(&a.street_address, &b.street_address),
+            TableColumn::Condition => ConditionLevel::of(a).cmp(&ConditionLevel::of(b)),
+            TableColumn::DamagedTiles => a.number_of_tiles_damaged.cmp(&b.number_of_tiles_damaged),
+            TableColumn::Photos => a.photos().count().cmp(&b.photos().count()),
+            TableColumn::Coordinates => coordinate_rank(a).cmp(&coordinate_rank(b)),
+        }
+    }
+}
+
+fn compare_text(a: &str, b: &str) -> Ordering {
+    a.to_lowercase().cmp(&b.to_lowercase())
+}
+
+/// Sorts records by `column`, keeping the existing order of records that compare equal.
+pub fn sort_rows(rows: &mut [&TileInventory], column: TableColumn, ascending: bool) {
+    rows.sort_by(|a, b| {
+        let ordering = column.compare(a, b);
+        if ascending {
+            ordering
+        } else {
+            ordering.reverse()
+        }
+    });
+}
+
+/// How a record's coordinates were obtained, in words.
+pub fn coordinate_status(item: &TileInventory) -> &'static str {
+    if item.latitude.is_none() || item.longitude.is_none() {
+        return "missing";
+    }
+    match item.geocode_match_type {
+        Some(match_type) => match_type.accuracy_label(),
+        None => "approximate (match quality unknown)",
+    }
+}
+
+/// Missing coordinates first, then approximate ones, then exact ones.
+fn coordinate_rank(item: &TileInventory) -> u8 {
+    if item.latitude.is_none() || item.longitude.is_none() {
+        return 0;
+    }
+    match item.geocode_match_type {
+        None => 1,
+        Some(MatchType::Street | MatchType::Landmark | MatchType::Intersection) => 2,
+        Some(MatchType::House | MatchType::Manual) => 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn record(
+        id: u32,
+        condition: Option<&str>,
+        damaged: Option<u32>,
+        match_type: Option<Option<MatchType>>,
+    ) -> TileInventory {
+        TileInventory {
+            sign_condition: condition.map(str::to_string),
+            number_of_tiles_damaged: damaged,
+            latitude: match_type.map(|_| 29.9584),
+            longitude: match_type.map(|_| -90.0644),
+            geocode_match_type: match_type.flatten(),
+            ..TileInventory::new(id, "Rue Bourbon", format!("{}00 Bourbon St", id))
+        }
+    }
+
+    fn sorted(records: &[TileInventory], column: TableColumn, ascending: bool) -> Vec<u32> {
+        let mut rows: Vec<&TileInventory> = records.iter().collect();
+        sort_rows(&mut rows, column, ascending);
+        rows.iter().map(|item| item.id).collect()
+    }
+
+    #[test]
+    fn damaged_tiles_sort_as_numbers() {
+        let records = [
+            record(1, None, Some(10), None),
+            record(2, None, Some(9), None),
+            record(3, None, None, None),
+            record(4, None, Some(100), None),
+        ];
+        assert_eq!(
+            sorted(&records, TableColumn::DamagedTiles, true),
+            [3, 2, 1, 4]
+        );
+        assert_eq!(
+            sorted(&records, TableColumn::DamagedTiles, false),
+            [4, 1, 2, 3]
+        );
+        // As text, "10" would come before "9".
+        assert_eq!(TableColumn::DamagedTiles.text(&records[0]), "10");
+        assert_eq!(TableColumn::DamagedTiles.text(&records[2]), "");
+    }
+
+    #[test]
+    fn conditions_sort_from_good_to_falling_apart() {
+        let records = [
+            record(1, Some("Falling Apart"), None, None),
+            record(2, Some("good"), None, None),
+            record(3, None, None, None),
+            record(4, Some("Faded/Cracked Polish"), None, None),
+            record(5, Some("Fair Condition"), None, None),
+        ];
+        assert_eq!(
+            sorted(&records, TableColumn::Condition, true),
+            [3, 2, 5, 4, 1]
+        );
+    }
+
+    #[test]
+    fn coordinates_sort_missing_then_approximate_then_exact() {
+        let records = [
+            record(1, None, None, Some(Some(MatchType::House))),
+            record(2, None, None, Some(Some(MatchType::Street))),
+            record(3, None, None, None),
+            record(4, None, None, Some(None)),
+            record(5, None, None, Some(Some(MatchType::Manual))),
+            record(6, None, None, Some(Some(MatchType::Intersection))),
+        ];
+        let ranks: Vec<u8> = records.iter().map(coordinate_rank).collect();
+        assert_eq!(ranks, [3, 2, 0, 1, 3, 2]);
+        assert_eq!(
+            sorted(&records, TableColumn::Coordinates, true),
+            [3, 4, 2, 6, 1, 5]
+        );
+        assert_eq!(TableColumn::Coordinates.text(&records[2]), "missing");
+        assert_eq!(
+            TableColumn::Coordinates.text(&records[3]),
+            "approximate (match quality unknown)"
+        );
+        assert_eq!(TableColumn::Coordinates.text(&records[0]), "exact (house)");
+    }
+
+    #[test]
+    fn text_sorts_ignore_case_with_empty_values_first() {
+        let mut records = [
+            record(1, None, None, None),
+            record(2, None, None, None),
+            record(3, None, None, None),
+        ];
+        records[0].street_sign = "rue Bourbon".to_string();
+        records[1].street_sign = String::new();
+        records[2].street_sign = "Calle Real".to_string();
+        assert_eq!(sorted(&records, TableColumn::StreetSign, true), [2, 3, 1]);
+    }
+
+    #[test]
+    fn sorting_is_stable_in_both_directions() {
+        let mut records = [
+            record(1, Some("Graffiti"), Some(2), None),
+            record(2, Some("Good Condition"), Some(2), None),
+            record(3, Some("Graffiti"), Some(1), None),
+            record(4, Some("Graffiti"), Some(2), None),
+        ];
+        records[3].photo_1 = Some(PathBuf::from("Inventory_Images/4.Photo 1.120000.jpg"));
+        // Ties keep their order whichever way the column is sorted.
+        assert_eq!(
+            sorted(&records, TableColumn::DamagedTiles, true),
+            [3, 1, 2, 4]
+        );
+        assert_eq!(
+            sorted(&records, TableColumn::DamagedTiles, false),
+            [1, 2, 4, 3]
+        );
+        assert_eq!(
+            sorted(&records, TableColumn::Condition, false),
+            [1, 3, 4, 2]
+        );
+        assert_eq!(sorted(&records, TableColumn::Photos, true), [1, 2, 3, 4]);
+        assert_eq!(sorted(&records, TableColumn::Photos, false), [4, 1, 2, 3]);
+    }
+}
@@ -0,0 +1,247 @@
+use crate::boundary::{Boundary, Placement, DEFAULT_EDGE_MARGIN_M};
+use crate::inventory::TileInventory;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// The sign conditions on the survey form.
+pub const KNOWN_CONDITIONS: [&str; 6] = [
+    "Good Condition",
+    "Fair Condition",
+    "Faded/Cracked Polish",
+    "Graffiti",
+    "Falling Apart",
+    "No Ruiz de Luna Signature; Possible Non-Spanish Copy",
+];
+
+/// Spellings seen in spreadsheets for each known condition, compared after `condition_key`.
+const CONDITION_ALIASES: [(&str, &str); 9] = [
+    ("good", "Good Condition"),
+    ("fair", "Fair Condition"),
+    ("faded", "Faded/Cracked Polish"),
+    ("faded cracked", "Faded/Cracked Polish"),
+    ("faded and cracked polish", "Faded/Cracked Polish"),
+    ("grafitti", "Graffiti"),
+    ("graffitti", "Graffiti"),
+    ("possible non spanish copy", KNOWN_CONDITIONS[5]),
+    ("no ruiz de luna signature", KNOWN_CONDITIONS[5]),
+];
+
+/// The survey's spelling of `condition`, ignoring case, punctuation and spacing and
+/// accepting a few common variants, e.g. "good" or "faded / cracked polish".
+pub fn canonical_condition(condition: &str) -> Option<&'static str> {
+    let key = condition_key(condition);
+    KNOWN_CONDITIONS
+        .iter()
+        .find(|known| condition_key(known) == key)
+        .or_else(|| {
+            CONDITION_ALIASES
+                .iter()
+                .find(|(alias, _)| *alias == key)
+                .map(|(_, known)| known)
+        })
+        .copied()
+}
+
+fn condition_key(condition: &str) -> String {
+    condition
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+const PHOTO_COLUMNS: [&str; 5] = ["Photo 1", "Photo 2", "Photo 3", "Photo 4", "Photo 5"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Worth a look, but the record is usable.
+    Warning,
+    /// The record is wrong or can't be placed on the map.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem with one field of one record.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidationIssue {
+    pub id: u32,
+    /// The CSV column the problem is in.
+    pub field: &'static str,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ID {} {}: {}: {}",
+            self.id, self.field, self.severity, self.message
+        )
+    }
+}
+
+/// Checks a single record. Coordinates are checked against `boundary`, usually
+/// `Boundary::french_quarter()`.
+pub fn validate_record(item: &TileInventory, boundary: &Boundary) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    let mut issue = |field, severity, message: String| {
+        issues.push(ValidationIssue {
+            id: item.id,
+            field,
+            severity,
+            message,
+        })
+    };
+
+    if item.street_sign.trim().is_empty() {
+        issue("Street Sign", Severity::Error, "is empty".to_string());
+    }
+    if item.street_address.trim().is_empty() {
+        issue("Street Address", Severity::Error, "is empty".to_string());
+    }
+
+    match item.sign_condition.as_deref().map(str::trim) {
+        None | Some("") => issue("Sign Condition", Severity::Warning, "is empty".to_string()),
+        Some(condition) if !KNOWN_CONDITIONS.contains(&condition) => issue(
+            "Sign Condition",
+            Severity::Warning,
+            match canonical_condition(condition) {
+                Some(known) => format!("{:?} is spelled {:?} on the survey", condition, known),
+                None => format!("{:?} is not one of the survey's conditions", condition),
+            },
+        ),
+        Some(_) => {}
+    }
+    if item.sign_condition.as_deref() == Some("Good Condition")
+        && item.number_of_tiles_damaged.unwrap_or_default() > 0
+    {
+        issue(
+            "Number of Tiles Damaged",
+            Severity::Warning,
+            format!(
+                "{} damaged tiles on a sign in good condition",
+                item.number_of_tiles_damaged.unwrap_or_default()
+            ),
+        );
+    }
+
+    match (item.latitude, item.longitude) {
+        (None, None) => issue("latitude", Severity::Warning, "not geocoded".to_string()),
+        (Some(_), None) => issue(
+            "longitude",
+            Severity::Error,
+            "missing while latitude is set".to_string(),
+        ),
+        (None, Some(_)) => issue(
+            "latitude",
+            Severity::Error,
+            "missing while longitude is set".to_string(),
+        ),
+        (Some(latitude), Some(longitude)) => {
+            if !(-90.0..=90.0).contains(&latitude) {
+                issue(
+                    "latitude",
+                    Severity::Error,
+                    format!("{} is out of range", latitude),
+                );
+            } else if !(-180.0..=180.0).contains(&longitude) {
+                issue(
+                    "longitude",
+                    Severity::Error,
+                    format!("{} is out of range", longitude),
+                );
+            } else if boundary.classify(latitude, longitude, DEFAULT_EDGE_MARGIN_M)
+                == Placement::Outside
+            {
+                issue(
+                    "latitude",
+                    Severity::Warning,
+                    format!(
+                        "{}, {} is {:.0} m outside {}",
+                        latitude,
+                        longitude,
+                        boundary.distance_to_edge_m(latitude, longitude),
+                        boundary.name.as_deref().unwrap_or("the boundary")
+                    ),
+                );
+            }
+        }
+    }
+
+    if let Some(confidence) = item.geocode_confidence {
+        if !(0.0..=1.0).contains(&confidence) {
+            issue(
+                "geocode_confidence",
+                Severity::Error,
+                format!("{} is not between 0 and 1", confidence),
+            );
+        }
+    }
+
+    let slots = item.photo_slots();
+    if slots.iter().all(|slot| slot.is_none()) {
+        issue("Photo 1", Severity::Warning, "no photos".to_string());
+    }
+    if let Some(gap) = slots.iter().position(|slot| slot.is_none()) {
+        if let Some(after) = slots[gap..].iter().position(|slot| slot.is_some()) {
+            issue(
+                PHOTO_COLUMNS[gap],
+                Severity::Warning,
+                format!("is empty but {} is set", PHOTO_COLUMNS[gap + after]),
+            );
+        }
+    }
+    let mut seen = HashSet::new();
+    for (index, photo) in slots.into_iter().enumerate() {
+        if let Some(photo) = photo {
+            if !seen.insert(photo) {
+                issue(
+                    PHOTO_COLUMNS[index],
+                    Severity::Warning,
+                    format!("repeats {:?}", photo),
+                );
+            }
+        }
+    }
+
+    issues
+}
+
+/// Checks every record, plus IDs shared between records.
+pub fn validate_inventory(
+    inventory: &[TileInventory],
+    boundary: &Boundary,
+) -> Vec<ValidationIssue> {
+    let mut counts: HashMap<u32, usize> = HashMap::new();
+    for item in inventory {
+        *counts.entry(item.id).or_default() += 1;
+    }
+
+    let mut issues = Vec::new();
+    let mut reported = HashSet::new();
+    for item in inventory {
+        let count = counts[&item.id];
+        if count > 1 && reported.insert(item.id) {
+            issues.push(ValidationIssue {
+                id: item.id,
+                field: "ID",
+                severity: Severity::Error,
+                message: format!("used by {} records", count),
+            });
+        }
+        issues.extend(validate_record(item, boundary));
+    }
+    issues
+}
@@ -0,0 +1,715 @@
+use chrono::Utc;
+use data::boundary::{classify_inventory, Boundary, BoundaryCheck, BoundaryError, Placement};
+use data::inventory::TileInventory;
+use data::overrides::apply_overrides;
+use data::projection::Crs;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use structopt::StructOpt;
+use utils::csv_parser::{ensure_known_columns, parse_csv, parse_overrides, write_csv, CsvError};
+use utils::export::{export, ExportError};
+use utils::geocoding::{
+    geocode_inventory, plan_queries, read_checkpoint, remove_checkpoint, snap_to_corners,
+    unix_timestamp, verify_coordinates, write_review_csv, AddressNormalizer, BatchOptions,
+    CachedGeocoder, GeocodeCache, Geocoder, GeocodingError, IntersectionGeocoder,
+    NominatimGeocoder, OfflineGeocoder, Outcome, Region, RetryPolicy, Selection, StreetNetwork,
+    VerifyOptions,
+};
+
+/// Prints progress to stdout, or to stderr when stdout carries a JSON summary.
+macro_rules! status {
+    ($args:expr, $($arg:tt)*) => {
+        if $args.json {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
+/// Options for geocoding an inventory CSV, shared by `lat-long-finder` and
+/// `tile-inventory geocode`.
+#[derive(StructOpt)]
+pub struct GeocodeArgs {
+    #[structopt(parse(from_os_str), short = "i", long = "in")]
+    input: PathBuf,
+    #[structopt(
+        parse(from_os_str),
+        short = "o",
+        long = "out",
+        required_unless_one = &["in-place", "dry-run"]
+    )]
+    output: Option<PathBuf>,
+    /// Write the results back to --in, after copying it to <in>.<timestamp>.bak
+    #[structopt(long = "in-place", conflicts_with_all = &["output", "verify"])]
+    in_place: bool,
+    /// Only geocode these record IDs, e.g. "3,17,42"
+    #[structopt(long, use_delimiter = true)]
+    ids: Vec<u32>,
+    /// Only geocode records whose address, sign or modern street name contains this
+    #[structopt(long)]
+    street: Option<String>,
+    /// Geocode selected records again even if they already have coordinates, asking the
+    /// geocoder rather than the cache
+    #[structopt(long)]
+    force: bool,
+    /// Print the queries that would be sent and which the cache already answers, without
+    /// querying the geocoder or writing anything
+    #[structopt(long = "dry-run", conflicts_with = "verify")]
+    dry_run: bool,
+    /// Geocoding backend: "nominatim" or "offline"
+    #[structopt(long, default_value = "nominatim")]
+    geocoder: String,
+    /// Nominatim server to query instead of nominatim.openstreetmap.org
+    #[structopt(long = "nominatim-url")]
+    nominatim_url: Option<String>,
+    /// address,latitude,longitude CSV for the offline backend instead of the bundled table
+    #[structopt(parse(from_os_str), long)]
+    gazetteer: Option<PathBuf>,
+    /// JSON file remembering earlier lookups between runs
+    #[structopt(parse(from_os_str), long, default_value = "geocode_cache.json")]
+    cache: PathBuf,
+    /// Don't read or write the cache file
+    #[structopt(long = "no-cache")]
+    no_cache: bool,
+    /// Ignore cache entries older than this many days
+    #[structopt(long = "cache-ttl-days")]
+    cache_ttl_days: Option<u64>,
+    /// Re-query every address, replacing what the cache has
+    #[structopt(long)]
+    refresh: bool,
+    /// Progress file for resuming an interrupted run (default: <out>.checkpoint.jsonl)
+    #[structopt(parse(from_os_str), long)]
+    checkpoint: Option<PathBuf>,
+    /// Tries per address before a rate-limit or server error counts as failed
+    #[structopt(long, default_value = "4")]
+    retries: u32,
+    /// City assumed for addresses that don't name one
+    #[structopt(long, default_value = "New Orleans")]
+    city: String,
+    /// State sent with every query
+    #[structopt(long, default_value = "Louisiana")]
+    state: String,
+    /// ISO country code to restrict lookups to; empty for anywhere
+    #[structopt(long, default_value = "us")]
+    country: String,
+    /// After geocoding, move each sign to the corner of the street it names and the
+    /// building's street
+    #[structopt(long = "snap-to-corners", requires = "centerlines")]
+    snap_to_corners: bool,
+    /// street,latitude,longitude CSV of street centerlines for --snap-to-corners, one row per
+    /// vertex in order along each street, e.g. from an OpenStreetMap extract
+    #[structopt(parse(from_os_str), long)]
+    centerlines: Option<PathBuf>,
+    /// id,latitude,longitude,reason CSV of hand-corrected positions, applied last; skipped if
+    /// the file doesn't exist
+    #[structopt(parse(from_os_str), long, default_value = "coordinate_overrides.csv")]
+    overrides: PathBuf,
+    /// GeoJSON polygon that every record should fall inside, instead of the bundled French
+    /// Quarter outline
+    #[structopt(parse(from_os_str), long)]
+    boundary: Option<PathBuf>,
+    /// Records within this many meters of the boundary are reported as near the edge
+    #[structopt(long = "edge-margin", default_value = "50")]
+    edge_margin_m: f64,
+    /// Also write the results for GIS use to this .csv, .geojson, .kml or .json file, in --crs
+    #[structopt(parse(from_os_str), long = "export")]
+    export: Option<PathBuf>,
+    /// Coordinate system for --export: EPSG:4326 (latitude/longitude), EPSG:3452 (Louisiana
+    /// South state plane, US feet) or EPSG:32615 (UTM zone 15N)
+    #[structopt(long, default_value = "EPSG:4326")]
+    crs: Crs,
+    /// Instead of geocoding, reverse-geocode the stored coordinates and write a review CSV
+    /// of records whose address doesn't match to --out
+    #[structopt(long)]
+    verify: bool,
+    /// With --verify, flag matches further than this many meters from the stored coordinate
+    #[structopt(long = "max-distance", default_value = "25")]
+    max_distance_m: f64,
+    /// Set by callers that print the summary as JSON, to keep stdout clean.
+    #[structopt(skip)]
+    pub json: bool,
+}
+
+/// What a run did, for scripting.
+#[derive(Debug, Default, Serialize)]
+pub struct GeocodeSummary {
+    pub records: usize,
+    pub found: usize,
+    pub not_found: Vec<u32>,
+    /// IDs that failed to geocode and will be retried on the next run.
+    pub failed: Vec<u32>,
+    pub already_located: usize,
+    /// Records left out by --ids or --street.
+    pub filtered: usize,
+    pub resumed: usize,
+    pub snapped_to_corners: usize,
+    pub overrides_applied: usize,
+    pub outside_boundary: Vec<u32>,
+    pub near_boundary: Vec<u32>,
+    /// With --verify, IDs written to the review CSV.
+    pub needs_review: Option<Vec<u32>>,
+    /// With --dry-run, the lookups that would have been made.
+    pub dry_run: Option<Vec<PlannedLookup>>,
+    /// Where --in was copied before --in-place overwrote it.
+    pub backup: Option<PathBuf>,
+    /// Not set for --dry-run, which writes nothing.
+    pub output: Option<PathBuf>,
+}
+
+impl GeocodeSummary {
+    /// What a person has to follow up on, one line each: failed lookups, records outside the
+    /// boundary and, with --verify, coordinates that need review.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !self.failed.is_empty() {
+            problems.push(format!(
+                "{} addresses failed to geocode; rerun to retry them",
+                self.failed.len()
+            ));
+        }
+        if !self.outside_boundary.is_empty() {
+            problems.push(format!(
+                "{} records are outside the boundary; check their addresses",
+                self.outside_boundary.len()
+            ));
+        }
+        if let Some(ids) = self.needs_review.as_ref().filter(|ids| !ids.is_empty()) {
+            problems.push(format!(
+                "{} records need review; see the review CSV",
+                ids.len()
+            ));
+        }
+        problems
+    }
+
+    /// 1 when there are `problems`, 0 otherwise.
+    pub fn exit_code(&self) -> i32 {
+        if self.problems().is_empty() {
+            0
+        } else {
+            1
+        }
+    }
+}
+
+/// A lookup `--dry-run` found, and what the cache already knows about it.
+#[derive(Debug, Serialize)]
+pub struct PlannedLookup {
+    pub id: u32,
+    pub query: String,
+    /// Answered by the checkpoint of an interrupted run, which takes precedence over the cache.
+    pub resumed: bool,
+    pub cache_hit: bool,
+    /// The cached coordinates; empty for cache misses and for addresses cached as not found.
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("CSV error: {0}")]
+    Csv(#[from] CsvError),
+    #[error("Geocoding error: {0}")]
+    Geocoding(#[from] GeocodingError),
+    #[error("Export error: {0}")]
+    Export(#[from] ExportError),
+    #[error("Can't read boundary {path:?}: {source}")]
+    BoundaryFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Can't back up {path:?}: {source}")]
+    Backup {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Boundary error: {0}")]
+    Boundary(#[from] BoundaryError),
+    #[error("Unknown geocoder {0:?}, expected \"nominatim\" or \"offline\"")]
+    UnknownGeocoder(String),
+    #[error("--snap-to-corners needs a street centerline CSV from --centerlines")]
+    MissingCenterlines,
+}
+
+impl GeocodeArgs {
+    /// Where results are written: --out, or --in itself with --in-place.
+    fn output(&self) -> &Path {
+        match &self.output {
+            Some(output) if !self.in_place => output,
+            _ => &self.input,
+        }
+    }
+}
+
+/// Geocodes `args.input` and writes `args.output`, or with `args.verify` checks the stored
+/// coordinates instead. Records that failed to geocode don't make this an error; they are
+/// listed in the summary for the caller to act on.
+pub async fn run(args: &GeocodeArgs) -> Result<GeocodeSummary, Error> {
+    match args.geocoder.as_str() {
+        "nominatim" => {
+            let geocoder = match &args.nominatim_url {
+                Some(url) => NominatimGeocoder::with_base_url(url),
+                None => NominatimGeocoder::new(),
+            };
+            process(args, geocoder).await
+        }
+        "offline" => {
+            let geocoder = match &args.gazetteer {
+                Some(path) => OfflineGeocoder::from_path(path)?,
+                None => OfflineGeocoder::bundled()?,
+            };
+            process(args, geocoder).await
+        }
+        other => Err(Error::UnknownGeocoder(other.to_string())),
+    }
+}
+
+async fn process<G: Geocoder + Sync>(
+    args: &GeocodeArgs,
+    geocoder: G,
+) -> Result<GeocodeSummary, Error> {
+    status!(args, "Reading CSV from {:?}", args.input);
+    if args.in_place {
+        // Overwriting --in would lose any columns the inventory can't hold.
+        ensure_known_columns(&args.input)?;
+    }
+    let mut inventory = parse_csv(&args.input)?;
+    status!(args, "Successfully read {} records", inventory.len());
+    let mut summary = GeocodeSummary {
+        records: inventory.len(),
+        output: (!args.dry_run).then(|| args.output().to_path_buf()),
+        ..GeocodeSummary::default()
+    };
+
+    if args.verify {
+        summary.needs_review = Some(verify(args, &geocoder, &inventory).await?);
+        return Ok(summary);
+    }
+
+    let cache = if args.no_cache {
+        GeocodeCache::in_memory()
+    } else {
+        let cache = GeocodeCache::open(&args.cache)?;
+        status!(
+            args,
+            "Loaded {} cached lookups from {:?}",
+            cache.len(),
+            args.cache
+        );
+        cache
+    };
+    let ttl = args
+        .cache_ttl_days
+        .map(|days| Duration::from_secs(days * 24 * 60 * 60));
+    let cache = cache.with_ttl(ttl);
+
+    let checkpoint = args.checkpoint.clone().unwrap_or_else(|| {
+        let mut path = args.output().as_os_str().to_os_string();
+        path.push(".checkpoint.jsonl");
+        PathBuf::from(path)
+    });
+    let options = BatchOptions {
+        retry: RetryPolicy {
+            max_attempts: args.retries.max(1),
+            ..RetryPolicy::default()
+        },
+        checkpoint: Some(checkpoint.clone()),
+        normalizer: AddressNormalizer::new(Region {
+            city: args.city.clone(),
+            state: args.state.clone(),
+            country_code: Some(args.country.clone()).filter(|code| !code.is_empty()),
+        }),
+        selection: Selection {
+            ids: (!args.ids.is_empty()).then(|| args.ids.iter().copied().collect::<HashSet<_>>()),
+            street: args.street.clone(),
+            force: args.force,
+        },
+    };
+
+    if args.dry_run {
+        let lookups = dry_run(args, &geocoder, &cache, &inventory, &options)?;
+        let selected = inventory
+            .iter()
+            .filter(|item| options.selection.matches(item))
+            .count();
+        summary.filtered = inventory.len() - selected;
+        summary.already_located = selected - lookups.len();
+        summary.resumed = lookups.iter().filter(|lookup| lookup.resumed).count();
+        summary.dry_run = Some(lookups);
+        return Ok(summary);
+    }
+    // Only the selected records reach the geocoder, so forcing them bypasses the cache for
+    // them alone.
+    let geocoder = CachedGeocoder::new(geocoder, cache).refresh(args.refresh || args.force);
+
+    status!(args, "Geocoding addresses with {}...", geocoder.name());
+    let result = geocode_inventory(&geocoder, &mut inventory, &options).await;
+    // Keep whatever was looked up before a failure.
+    geocoder.save()?;
+    let report = result?;
+
+    for record in &report.outcomes {
+        match &record.outcome {
+            Outcome::Found { .. } => {}
+            Outcome::NotFound => status!(args, "Not found: ID {} ({})", record.id, record.address),
+            Outcome::Failed { error, transient } => status!(
+                args,
+                "Failed: ID {} ({}) after {} attempt(s): {}{}",
+                record.id,
+                record.address,
+                record.attempts,
+                error,
+                if *transient { " [transient]" } else { "" }
+            ),
+        }
+    }
+    status!(
+        args,
+        "Geocoding complete: {} found, {} not found, {} failed, {} already located, {} filtered out, {} resumed from {:?}",
+        report.found(),
+        report.not_found(),
+        report.failed(),
+        report.skipped,
+        report.filtered,
+        report.resumed,
+        checkpoint
+    );
+
+    summary.found = report.found();
+    summary.already_located = report.skipped;
+    summary.filtered = report.filtered;
+    summary.resumed = report.resumed;
+    for record in &report.outcomes {
+        match record.outcome {
+            Outcome::Found { .. } => {}
+            Outcome::NotFound => summary.not_found.push(record.id),
+            Outcome::Failed { .. } => summary.failed.push(record.id),
+        }
+    }
+
+    if args.snap_to_corners {
+        summary.snapped_to_corners = snap(args, &mut inventory)?;
+    }
+    summary.overrides_applied = apply_override_file(args, &mut inventory)?;
+    for check in check_boundary(args, &inventory)? {
+        match check.placement {
+            Placement::Inside => {}
+            Placement::NearEdge => summary.near_boundary.push(check.id),
+            Placement::Outside => summary.outside_boundary.push(check.id),
+        }
+    }
+
+    let output = args.output();
+    if args.in_place {
+        let backup = backup_path(output);
+        back_up(output, &backup).map_err(|source| Error::Backup {
+            path: output.to_path_buf(),
+            source,
+        })?;
+        status!(args, "Backed up {:?} to {:?}", output, backup);
+        summary.backup = Some(backup);
+    }
+    status!(args, "Writing results to {:?}", output);
+    write_csv(output, &inventory)?;
+    status!(args, "Processing complete. Output written to {:?}", output);
+    if let Some(path) = &args.export {
+        export(path, &inventory, args.crs)?;
+        status!(
+            args,
+            "Exported {} records in {} to {:?}",
+            inventory.len(),
+            args.crs,
+            path
+        );
+    }
+
+    // Failed records aren't kept in the checkpoint, so leave it for the rerun to pick up.
+    if report.failed() == 0 {
+        remove_checkpoint(&checkpoint)?;
+    }
+
+    Ok(summary)
+}
+
+/// Reports what a run would look up without looking anything up or touching the checkpoint.
+fn dry_run<G: Geocoder>(
+    args: &GeocodeArgs,
+    geocoder: &G,
+    cache: &GeocodeCache,
+    inventory: &[TileInventory],
+    options: &BatchOptions,
+) -> Result<Vec<PlannedLookup>, Error> {
+    let checkpointed = match &options.checkpoint {
+        Some(path) => read_checkpoint(path)?,
+        None => Default::default(),
+    };
+    let lookups: Vec<PlannedLookup> = plan_queries(inventory, options)
+        .into_iter()
+        .map(|planned| {
+            let query = planned.query.to_string();
+            if let Some(record) = checkpointed.get(&(planned.id, query.clone())) {
+                let coordinates = match &record.outcome {
+                    Outcome::Found {
+                        latitude,
+                        longitude,
+                        ..
+                    } => Some((*latitude, *longitude)),
+                    _ => None,
+                };
+                status!(
+                    args,
+                    "Resumed: ID {} {} -> {}",
+                    planned.id,
+                    query,
+                    coordinates.map_or("not found".to_string(), |(latitude, longitude)| {
+                        format!("{}/{}", latitude, longitude)
+                    })
+                );
+                return PlannedLookup {
+                    id: planned.id,
+                    query,
+                    resumed: true,
+                    cache_hit: false,
+                    latitude: coordinates.map(|(latitude, _)| latitude),
+                    longitude: coordinates.map(|(_, longitude)| longitude),
+                };
+            }
+            let entry = cache
+                .get(geocoder.name(), &query)
+                .filter(|_| !args.refresh && !args.force);
+            match entry {
+                Some(entry) => status!(
+                    args,
+                    "Cached ({}): ID {} {} -> {}",
+                    entry.provider,
+                    planned.id,
+                    query,
+                    entry.latitude.zip(entry.longitude).map_or(
+                        "not found".to_string(),
+                        |(latitude, longitude)| format!("{}/{}", latitude, longitude)
+                    )
+                ),
+                None => status!(
+                    args,
+                    "Query ({}): ID {} {}{}",
+                    geocoder.name(),
+                    planned.id,
+                    query,
+                    if planned.located {
+                        " [replacing existing coordinates]"
+                    } else {
+                        ""
+                    }
+                ),
+            }
+            PlannedLookup {
+                id: planned.id,
+                query,
+                resumed: false,
+                cache_hit: entry.is_some(),
+                latitude: entry.and_then(|entry| entry.latitude),
+                longitude: entry.and_then(|entry| entry.longitude),
+            }
+        })
+        .collect();
+    let resumed = lookups.iter().filter(|lookup| lookup.resumed).count();
+    let hits = lookups.iter().filter(|lookup| lookup.cache_hit).count();
+    status!(
+        args,
+        "Dry run: {} lookups, {} resumed from the checkpoint, {} answered by the cache, {} to send to {}; nothing written",
+        lookups.len(),
+        resumed,
+        hits,
+        lookups.len() - resumed - hits,
+        geocoder.name()
+    );
+    Ok(lookups)
+}
+
+/// Copies `path` to `backup`, refusing to replace an earlier backup made in the same second.
+fn back_up(path: &Path, backup: &Path) -> std::io::Result<()> {
+    let mut source = std::fs::File::open(path)?;
+    let mut copy = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(backup)
+        .map_err(|err| match err.kind() {
+            std::io::ErrorKind::AlreadyExists => std::io::Error::new(
+                err.kind(),
+                format!("{:?} already exists; try again in a second", backup),
+            ),
+            _ => err,
+        })?;
+    std::io::copy(&mut source, &mut copy)?;
+    Ok(())
+}
+
+/// `inventory.csv` -> `inventory.csv.20261018-153000.bak`, in UTC.
+fn backup_path(path: &Path) -> PathBuf {
+    let mut backup = path.as_os_str().to_os_string();
+    backup.push(Utc::now().format(".%Y%m%d-%H%M%S.bak").to_string());
+    PathBuf::from(backup)
+}
+
+/// Returns how many overrides were applied.
+fn apply_override_file(
+    args: &GeocodeArgs,
+    inventory: &mut [TileInventory],
+) -> Result<usize, Error> {
+    let path = &args.overrides;
+    if !path.exists() {
+        status!(args, "No coordinate overrides at {:?}", path);
+        return Ok(0);
+    }
+    let overrides = parse_overrides(path)?;
+    let report = apply_overrides(inventory, &overrides, unix_timestamp());
+    for applied in &report.applied {
+        status!(
+            args,
+            "Override: ID {} set to {}/{}{} ({})",
+            applied.id,
+            applied.latitude,
+            applied.longitude,
+            applied
+                .moved_m()
+                .map(|moved| format!(", moved {:.0} m", moved))
+                .unwrap_or_default(),
+            applied.reason
+        );
+    }
+    for id in &report.unknown_ids {
+        status!(args, "Override for unknown ID {} ignored", id);
+    }
+    for id in &report.duplicate_ids {
+        status!(
+            args,
+            "Override for ID {} listed more than once; the last one wins",
+            id
+        );
+    }
+    status!(
+        args,
+        "Applied {} coordinate overrides from {:?}",
+        report.applied.len(),
+        path
+    );
+    Ok(report.applied.len())
+}
+
+/// Reports records that landed outside the district, typically a geocoder matching the same
+/// street name across the river or out in Metairie.
+fn check_boundary(
+    args: &GeocodeArgs,
+    inventory: &[TileInventory],
+) -> Result<Vec<BoundaryCheck>, Error> {
+    let boundary = match &args.boundary {
+        Some(path) => {
+            let geojson = std::fs::read_to_string(path).map_err(|source| Error::BoundaryFile {
+                path: path.clone(),
+                source,
+            })?;
+            Boundary::from_geojson(&geojson)?
+        }
+        None => Boundary::french_quarter(),
+    };
+    let checks = classify_inventory(&boundary, inventory, args.edge_margin_m);
+    for check in &checks {
+        match check.placement {
+            Placement::Inside => {}
+            Placement::NearEdge => status!(
+                args,
+                "Near boundary: ID {} is {:.0} m from the edge",
+                check.id,
+                check.distance_m
+            ),
+            Placement::Outside => status!(
+                args,
+                "Outside boundary: ID {} is {:.0} m outside; check its address",
+                check.id,
+                check.distance_m
+            ),
+        }
+    }
+    let count = |placement| {
+        checks
+            .iter()
+            .filter(|check| check.placement == placement)
+            .count()
+    };
+    status!(
+        args,
+        "Boundary check against {}: {} inside, {} near the edge, {} outside",
+        boundary.name.as_deref().unwrap_or("custom boundary"),
+        count(Placement::Inside),
+        count(Placement::NearEdge),
+        count(Placement::Outside)
+    );
+    Ok(checks)
+}
+
+/// Returns how many signs were moved to a corner.
+fn snap(args: &GeocodeArgs, inventory: &mut [TileInventory]) -> Result<usize, Error> {
+    let Some(path) = &args.centerlines else {
+        return Err(Error::MissingCenterlines);
+    };
+    let network = StreetNetwork::from_path(path)?;
+    let report = snap_to_corners(&IntersectionGeocoder::new(network), inventory);
+    for corner in &report.located {
+        status!(
+            args,
+            "Corner: ID {} at {} & {}{}",
+            corner.id,
+            corner.sign_street,
+            corner.cross_street,
+            corner
+                .moved_m
+                .map(|moved| format!(", moved {:.0} m", moved))
+                .unwrap_or_default()
+        );
+    }
+    for (id, err) in &report.unresolved {
+        status!(args, "No corner for ID {}: {}", id, err);
+    }
+    status!(
+        args,
+        "Snapped {} signs to corners, {} left as geocoded",
+        report.located.len(),
+        report.unresolved.len()
+    );
+    Ok(report.located.len())
+}
+
+async fn verify<G: Geocoder>(
+    args: &GeocodeArgs,
+    geocoder: &G,
+    inventory: &[TileInventory],
+) -> Result<Vec<u32>, Error> {
+    let options = VerifyOptions {
+        retry: RetryPolicy {
+            max_attempts: args.retries.max(1),
+            ..RetryPolicy::default()
+        },
+        max_distance_m: args.max_distance_m,
+    };
+
+    status!(
+        args,
+        "Reverse geocoding stored coordinates with {}...",
+        geocoder.name()
+    );
+    let rows = verify_coordinates(geocoder, inventory, &options).await;
+    let review: Vec<_> = rows.into_iter().filter(|row| row.needs_review()).collect();
+    status!(
+        args,
+        "{} of {} records need review",
+        review.len(),
+        inventory.len()
+    );
+
+    write_review_csv(args.output(), &review)?;
+    status!(args, "Review CSV written to {:?}", args.output());
+    Ok(review.iter().map(|row| row.id).collect())
+}
@@ -0,0 +1,164 @@
+use std::path::PathBuf;
+use structopt::StructOpt;
+use utils::csv_parser::{parse_csv, write_csv, CsvError};
+use utils::photos::derivatives::{generate_derivatives, DerivativeError, DerivativeOptions};
+use utils::photos::exif_gps::locate_from_photos;
+use utils::photos::similarity::{find_similar_photos, parse_threshold};
+
+#[derive(StructOpt)]
+enum Cli {
+    /// Generate thumbnails and resized copies of every referenced photo, plus a manifest
+    Derivatives {
+        #[structopt(parse(from_os_str), short = "i", long = "in")]
+        input: PathBuf,
+        /// Directory the CSV's photo paths are relative to
+        #[structopt(parse(from_os_str), long = "base-dir", default_value = ".")]
+        base_dir: PathBuf,
+        /// Regenerate derivatives that are already up to date
+        #[structopt(long)]
+        force: bool,
+    },
+    /// Read GPS tags from each record's photos and store their median beside the geocoded
+    /// coordinate
+    ExifGps {
+        #[structopt(parse(from_os_str), short = "i", long = "in")]
+        input: PathBuf,
+        #[structopt(parse(from_os_str), short = "o", long = "out")]
+        output: PathBuf,
+        /// Directory the CSV's photo paths are relative to
+        #[structopt(parse(from_os_str), long = "base-dir", default_value = ".")]
+        base_dir: PathBuf,
+    },
+    /// Group near-identical photos within and across records using perceptual hashes
+    Duplicates {
+        #[structopt(parse(from_os_str), short = "i", long = "in")]
+        input: PathBuf,
+        /// Directory the CSV's photo paths are relative to
+        #[structopt(parse(from_os_str), long = "base-dir", default_value = ".")]
+        base_dir: PathBuf,
+        /// Maximum Hamming distance (0-64) for two photos to count as near-identical
+        #[structopt(short = "t", long, default_value = "10", parse(try_from_str = parse_threshold))]
+        threshold: u32,
+    },
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("Error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+enum AppError {
+    #[error("CSV error: {0}")]
+    Csv(#[from] CsvError),
+    #[error("Derivative error: {0}")]
+    Derivative(#[from] DerivativeError),
+    #[error("{0} photo(s) could not be processed")]
+    Failed(usize),
+}
+
+fn run() -> Result<(), AppError> {
+    match Cli::from_args() {
+        Cli::Derivatives {
+            input,
+            base_dir,
+            force,
+        } => {
+            println!("Reading CSV from {:?}", input);
+            let inventory = parse_csv(&input)?;
+            println!("Successfully read {} records", inventory.len());
+
+            let report = generate_derivatives(&inventory, &base_dir, &DerivativeOptions { force })?;
+            for err in &report.failed {
+                eprintln!("{}", err);
+            }
+            println!(
+                "Generated {} photo(s), {} already up to date, manifest lists {}",
+                report.generated,
+                report.skipped,
+                report.manifest.entries.len()
+            );
+
+            if !report.failed.is_empty() {
+                return Err(AppError::Failed(report.failed.len()));
+            }
+        }
+        Cli::ExifGps {
+            input,
+            output,
+            base_dir,
+        } => {
+            println!("Reading CSV from {:?}", input);
+            let mut inventory = parse_csv(&input)?;
+            println!("Successfully read {} records", inventory.len());
+
+            let report = locate_from_photos(&mut inventory, &base_dir);
+            for err in &report.failed {
+                eprintln!("{}", err);
+            }
+            for location in &report.locations {
+                let distance = location
+                    .distance_m
+                    .map_or("no geocoded coordinate".to_string(), |distance| {
+                        format!("{:.1} m from geocoded", distance)
+                    });
+                println!(
+                    "ID {}: {} {:.7}, {:.7} from {} photo(s), {}",
+                    location.id,
+                    location.source,
+                    location.latitude,
+                    location.longitude,
+                    location.photos_with_gps,
+                    distance
+                );
+            }
+            println!(
+                "{} record(s) located from photos, {} without GPS tags",
+                report.locations.len(),
+                report.without_gps.len()
+            );
+
+            println!("Writing results to {:?}", output);
+            write_csv(&output, &inventory)?;
+        }
+        Cli::Duplicates {
+            input,
+            base_dir,
+            threshold,
+        } => {
+            println!("Reading CSV from {:?}", input);
+            let inventory = parse_csv(&input)?;
+            println!("Successfully read {} records", inventory.len());
+
+            let report = find_similar_photos(&inventory, &base_dir, threshold);
+            for err in &report.failed {
+                eprintln!("{}", err);
+            }
+            for group in &report.groups {
+                let scope = if group.spans_records() {
+                    "across records"
+                } else {
+                    "within record"
+                };
+                println!(
+                    "Group of {} ({}, max distance {}):",
+                    group.photos.len(),
+                    scope,
+                    group.max_distance
+                );
+                for photo in &group.photos {
+                    println!("  ID {} Photo {}: {:?}", photo.id, photo.slot, photo.path);
+                }
+            }
+            println!(
+                "{} group(s) of near-identical photos at threshold {}",
+                report.groups.len(),
+                threshold
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,779 @@
+use data::boundary::Boundary;
+use data::inventory::TileInventory;
+use data::validation::{
+    canonical_condition, validate_inventory, Severity, ValidationIssue, KNOWN_CONDITIONS,
+};
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::widgets::TableState;
+use std::cmp::Ordering;
+use std::path::PathBuf;
+use utils::csv_parser::write_csv;
+use utils::geocoding::{modern_street_name, GeocodeHit, GeocodingError};
+
+/// Columns the list can be sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Id,
+    Sign,
+    Address,
+    Condition,
+    Damaged,
+    Issues,
+}
+
+impl SortKey {
+    pub const ALL: [SortKey; 6] = [
+        SortKey::Id,
+        SortKey::Sign,
+        SortKey::Address,
+        SortKey::Condition,
+        SortKey::Damaged,
+        SortKey::Issues,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            SortKey::Id => "ID",
+            SortKey::Sign => "Sign",
+            SortKey::Address => "Address",
+            SortKey::Condition => "Condition",
+            SortKey::Damaged => "Dmg",
+            SortKey::Issues => "!",
+        }
+    }
+
+    fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&key| key == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+/// The columns volunteers can edit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Condition,
+    Damaged,
+    Address,
+    Notes,
+}
+
+impl Field {
+    pub fn title(self) -> &'static str {
+        match self {
+            Field::Condition => "Sign Condition",
+            Field::Damaged => "Number of Tiles Damaged",
+            Field::Address => "Street Address",
+            Field::Notes => "Notes",
+        }
+    }
+}
+
+pub enum Mode {
+    Browse,
+    /// Typing into the filter box.
+    Filter,
+    Edit {
+        field: Field,
+        buffer: String,
+        /// Why the last attempt to commit `buffer` was refused.
+        error: Option<String>,
+    },
+    /// Quit was pressed with unsaved changes.
+    ConfirmQuit,
+}
+
+/// What the event loop has to do for the app, because the app can't do it itself.
+pub enum Command {
+    /// Geocode the selected record's address again.
+    Geocode,
+}
+
+pub struct App {
+    pub inventory: Vec<TileInventory>,
+    /// Where `w` saves to.
+    pub path: PathBuf,
+    /// Indexes into `inventory` of the records shown, in display order.
+    pub view: Vec<usize>,
+    pub table: TableState,
+    pub filter: String,
+    /// Only show records with validation issues.
+    pub only_issues: bool,
+    pub sort: SortKey,
+    pub descending: bool,
+    pub mode: Mode,
+    pub modified: bool,
+    /// One-line message for the footer, replaced by the key help once read.
+    pub status: Option<String>,
+    pub quit: bool,
+    /// Columns of the input `write_csv` can't write; saving is refused while there are any,
+    /// since it would drop them.
+    unknown_columns: Vec<String>,
+    boundary: Boundary,
+    issues: Vec<ValidationIssue>,
+}
+
+impl App {
+    pub fn new(inventory: Vec<TileInventory>, path: PathBuf, unknown_columns: Vec<String>) -> Self {
+        let status =
+            (!unknown_columns.is_empty()).then(|| unknown_columns_message(&unknown_columns));
+        let mut app = Self {
+            inventory,
+            path,
+            view: Vec::new(),
+            table: TableState::default(),
+            filter: String::new(),
+            only_issues: false,
+            sort: SortKey::Id,
+            descending: false,
+            mode: Mode::Browse,
+            modified: false,
+            status,
+            quit: false,
+            unknown_columns,
+            boundary: Boundary::french_quarter(),
+            issues: Vec::new(),
+        };
+        app.revalidate();
+        app
+    }
+
+    pub fn selected_item(&self) -> Option<&TileInventory> {
+        self.selected_index().map(|index| &self.inventory[index])
+    }
+
+    /// Validation issues for records with `id`.
+    pub fn issues_for(&self, id: u32) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues.iter().filter(move |issue| issue.id == id)
+    }
+
+    /// The worst severity among `id`'s issues and how many there are.
+    pub fn issue_summary(&self, id: u32) -> Option<(Severity, usize)> {
+        let issues: Vec<_> = self.issues_for(id).collect();
+        let worst = issues.iter().map(|issue| issue.severity).max()?;
+        Some((worst, issues.len()))
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<Command> {
+        let interrupt =
+            key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c');
+        // At the prompt, Ctrl-C is "any other key" and keeps the editor open.
+        if interrupt && !matches!(self.mode, Mode::ConfirmQuit) {
+            self.request_quit();
+            return None;
+        }
+        match self.mode {
+            Mode::Browse => return self.browse_key(key),
+            Mode::Filter => self.filter_key(key),
+            Mode::Edit { .. } => self.edit_key(key),
+            Mode::ConfirmQuit => match key.code {
+                KeyCode::Char('y') => self.quit = true,
+                KeyCode::Char('w') => self.quit = self.save(),
+                _ => self.mode = Mode::Browse,
+            },
+        }
+        None
+    }
+
+    fn browse_key(&mut self, key: KeyEvent) -> Option<Command> {
+        self.status = None;
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::PageUp => self.move_selection(-10),
+            KeyCode::PageDown => self.move_selection(10),
+            KeyCode::Home => self.move_selection(isize::MIN),
+            KeyCode::End => self.move_selection(isize::MAX),
+            KeyCode::Char('/') => self.mode = Mode::Filter,
+            KeyCode::Char('i') => {
+                self.only_issues = !self.only_issues;
+                self.refresh_view();
+            }
+            KeyCode::Char('s') => {
+                self.sort = self.sort.next();
+                self.refresh_view();
+            }
+            KeyCode::Char('r') => {
+                self.descending = !self.descending;
+                self.refresh_view();
+            }
+            KeyCode::Char('c') => self.begin_edit(Field::Condition),
+            KeyCode::Char('d') => self.begin_edit(Field::Damaged),
+            KeyCode::Char('a') => self.begin_edit(Field::Address),
+            KeyCode::Char('n') => self.begin_edit(Field::Notes),
+            KeyCode::Char('g') if self.selected_index().is_some() => return Some(Command::Geocode),
+            KeyCode::Char('w') => {
+                self.save();
+            }
+            KeyCode::Char('q') | KeyCode::Esc => self.request_quit(),
+            _ => {}
+        }
+        None
+    }
+
+    /// Quits, or asks first if there are unsaved changes.
+    fn request_quit(&mut self) {
+        if self.modified {
+            self.mode = Mode::ConfirmQuit;
+        } else {
+            self.quit = true;
+        }
+    }
+
+    fn filter_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Enter => self.mode = Mode::Browse,
+            KeyCode::Esc => {
+                self.filter.clear();
+                self.mode = Mode::Browse;
+            }
+            KeyCode::Backspace => {
+                self.filter.pop();
+            }
+            KeyCode::Char(c) => self.filter.push(c),
+            _ => return,
+        }
+        self.refresh_view();
+    }
+
+    fn edit_key(&mut self, key: KeyEvent) {
+        let Mode::Edit {
+            field,
+            buffer,
+            error,
+        } = &mut self.mode
+        else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => self.mode = Mode::Browse,
+            KeyCode::Enter => {
+                let (field, value) = (*field, buffer.clone());
+                match self.commit(field, &value) {
+                    Ok(()) => self.mode = Mode::Browse,
+                    Err(message) => {
+                        if let Mode::Edit { error, .. } = &mut self.mode {
+                            *error = Some(message);
+                        }
+                    }
+                }
+            }
+            KeyCode::Backspace => {
+                buffer.pop();
+            }
+            KeyCode::Up | KeyCode::Down | KeyCode::Tab if *field == Field::Condition => {
+                *buffer = cycle_condition(buffer, key.code == KeyCode::Up).to_string();
+                *error = None;
+            }
+            KeyCode::Char(c) => {
+                buffer.push(c);
+                *error = None;
+            }
+            _ => {}
+        }
+    }
+
+    fn begin_edit(&mut self, field: Field) {
+        let Some(item) = self.selected_item() else {
+            return;
+        };
+        let buffer = match field {
+            Field::Condition => item.sign_condition.clone().unwrap_or_default(),
+            Field::Damaged => item
+                .number_of_tiles_damaged
+                .map(|count| count.to_string())
+                .unwrap_or_default(),
+            Field::Address => item.street_address.clone(),
+            Field::Notes => item.notes.clone().unwrap_or_default(),
+        };
+        self.mode = Mode::Edit {
+            field,
+            buffer,
+            error: None,
+        };
+    }
+
+    /// Checks `value` and stores it in the selected record, or says why it can't.
+    fn commit(&mut self, field: Field, value: &str) -> Result<(), String> {
+        let Some(index) = self.selected_index() else {
+            return Ok(());
+        };
+        let value = value.trim();
+        let item = &mut self.inventory[index];
+        let changed = match field {
+            Field::Condition => {
+                let condition = match value {
+                    "" => None,
+                    _ => Some(
+                        canonical_condition(value)
+                            .ok_or_else(|| {
+                                format!(
+                                    "{:?} is not one of the survey's conditions; use ↑/↓ to pick one",
+                                    value
+                                )
+                            })?
+                            .to_string(),
+                    ),
+                };
+                replace(&mut item.sign_condition, condition)
+            }
+            Field::Damaged => {
+                let count = match value {
+                    "" => None,
+                    _ => Some(
+                        value
+                            .parse::<u32>()
+                            .map_err(|_| format!("{:?} is not a whole number of tiles", value))?,
+                    ),
+                };
+                replace(&mut item.number_of_tiles_damaged, count)
+            }
+            Field::Address => {
+                if value.is_empty() {
+                    return Err("the address can't be empty".to_string());
+                }
+                let changed = replace(&mut item.street_address, value.to_string());
+                if changed && item.latitude.is_some() {
+                    // They were found for the old address, and would otherwise be saved
+                    // claiming the new one to house-level accuracy.
+                    item.latitude = None;
+                    item.longitude = None;
+                    item.set_geocode_result(None);
+                    self.status = Some(format!(
+                        "ID {}: address changed, coordinates cleared; press g to geocode it",
+                        item.id
+                    ));
+                } else if changed {
+                    self.status = Some(format!(
+                        "ID {}: address changed; press g to geocode it",
+                        item.id
+                    ));
+                }
+                changed
+            }
+            Field::Notes => replace(
+                &mut item.notes,
+                Some(value.to_string()).filter(|v| !v.is_empty()),
+            ),
+        };
+        if changed {
+            self.modified = true;
+            self.revalidate();
+        }
+        Ok(())
+    }
+
+    /// Stores the result of geocoding the selected record's address as `query`.
+    pub fn apply_geocode(
+        &mut self,
+        query: &str,
+        result: Result<Option<GeocodeHit>, GeocodingError>,
+    ) {
+        let Some(index) = self.selected_index() else {
+            return;
+        };
+        let item = &mut self.inventory[index];
+        self.status = Some(match result {
+            Ok(Some(hit)) => {
+                item.latitude = Some(hit.latitude);
+                item.longitude = Some(hit.longitude);
+                item.set_geocode_result(Some(hit.result));
+                self.modified = true;
+                format!(
+                    "ID {}: {}, {} ({})",
+                    item.id,
+                    hit.latitude,
+                    hit.longitude,
+                    item.geocode_display_name
+                        .as_deref()
+                        .or(item.geocode_provider.as_deref())
+                        .unwrap_or_default()
+                )
+            }
+            Ok(None) => format!("ID {}: {} not found; coordinates unchanged", item.id, query),
+            Err(err) => format!("ID {}: geocoding failed: {}", item.id, err),
+        });
+        self.revalidate();
+    }
+
+    /// Writes the inventory back out, returning whether it worked.
+    pub fn save(&mut self) -> bool {
+        if !self.unknown_columns.is_empty() {
+            self.status = Some(unknown_columns_message(&self.unknown_columns));
+            self.mode = Mode::Browse;
+            return false;
+        }
+        match write_csv(&self.path, &self.inventory) {
+            Ok(()) => {
+                self.modified = false;
+                self.status = Some(format!(
+                    "Saved {} records to {}",
+                    self.inventory.len(),
+                    self.path.display()
+                ));
+                true
+            }
+            Err(err) => {
+                self.status = Some(format!("Save failed: {}", err));
+                self.mode = Mode::Browse;
+                false
+            }
+        }
+    }
+
+    fn selected_index(&self) -> Option<usize> {
+        self.table
+            .selected()
+            .and_then(|row| self.view.get(row))
+            .copied()
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        if self.view.is_empty() {
+            return;
+        }
+        let current = self.table.selected().unwrap_or(0) as isize;
+        let last = self.view.len() as isize - 1;
+        let row = current.saturating_add(delta).clamp(0, last);
+        self.table.select(Some(row as usize));
+    }
+
+    fn revalidate(&mut self) {
+        self.issues = validate_inventory(&self.inventory, &self.boundary);
+        self.refresh_view();
+    }
+
+    /// Re-applies the filter and sort, keeping the same record selected if it is still shown.
+    fn refresh_view(&mut self) {
+        let selected = self.selected_index();
+        let query = self.filter.to_lowercase();
+        let mut view: Vec<usize> = (0..self.inventory.len())
+            .filter(|&index| {
+                let item = &self.inventory[index];
+                matches_filter(item, &query)
+                    && (!self.only_issues || self.issues_for(item.id).next().is_some())
+            })
+            .collect();
+        view.sort_by(|&a, &b| {
+            let ordering = self
+                .compare(&self.inventory[a], &self.inventory[b])
+                .then_with(|| self.inventory[a].id.cmp(&self.inventory[b].id));
+            if self.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        self.view = view;
+
+        let row = selected
+            .and_then(|index| self.view.iter().position(|&shown| shown == index))
+            .or((!self.view.is_empty()).then_some(0));
+        self.table.select(row);
+    }
+
+    fn compare(&self, a: &TileInventory, b: &TileInventory) -> Ordering {
+        match self.sort {
+            SortKey::Id => a.id.cmp(&b.id),
+            SortKey::Sign => a.street_sign.cmp(&b.street_sign),
+            SortKey::Address => a.street_address.cmp(&b.street_address),
+            SortKey::Condition => condition_rank(a).cmp(&condition_rank(b)),
+            SortKey::Damaged => a.number_of_tiles_damaged.cmp(&b.number_of_tiles_damaged),
+            SortKey::Issues => self.issue_summary(a.id).cmp(&self.issue_summary(b.id)),
+        }
+    }
+}
+
+fn unknown_columns_message(columns: &[String]) -> String {
+    format!(
+        "Saving is disabled: it would drop the columns {}",
+        columns.join(", ")
+    )
+}
+
+/// Sets `slot` to `value`, returning whether that changed it.
+fn replace<T: PartialEq>(slot: &mut T, value: T) -> bool {
+    if *slot == value {
+        return false;
+    }
+    *slot = value;
+    true
+}
+
+/// Matches the ID, sign, the sign's modern street, address, condition and notes, ignoring case.
+fn matches_filter(item: &TileInventory, query: &str) -> bool {
+    if query.is_empty() {
+        return true;
+    }
+    [
+        Some(item.id.to_string().as_str()),
+        Some(item.street_sign.as_str()),
+        modern_street_name(&item.street_sign),
+        Some(item.street_address.as_str()),
+        item.sign_condition.as_deref(),
+        item.notes.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    .any(|text| text.to_lowercase().contains(query))
+}
+
+/// Position on the survey form, with unknown conditions after the known ones.
+fn condition_rank(item: &TileInventory) -> usize {
+    item.sign_condition
+        .as_deref()
+        .and_then(|condition| {
+            KNOWN_CONDITIONS
+                .iter()
+                .position(|&known| known == condition)
+        })
+        .unwrap_or(KNOWN_CONDITIONS.len())
+}
+
+/// The known condition before or after `current`, wrapping around.
+fn cycle_condition(current: &str, backwards: bool) -> &'static str {
+    let count = KNOWN_CONDITIONS.len();
+    let index = match KNOWN_CONDITIONS.iter().position(|&known| known == current) {
+        Some(index) if backwards => (index + count - 1) % count,
+        Some(index) => (index + 1) % count,
+        None if backwards => count - 1,
+        None => 0,
+    };
+    KNOWN_CONDITIONS[index]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::geo::{GeocodeResult, MatchType};
+
+    fn inventory() -> Vec<TileInventory> {
+        vec![
+            TileInventory {
+                sign_condition: Some("Good Condition".to_string()),
+                number_of_tiles_damaged: Some(2),
+                latitude: Some(29.9557409),
+                longitude: Some(-90.0686785),
+                geocode_provider: Some("nominatim".to_string()),
+                geocode_match_type: Some(MatchType::House),
+                geocode_confidence: Some(0.9),
+                ..TileInventory::new(1, "Calle D'Bienville", "813 Bienville St")
+            },
+            TileInventory {
+                sign_condition: Some("Falling Apart".to_string()),
+                number_of_tiles_damaged: Some(10),
+                photo_1: Some(PathBuf::from("Inventory_Images/2-1.jpg")),
+                latitude: Some(29.9553),
+                longitude: Some(-90.0675),
+                ..TileInventory::new(2, "Calle Real", "301 Royal St")
+            },
+            TileInventory {
+                number_of_tiles_damaged: Some(9),
+                ..TileInventory::new(3, "Calle D'Conti", "500 Conti St")
+            },
+        ]
+    }
+
+    fn app() -> App {
+        App::new(inventory(), PathBuf::from("unused.csv"), Vec::new())
+    }
+
+    fn press(app: &mut App, code: KeyCode) -> Option<Command> {
+        app.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        for c in text.chars() {
+            press(app, KeyCode::Char(c));
+        }
+    }
+
+    /// Opens `key`'s field on the selected record, replaces its value and presses Enter.
+    fn edit(app: &mut App, key: char, value: &str) {
+        press(app, KeyCode::Char(key));
+        if let Mode::Edit { buffer, .. } = &mut app.mode {
+            buffer.clear();
+        }
+        type_text(app, value);
+        press(app, KeyCode::Enter);
+    }
+
+    fn shown_ids(app: &App) -> Vec<u32> {
+        app.view
+            .iter()
+            .map(|&index| app.inventory[index].id)
+            .collect()
+    }
+
+    #[test]
+    fn commits_valid_edits() {
+        let mut app = app();
+        edit(&mut app, 'c', "faded / cracked polish");
+        edit(&mut app, 'd', " 7 ");
+        edit(&mut app, 'n', "behind a balcony");
+        assert!(matches!(app.mode, Mode::Browse));
+        assert!(app.modified);
+        let item = app.selected_item().unwrap();
+        assert_eq!(item.sign_condition.as_deref(), Some("Faded/Cracked Polish"));
+        assert_eq!(item.number_of_tiles_damaged, Some(7));
+        assert_eq!(item.notes.as_deref(), Some("behind a balcony"));
+
+        edit(&mut app, 'n', "");
+        assert_eq!(app.selected_item().unwrap().notes, None);
+    }
+
+    #[test]
+    fn refuses_invalid_values_and_keeps_editing() {
+        let mut app = app();
+        for (key, value) in [('d', "lots"), ('c', "shiny"), ('a', "  ")] {
+            edit(&mut app, key, value);
+            assert!(matches!(app.mode, Mode::Edit { error: Some(_), .. }));
+            // Typing clears the message; Esc drops the edit.
+            type_text(&mut app, "x");
+            assert!(matches!(app.mode, Mode::Edit { error: None, .. }));
+            press(&mut app, KeyCode::Esc);
+            assert!(matches!(app.mode, Mode::Browse));
+        }
+        assert!(!app.modified);
+        assert_eq!(
+            app.selected_item().unwrap().number_of_tiles_damaged,
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn an_unchanged_value_is_not_a_modification() {
+        let mut app = app();
+        edit(&mut app, 'a', "813 Bienville St");
+        edit(&mut app, 'c', "Good Condition");
+        assert!(!app.modified);
+        assert_eq!(app.selected_item().unwrap().latitude, Some(29.9557409));
+    }
+
+    #[test]
+    fn changing_the_address_clears_its_coordinates() {
+        let mut app = app();
+        edit(&mut app, 'a', "815 Bienville St");
+        let item = app.selected_item().unwrap();
+        assert_eq!(item.street_address, "815 Bienville St");
+        assert_eq!((item.latitude, item.longitude), (None, None));
+        assert_eq!(item.geocode_result(), None);
+        assert_eq!(item.geocode_match_type, None);
+        assert!(app
+            .status
+            .as_deref()
+            .unwrap()
+            .contains("coordinates cleared"));
+
+        let hit = GeocodeHit {
+            latitude: 29.9558,
+            longitude: -90.0685,
+            result: GeocodeResult {
+                provider: "mock".to_string(),
+                display_name: None,
+                match_type: MatchType::House,
+                confidence: 1.0,
+                timestamp: 0,
+            },
+            raw: Default::default(),
+        };
+        app.apply_geocode("815 Bienville Street", Ok(Some(hit)));
+        let item = app.selected_item().unwrap();
+        assert_eq!(item.latitude, Some(29.9558));
+        assert_eq!(item.geocode_provider.as_deref(), Some("mock"));
+    }
+
+    #[test]
+    fn condition_edits_cycle_through_the_survey_values() {
+        assert_eq!(cycle_condition("", false), KNOWN_CONDITIONS[0]);
+        assert_eq!(cycle_condition("", true), KNOWN_CONDITIONS[5]);
+        assert_eq!(
+            cycle_condition(KNOWN_CONDITIONS[5], false),
+            KNOWN_CONDITIONS[0]
+        );
+        assert_eq!(
+            cycle_condition(KNOWN_CONDITIONS[0], true),
+            KNOWN_CONDITIONS[5]
+        );
+
+        let mut app = app();
+        press(&mut app, KeyCode::Char('c'));
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(
+            app.selected_item().unwrap().sign_condition.as_deref(),
+            Some(KNOWN_CONDITIONS[1])
+        );
+    }
+
+    #[test]
+    fn filters_and_sorts_keeping_the_selection() {
+        let mut app = app();
+        assert_eq!(shown_ids(&app), [1, 2, 3]);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.selected_item().unwrap().id, 2);
+
+        // Damaged, descending: 10, 9, 2.
+        while app.sort != SortKey::Damaged {
+            press(&mut app, KeyCode::Char('s'));
+        }
+        press(&mut app, KeyCode::Char('r'));
+        assert_eq!(shown_ids(&app), [2, 3, 1]);
+        assert_eq!(app.selected_item().unwrap().id, 2);
+
+        // "Royal" matches the modern name of Calle Real.
+        press(&mut app, KeyCode::Char('/'));
+        type_text(&mut app, "ROYAL");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(shown_ids(&app), [2]);
+        press(&mut app, KeyCode::Char('/'));
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(shown_ids(&app), [2, 3, 1]);
+
+        // Only record 2 has a photo, a known condition and coordinates; the selection moves
+        // off it when it is hidden.
+        press(&mut app, KeyCode::Char('i'));
+        assert_eq!(shown_ids(&app), [3, 1]);
+        assert_eq!(app.selected_item().unwrap().id, 3);
+    }
+
+    #[test]
+    fn asks_before_quitting_with_unsaved_changes() {
+        let mut app = app();
+        edit(&mut app, 'd', "3");
+        press(&mut app, KeyCode::Char('q'));
+        assert!(matches!(app.mode, Mode::ConfirmQuit));
+        // Any other key, Ctrl-C included, goes back to the list.
+        app.handle_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL));
+        assert!(matches!(app.mode, Mode::Browse));
+        assert!(!app.quit);
+        press(&mut app, KeyCode::Char('q'));
+        press(&mut app, KeyCode::Char('y'));
+        assert!(app.quit);
+
+        let mut app = self::app();
+        press(&mut app, KeyCode::Char('q'));
+        assert!(app.quit);
+    }
+
+    #[test]
+    fn saves_and_refuses_to_drop_unknown_columns() {
+        let path =
+            std::env::temp_dir().join(format!("tile-editor-test-{}.csv", std::process::id()));
+        let mut app = App::new(inventory(), path.clone(), Vec::new());
+        edit(&mut app, 'd', "4");
+        press(&mut app, KeyCode::Char('w'));
+        assert!(!app.modified);
+        let saved = utils::csv_parser::parse_csv(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(saved.len(), 3);
+        assert_eq!(saved[0].number_of_tiles_damaged, Some(4));
+        assert_eq!(saved[0].geocode_match_type, Some(MatchType::House));
+
+        let mut app = App::new(inventory(), path.clone(), vec!["Tile Maker".to_string()]);
+        edit(&mut app, 'd', "4");
+        assert!(!app.save());
+        assert!(app.modified);
+        assert!(!path.exists());
+        assert!(app.status.as_deref().unwrap().contains("Tile Maker"));
+    }
+}
@@ -0,0 +1,133 @@
+mod app;
+mod ui;
+
+use app::{App, Command};
+use ratatui::crossterm::event::{self, Event, KeyEventKind};
+use ratatui::DefaultTerminal;
+use std::path::PathBuf;
+use structopt::StructOpt;
+use tokio::runtime::Runtime;
+use utils::csv_parser::{parse_csv, unknown_columns, CsvError};
+use utils::geocoding::{
+    AddressNormalizer, CachedGeocoder, GeocodeCache, Geocoder, GeocodingError, NominatimGeocoder,
+    OfflineGeocoder,
+};
+
+/// Browse and edit an inventory CSV in the terminal, checking each change as it is made
+#[derive(StructOpt)]
+struct Cli {
+    #[structopt(parse(from_os_str), short = "i", long = "in")]
+    input: PathBuf,
+    /// Save to this file instead of back to --in
+    #[structopt(parse(from_os_str), short = "o", long = "out")]
+    output: Option<PathBuf>,
+    /// Geocoding backend for re-geocoding a record: "nominatim" or "offline"
+    #[structopt(long, default_value = "nominatim")]
+    geocoder: String,
+    /// Nominatim server to query instead of nominatim.openstreetmap.org
+    #[structopt(long = "nominatim-url")]
+    nominatim_url: Option<String>,
+    /// JSON file remembering earlier lookups, shared with lat-long-finder
+    #[structopt(parse(from_os_str), long, default_value = "geocode_cache.json")]
+    cache: PathBuf,
+    /// Don't read or write the cache file
+    #[structopt(long = "no-cache")]
+    no_cache: bool,
+}
+
+#[derive(thiserror::Error, Debug)]
+enum AppError {
+    #[error("CSV error: {0}")]
+    Csv(#[from] CsvError),
+    #[error("Geocoding error: {0}")]
+    Geocoding(#[from] GeocodingError),
+    #[error("Terminal error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Unknown geocoder {0:?}, expected \"nominatim\" or \"offline\"")]
+    UnknownGeocoder(String),
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("Error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), AppError> {
+    let cli = Cli::from_args();
+    match cli.geocoder.as_str() {
+        "nominatim" => {
+            let geocoder = match &cli.nominatim_url {
+                Some(url) => NominatimGeocoder::with_base_url(url),
+                None => NominatimGeocoder::new(),
+            };
+            edit(&cli, geocoder)
+        }
+        "offline" => edit(&cli, OfflineGeocoder::bundled()?),
+        other => Err(AppError::UnknownGeocoder(other.to_string())),
+    }
+}
+
+fn edit<G: Geocoder + Sync>(cli: &Cli, geocoder: G) -> Result<(), AppError> {
+    let inventory = parse_csv(&cli.input)?;
+    let unknown_columns = unknown_columns(&cli.input)?;
+    let cache = if cli.no_cache {
+        GeocodeCache::in_memory()
+    } else {
+        GeocodeCache::open(&cli.cache)?
+    };
+    let geocoder = CachedGeocoder::new(geocoder, cache);
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    let mut app = App::new(
+        inventory,
+        cli.output.clone().unwrap_or_else(|| cli.input.clone()),
+        unknown_columns,
+    );
+
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, &mut app, &geocoder, &runtime);
+    ratatui::restore();
+    geocoder.save()?;
+    result?;
+    if app.modified {
+        eprintln!("Quit without saving changes to {:?}", app.path);
+    }
+    Ok(())
+}
+
+fn event_loop<G: Geocoder>(
+    terminal: &mut DefaultTerminal,
+    app: &mut App,
+    geocoder: &G,
+    runtime: &Runtime,
+) -> std::io::Result<()> {
+    let normalizer = AddressNormalizer::default();
+    while !app.quit {
+        terminal.draw(|frame| ui::draw(frame, app))?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match app.handle_key(key) {
+            Some(Command::Geocode) => {
+                let Some(item) = app.selected_item() else {
+                    continue;
+                };
+                let query = normalizer.normalize(&item.street_address);
+                app.status = Some(format!("Geocoding {} with {}...", query, geocoder.name()));
+                terminal.draw(|frame| ui::draw(frame, app))?;
+                // No logger is installed, so the geocoders' progress messages don't
+                // land on top of the screen.
+                let result = runtime.block_on(geocoder.geocode(&query));
+                app.apply_geocode(&query.to_string(), result);
+            }
+            None => {}
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,225 @@
+use crate::app::{App, Field, Mode, SortKey};
+use data::validation::Severity;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Cell, Paragraph, Row, Table, TableState, Wrap};
+use ratatui::Frame;
+
+const BROWSE_HELP: &str = "↑/↓ move  / filter  i issues only  s sort  r reverse  \
+c condition  d damaged  a address  n notes  g geocode  w save  q quit";
+
+pub fn draw(frame: &mut Frame, app: &mut App) {
+    let [main, footer] =
+        Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+    let [list, details] =
+        Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)]).areas(main);
+    // Rendering scrolls the table state, so it is lent out separately from the rows' `app`.
+    let mut state = std::mem::take(&mut app.table);
+    draw_list(frame, app, &mut state, list);
+    app.table = state;
+    draw_details(frame, app, details);
+    draw_footer(frame, app, footer);
+}
+
+fn draw_list(frame: &mut Frame, app: &App, state: &mut TableState, area: Rect) {
+    let header = Row::new(SortKey::ALL.map(|key| {
+        let arrow = match (key == app.sort, app.descending) {
+            (false, _) => "",
+            (true, false) => " ▲",
+            (true, true) => " ▼",
+        };
+        Cell::from(format!("{}{}", key.title(), arrow))
+    }))
+    .bold();
+
+    let rows: Vec<Row> = app
+        .view
+        .iter()
+        .map(|&index| {
+            let item = &app.inventory[index];
+            let issues = match app.issue_summary(item.id) {
+                Some((severity, count)) => {
+                    Cell::from(count.to_string()).style(severity_style(severity))
+                }
+                None => Cell::from(""),
+            };
+            Row::new([
+                Cell::from(item.id.to_string()),
+                Cell::from(item.street_sign.as_str()),
+                Cell::from(item.street_address.as_str()),
+                Cell::from(item.sign_condition.as_deref().unwrap_or_default()),
+                Cell::from(
+                    item.number_of_tiles_damaged
+                        .map(|count| count.to_string())
+                        .unwrap_or_default(),
+                ),
+                issues,
+            ])
+        })
+        .collect();
+
+    let mut title = format!(
+        " {} — {} of {} records",
+        app.path.display(),
+        app.view.len(),
+        app.inventory.len()
+    );
+    if !app.filter.is_empty() {
+        title.push_str(&format!(", filter {:?}", app.filter));
+    }
+    if app.only_issues {
+        title.push_str(", with issues");
+    }
+    if app.modified {
+        title.push_str(" [modified]");
+    }
+    title.push(' ');
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(4),
+            Constraint::Fill(1),
+            Constraint::Fill(2),
+            Constraint::Length(14),
+            Constraint::Length(4),
+            Constraint::Length(3),
+        ],
+    )
+    .header(header)
+    .block(Block::bordered().title(title))
+    .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(table, area, state);
+}
+
+fn draw_details(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::bordered().title(" Record ");
+    let Some(item) = app.selected_item() else {
+        let message = if app.inventory.is_empty() {
+            "The inventory is empty."
+        } else {
+            "No records match the filter."
+        };
+        frame.render_widget(Paragraph::new(message).block(block), area);
+        return;
+    };
+
+    let field = |name: &str, value: String| {
+        Line::from(vec![
+            Span::from(format!("{}: ", name)).bold(),
+            Span::from(value),
+        ])
+    };
+    let missing = || "—".to_string();
+    let mut lines = vec![
+        field("ID", item.id.to_string()),
+        field("Street Sign", item.street_sign.clone()),
+        field("Street Address", item.street_address.clone()),
+        field(
+            "Sign Condition",
+            item.sign_condition.clone().unwrap_or_else(missing),
+        ),
+        field(
+            "Tiles Damaged",
+            item.number_of_tiles_damaged
+                .map_or_else(missing, |count| count.to_string()),
+        ),
+        field("Notes", item.notes.clone().unwrap_or_else(missing)),
+        field(
+            "Coordinates",
+            match item.latitude.zip(item.longitude) {
+                Some((latitude, longitude)) => format!("{}, {}", latitude, longitude),
+                None => "not geocoded".to_string(),
+            },
+        ),
+    ];
+    if let Some(result) = item.geocode_result() {
+        lines.push(field(
+            "Geocoded by",
+            format!(
+                "{}, {}, confidence {:.2}",
+                result.provider,
+                result.match_type.accuracy_label(),
+                result.confidence
+            ),
+        ));
+    }
+    lines.push(field("Photos", item.photos().count().to_string()));
+    lines.push(Line::default());
+
+    let issues: Vec<Line> = app
+        .issues_for(item.id)
+        .map(|issue| {
+            Line::from(vec![
+                Span::styled(
+                    format!("{:<8}", issue.severity),
+                    severity_style(issue.severity),
+                ),
+                Span::from(format!("{}: {}", issue.field, issue.message)),
+            ])
+        })
+        .collect();
+    if issues.is_empty() {
+        lines.push(Line::styled("No issues", Style::new().fg(Color::Green)));
+    } else {
+        lines.push(Line::from("Issues").bold());
+        lines.extend(issues);
+    }
+
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false }),
+        area,
+    );
+}
+
+fn draw_footer(frame: &mut Frame, app: &App, area: Rect) {
+    let line = match &app.mode {
+        Mode::Browse => match &app.status {
+            Some(status) => Line::from(status.as_str()),
+            None => Line::from(BROWSE_HELP).dim(),
+        },
+        Mode::Filter => Line::from(vec![
+            Span::from("Filter: ").bold(),
+            Span::from(app.filter.as_str()),
+            Span::from("▏"),
+            Span::from("  Enter keep  Esc clear").dim(),
+        ]),
+        Mode::Edit {
+            field,
+            buffer,
+            error,
+        } => {
+            let mut spans = vec![
+                Span::from(format!("{}: ", field.title())).bold(),
+                Span::from(buffer.as_str()),
+                Span::from("▏"),
+            ];
+            match error {
+                Some(error) => spans.push(Span::styled(
+                    format!("  {}", error),
+                    Style::new().fg(Color::Red),
+                )),
+                None if *field == Field::Condition => {
+                    spans.push(Span::from("  ↑/↓ known conditions  Enter save  Esc cancel").dim())
+                }
+                None => spans.push(Span::from("  Enter save  Esc cancel").dim()),
+            }
+            Line::from(spans)
+        }
+        Mode::ConfirmQuit => Line::from(
+            "Unsaved changes: w save and quit  y quit without saving  any other key to stay",
+        )
+        .fg(Color::Yellow),
+    };
+    frame.render_widget(Paragraph::new(line), area);
+}
+
+fn severity_style(severity: Severity) -> Style {
+    match severity {
+        Severity::Error => Style::new().fg(Color::Red),
+        Severity::Warning => Style::new().fg(Color::Yellow),
+    }
+}
@@ -0,0 +1,724 @@
+use data::analysis::inventory_stats;
+use data::boundary::Boundary;
+use data::projection::Crs;
+use data::search::{SearchHit, SearchIndex};
+use data::validation::{validate_inventory, Severity, ValidationIssue};
+use lat_long_finder::GeocodeArgs;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use structopt::StructOpt;
+use utils::csv_parser::{ensure_known_columns, parse_csv, write_csv, CsvError};
+use utils::diff::diff_inventories;
+use utils::export::{write_export, ExportError, ExportFormat};
+use utils::fmt::{format_csv, FormatError, FormatReport};
+use utils::merge::{merge_inventories, Prefer};
+use utils::photos::audit::{apply_fixes, audit_photos, PhotoAuditError, PhotoAuditReport};
+use utils::photos::filename::{rebuild_photo_columns, RebuildReport};
+use utils::photos::IMAGES_DIR;
+
+/// Maintains the Spanish tile inventory CSVs.
+///
+/// Exit status is 0 when a command succeeds with nothing to report, 1 when it ran but found
+/// problems (validation errors, differences, merge conflicts, failed geocodes, photo
+/// problems, no search results), and 2 when it couldn't run.
+#[derive(StructOpt)]
+#[structopt(name = "tile-inventory")]
+struct Cli {
+    /// Print a JSON report on stdout instead of text; progress goes to stderr
+    #[structopt(long, global = true)]
+    json: bool,
+    #[structopt(subcommand)]
+    command: Command,
+}
+
+// Parsed once, so the size of the geocoding options doesn't matter.
+#[allow(clippy::large_enum_variant)]
+#[derive(StructOpt)]
+enum Command {
+    /// Check every record for missing, inconsistent or implausible values
+    Validate {
+        #[structopt(parse(from_os_str), short = "i", long = "in")]
+        input: PathBuf,
+        /// Count warnings as problems too
+        #[structopt(long)]
+        strict: bool,
+    },
+    /// Summarize conditions, damage, geocoding and photos
+    Stats {
+        #[structopt(parse(from_os_str), short = "i", long = "in")]
+        input: PathBuf,
+    },
+    /// Find records by sign, address, modern street name or occupant, tolerating typos
+    Search {
+        #[structopt(parse(from_os_str), short = "i", long = "in")]
+        input: PathBuf,
+        query: String,
+        /// Most results to show
+        #[structopt(long, default_value = "10")]
+        limit: usize,
+    },
+    /// Geocode addresses; the same as lat-long-finder
+    Geocode(GeocodeArgs),
+    /// List records added, removed or changed between two CSVs
+    Diff {
+        #[structopt(parse(from_os_str))]
+        old: PathBuf,
+        #[structopt(parse(from_os_str))]
+        new: PathBuf,
+    },
+    /// Combine two CSVs by ID, filling empty columns from either side
+    Merge {
+        #[structopt(parse(from_os_str))]
+        base: PathBuf,
+        #[structopt(parse(from_os_str))]
+        other: PathBuf,
+        #[structopt(parse(from_os_str), short = "o", long = "out")]
+        output: PathBuf,
+        /// Which side wins when both have a different value: "base" or "other"
+        #[structopt(long, default_value = "base")]
+        prefer: Prefer,
+    },
+    /// Write the inventory as GeoJSON, KML, JSON or a projected CSV
+    Export {
+        #[structopt(parse(from_os_str), short = "i", long = "in")]
+        input: PathBuf,
+        /// Defaults to stdout
+        #[structopt(parse(from_os_str), short = "o", long = "out")]
+        output: Option<PathBuf>,
+        /// geojson, kml, json or csv; defaults to the output's extension, or geojson
+        #[structopt(long)]
+        format: Option<ExportFormat>,
+        /// EPSG:4326 (latitude/longitude), EPSG:3452 (Louisiana South state plane, US feet)
+        /// or EPSG:32615 (UTM zone 15N)
+        #[structopt(long, default_value = "EPSG:4326")]
+        crs: Crs,
+    },
+    /// Photo checks
+    Photos(PhotosCommand),
+    /// Rewrite a CSV in canonical form: sorted by ID, standard columns and quoting, trimmed
+    /// text, survey condition spellings and rounded coordinates
+    Fmt {
+        #[structopt(parse(from_os_str), short = "i", long = "in")]
+        input: PathBuf,
+        /// Defaults to overwriting the input
+        #[structopt(
+            parse(from_os_str),
+            short = "o",
+            long = "out",
+            conflicts_with = "check"
+        )]
+        output: Option<PathBuf>,
+        /// Don't write anything; exit with 1 if the file isn't canonical
+        #[structopt(long)]
+        check: bool,
+    },
+}
+
+#[derive(StructOpt)]
+enum PhotosCommand {
+    /// Check the photo columns against the files in the image directory
+    Audit {
+        #[structopt(parse(from_os_str), short = "i", long = "in")]
+        input: PathBuf,
+        /// Directory the CSV's photo paths are relative to
+        #[structopt(parse(from_os_str), long = "base-dir", default_value = ".")]
+        base_dir: PathBuf,
+        /// Image directory, relative to --base-dir
+        #[structopt(parse(from_os_str), long = "images-dir", default_value = IMAGES_DIR)]
+        images_dir: PathBuf,
+        /// Write a copy of the CSV with the suggested fixes applied
+        #[structopt(parse(from_os_str), long)]
+        fix: Option<PathBuf>,
+    },
+    /// Refill every record's photo columns from the image file names alone, keeping the
+    /// latest capture of each slot
+    Rebuild {
+        #[structopt(parse(from_os_str), short = "i", long = "in")]
+        input: PathBuf,
+        #[structopt(parse(from_os_str), short = "o", long = "out")]
+        output: PathBuf,
+        /// Directory the CSV's photo paths are relative to
+        #[structopt(parse(from_os_str), long = "base-dir", default_value = ".")]
+        base_dir: PathBuf,
+        /// Image directory, relative to --base-dir
+        #[structopt(parse(from_os_str), long = "images-dir", default_value = IMAGES_DIR)]
+        images_dir: PathBuf,
+    },
+}
+
+/// Whether a command that ran found anything that needs attention.
+enum Outcome {
+    Clean,
+    Problems,
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = match Cli::from_iter_safe(std::env::args_os()) {
+        Ok(cli) => cli,
+        Err(err) if err.use_stderr() => {
+            eprintln!("{}", err.message);
+            std::process::exit(2);
+        }
+        // --help and --version
+        Err(err) => err.exit(),
+    };
+    utils::logging::init_stderr();
+    let code = match run(cli).await {
+        Ok(Outcome::Clean) => 0,
+        Ok(Outcome::Problems) => 1,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            2
+        }
+    };
+    std::process::exit(code);
+}
+
+#[derive(thiserror::Error, Debug)]
+enum AppError {
+    #[error("CSV error: {0}")]
+    Csv(#[from] CsvError),
+    #[error("{0}")]
+    Geocode(#[from] lat_long_finder::Error),
+    #[error("Export error: {0}")]
+    Export(#[from] ExportError),
+    #[error("Format error: {0}")]
+    Format(#[from] FormatError),
+    #[error("Photo audit error: {0}")]
+    PhotoAudit(#[from] PhotoAuditError),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+async fn run(cli: Cli) -> Result<Outcome, AppError> {
+    let json = cli.json;
+    match cli.command {
+        Command::Validate { input, strict } => validate(json, input, strict),
+        Command::Stats { input } => stats(json, input),
+        Command::Search {
+            input,
+            query,
+            limit,
+        } => search(json, input, query, limit),
+        Command::Geocode(mut args) => {
+            args.json = json;
+            geocode(args).await
+        }
+        Command::Diff { old, new } => diff(json, old, new),
+        Command::Merge {
+            base,
+            other,
+            output,
+            prefer,
+        } => merge(json, base, other, output, prefer),
+        Command::Export {
+            input,
+            output,
+            format,
+            crs,
+        } => export(json, input, output, format, crs),
+        Command::Photos(PhotosCommand::Audit {
+            input,
+            base_dir,
+            images_dir,
+            fix,
+        }) => photos_audit(json, input, base_dir, images_dir, fix),
+        Command::Photos(PhotosCommand::Rebuild {
+            input,
+            output,
+            base_dir,
+            images_dir,
+        }) => photos_rebuild(json, input, output, base_dir, images_dir),
+        Command::Fmt {
+            input,
+            output,
+            check,
+        } => fmt(json, input, output, check),
+    }
+}
+
+fn print_json<T: Serialize>(report: &T) -> Result<(), AppError> {
+    let mut stdout = std::io::stdout().lock();
+    serde_json::to_writer_pretty(&mut stdout, report)?;
+    writeln!(stdout)?;
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct ValidationReport {
+    records: usize,
+    errors: usize,
+    warnings: usize,
+    issues: Vec<ValidationIssue>,
+}
+
+fn validate(json: bool, input: PathBuf, strict: bool) -> Result<Outcome, AppError> {
+    let inventory = parse_csv(&input)?;
+    let issues = validate_inventory(&inventory, &Boundary::french_quarter());
+    let count = |severity| {
+        issues
+            .iter()
+            .filter(|issue| issue.severity == severity)
+            .count()
+    };
+    let report = ValidationReport {
+        records: inventory.len(),
+        errors: count(Severity::Error),
+        warnings: count(Severity::Warning),
+        issues,
+    };
+
+    if json {
+        print_json(&report)?;
+    } else {
+        for issue in &report.issues {
+            println!("{}", issue);
+        }
+        println!(
+            "{} records: {} errors, {} warnings",
+            report.records, report.errors, report.warnings
+        );
+    }
+
+    if report.errors > 0 || (strict && report.warnings > 0) {
+        Ok(Outcome::Problems)
+    } else {
+        Ok(Outcome::Clean)
+    }
+}
+
+fn stats(json: bool, input: PathBuf) -> Result<Outcome, AppError> {
+    let inventory = parse_csv(&input)?;
+    let stats = inventory_stats(&inventory);
+
+    if json {
+        print_json(&stats)?;
+        return Ok(Outcome::Clean);
+    }
+
+    let print_counts = |title: &str, counts: &BTreeMap<String, usize>| {
+        println!("{}:", title);
+        let mut counts: Vec<_> = counts.iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        for (name, count) in counts {
+            println!("  {}: {}", name, count);
+        }
+    };
+    println!("Records: {}", stats.total_items);
+    println!(
+        "Damaged tiles: {} total, {:.2} per sign",
+        stats.total_damaged_tiles, stats.average_damaged_tiles
+    );
+    println!("With coordinates: {}", stats.with_coordinates);
+    println!(
+        "With photos: {} ({} photos)",
+        stats.with_photos, stats.total_photos
+    );
+    print_counts("By condition", &stats.by_condition);
+    print_counts("By geocode match", &stats.by_match_type);
+    print_counts("By street sign", &stats.by_street_sign);
+    Ok(Outcome::Clean)
+}
+
+#[derive(Serialize)]
+struct SearchResult {
+    #[serde(flatten)]
+    hit: SearchHit,
+    street_sign: String,
+    street_address: String,
+}
+
+fn search(json: bool, input: PathBuf, query: String, limit: usize) -> Result<Outcome, AppError> {
+    let inventory = parse_csv(&input)?;
+    let results: Vec<SearchResult> = SearchIndex::new(&inventory)
+        .search(&query, limit)
+        .into_iter()
+        .filter_map(|hit| {
+            let item = inventory.iter().find(|item| item.id == hit.id)?;
+            Some(SearchResult {
+                street_sign: item.street_sign.clone(),
+                street_address: item.street_address.clone(),
+                hit,
+            })
+        })
+        .collect();
+
+    if json {
+        print_json(&results)?;
+    } else if results.is_empty() {
+        println!("No records match {:?}", query);
+    } else {
+        for result in &results {
+            println!(
+                "ID {:>3}  {:>3.0}%  {}, {} (matched {})",
+                result.hit.id,
+                result.hit.score * 100.0,
+                result.street_sign,
+                result.street_address,
+                result.hit.field.label()
+            );
+        }
+    }
+
+    if results.is_empty() {
+        Ok(Outcome::Problems)
+    } else {
+        Ok(Outcome::Clean)
+    }
+}
+
+async fn geocode(args: GeocodeArgs) -> Result<Outcome, AppError> {
+    let summary = lat_long_finder::run(&args).await?;
+    if args.json {
+        print_json(&summary)?;
+    } else {
+        for problem in summary.problems() {
+            eprintln!("{}", problem);
+        }
+    }
+    if summary.exit_code() == 0 {
+        Ok(Outcome::Clean)
+    } else {
+        Ok(Outcome::Problems)
+    }
+}
+
+fn diff(json: bool, old: PathBuf, new: PathBuf) -> Result<Outcome, AppError> {
+    let diff = diff_inventories(&parse_csv(&old)?, &parse_csv(&new)?);
+
+    if json {
+        print_json(&diff)?;
+    } else {
+        for id in &diff.added {
+            println!("+ ID {}", id);
+        }
+        for id in &diff.removed {
+            println!("- ID {}", id);
+        }
+        for record in &diff.changed {
+            for change in &record.changes {
+                println!(
+                    "~ ID {} {}: {} -> {}",
+                    record.id, change.field, change.old, change.new
+                );
+            }
+        }
+        println!(
+            "{} added, {} removed, {} changed",
+            diff.added.len(),
+            diff.removed.len(),
+            diff.changed.len()
+        );
+    }
+
+    if diff.is_empty() {
+        Ok(Outcome::Clean)
+    } else {
+        Ok(Outcome::Problems)
+    }
+}
+
+fn merge(
+    json: bool,
+    base: PathBuf,
+    other: PathBuf,
+    output: PathBuf,
+    prefer: Prefer,
+) -> Result<Outcome, AppError> {
+    // Columns either side has that the output can't hold would be lost.
+    ensure_known_columns(&base)?;
+    ensure_known_columns(&other)?;
+    let mut inventory = parse_csv(&base)?;
+    let report = merge_inventories(&mut inventory, &parse_csv(&other)?, prefer)?;
+    write_csv(&output, &inventory)?;
+
+    if json {
+        print_json(&report)?;
+    } else {
+        for conflict in &report.conflicts {
+            println!(
+                "Conflict: ID {} {}: kept {}, discarded {}",
+                conflict.id, conflict.field, conflict.kept, conflict.discarded
+            );
+        }
+        println!(
+            "{} records added, {} empty columns filled, {} conflicts; written to {:?}",
+            report.added.len(),
+            report.filled,
+            report.conflicts.len(),
+            output
+        );
+    }
+
+    if report.conflicts.is_empty() {
+        Ok(Outcome::Clean)
+    } else {
+        Ok(Outcome::Problems)
+    }
+}
+
+#[derive(Serialize)]
+struct ExportReport {
+    records: usize,
+    format: String,
+    crs: String,
+    output: PathBuf,
+}
+
+fn export(
+    json: bool,
+    input: PathBuf,
+    output: Option<PathBuf>,
+    format: Option<ExportFormat>,
+    crs: Crs,
+) -> Result<Outcome, AppError> {
+    let inventory = parse_csv(&input)?;
+    let format = match (format, &output) {
+        (Some(format), _) => format,
+        (None, Some(path)) => ExportFormat::from_path(path)?,
+        (None, None) => ExportFormat::GeoJson,
+    };
+
+    // Without --out the export itself is the output, so there's no report to print.
+    let Some(output) = output else {
+        write_export(std::io::stdout().lock(), &inventory, format, crs)?;
+        return Ok(Outcome::Clean);
+    };
+    let mut writer = BufWriter::new(File::create(&output)?);
+    write_export(&mut writer, &inventory, format, crs)?;
+    writer.flush()?;
+
+    let report = ExportReport {
+        records: inventory.len(),
+        format: format.to_string(),
+        crs: crs.to_string(),
+        output,
+    };
+    if json {
+        print_json(&report)?;
+    } else {
+        println!(
+            "Exported {} records as {} in {} to {:?}",
+            report.records, report.format, report.crs, report.output
+        );
+    }
+    Ok(Outcome::Clean)
+}
+
+#[derive(Serialize)]
+struct PhotoAuditOutput<'a> {
+    #[serde(flatten)]
+    report: &'a PhotoAuditReport,
+    fixes_applied: Option<usize>,
+}
+
+fn photos_audit(
+    json: bool,
+    input: PathBuf,
+    base_dir: PathBuf,
+    images_dir: PathBuf,
+    fix: Option<PathBuf>,
+) -> Result<Outcome, AppError> {
+    if fix.is_some() {
+        ensure_known_columns(&input)?;
+    }
+    let mut inventory = parse_csv(&input)?;
+    let report = audit_photos(&inventory, &base_dir, &images_dir)?;
+    let fixes_applied = match &fix {
+        Some(path) => {
+            let applied = apply_fixes(&mut inventory, &report.fixes);
+            write_csv(path, &inventory)?;
+            Some(applied)
+        }
+        None => None,
+    };
+
+    if json {
+        print_json(&PhotoAuditOutput {
+            report: &report,
+            fixes_applied,
+        })?;
+    } else {
+        for reference in &report.missing {
+            println!(
+                "Missing: ID {} Photo {}: {:?}",
+                reference.id, reference.slot, reference.path
+            );
+        }
+        for mismatch in &report.mismatched {
+            println!(
+                "Case or spacing differs: ID {} Photo {}: {:?} is {:?}",
+                mismatch.reference.id,
+                mismatch.reference.slot,
+                mismatch.reference.path,
+                mismatch.actual
+            );
+        }
+        for duplicate in &report.duplicates {
+            let ids: Vec<String> = duplicate
+                .references
+                .iter()
+                .map(|reference| format!("ID {} Photo {}", reference.id, reference.slot))
+                .collect();
+            println!("Shared: {:?} by {}", duplicate.path, ids.join(", "));
+        }
+        for mismatch in &report.id_mismatches {
+            println!(
+                "Wrong record: ID {} Photo {}: {:?} is named for ID {}",
+                mismatch.reference.id,
+                mismatch.reference.slot,
+                mismatch.reference.path,
+                mismatch.embedded_id
+            );
+        }
+        for unreadable in &report.unreadable {
+            println!(
+                "Unreadable: {:?} ({:?})",
+                unreadable.path, unreadable.reason
+            );
+        }
+        for orphan in &report.orphaned {
+            println!("Unreferenced: {:?}", orphan);
+        }
+        for suggestion in &report.fixes {
+            println!(
+                "Suggested fix: ID {} Photo {}: {:?} -> {:?}",
+                suggestion.reference.id,
+                suggestion.reference.slot,
+                suggestion.reference.path,
+                suggestion.replacement
+            );
+        }
+        println!(
+            "{} missing, {} mismatched, {} shared, {} wrong record, {} unreadable, {} unreferenced",
+            report.missing.len(),
+            report.mismatched.len(),
+            report.duplicates.len(),
+            report.id_mismatches.len(),
+            report.unreadable.len(),
+            report.orphaned.len()
+        );
+        if let (Some(path), Some(applied)) = (&fix, fixes_applied) {
+            println!("Applied {} fixes, written to {:?}", applied, path);
+        }
+    }
+
+    if report.is_clean() {
+        Ok(Outcome::Clean)
+    } else {
+        Ok(Outcome::Problems)
+    }
+}
+
+fn photos_rebuild(
+    json: bool,
+    input: PathBuf,
+    output: PathBuf,
+    base_dir: PathBuf,
+    images_dir: PathBuf,
+) -> Result<Outcome, AppError> {
+    ensure_known_columns(&input)?;
+    let mut inventory = parse_csv(&input)?;
+    let report = rebuild_photo_columns(&mut inventory, &base_dir, &images_dir)?;
+    write_csv(&output, &inventory)?;
+
+    if json {
+        print_json(&report)?;
+    } else {
+        let RebuildReport {
+            updated,
+            unparsed,
+            unknown_ids,
+            out_of_range,
+            superseded,
+        } = &report;
+        for path in unparsed {
+            println!("Not named <ID>.Photo <N>.<HHMMSS>.jpg: {:?}", path);
+        }
+        for path in unknown_ids {
+            println!("No record with this ID: {:?}", path);
+        }
+        for path in out_of_range {
+            println!("Slot outside Photo 1 to Photo 5: {:?}", path);
+        }
+        for path in superseded {
+            println!("Replaced by a later capture: {:?}", path);
+        }
+        println!(
+            "{} records updated, {} unparsed, {} unknown IDs, {} out of range, {} superseded; written to {:?}",
+            updated.len(),
+            unparsed.len(),
+            unknown_ids.len(),
+            out_of_range.len(),
+            superseded.len(),
+            output
+        );
+    }
+
+    // Superseded captures are expected retakes, not problems.
+    if report.unparsed.is_empty() && report.unknown_ids.is_empty() && report.out_of_range.is_empty()
+    {
+        Ok(Outcome::Clean)
+    } else {
+        Ok(Outcome::Problems)
+    }
+}
+
+#[derive(Serialize)]
+struct FmtOutput {
+    #[serde(flatten)]
+    report: FormatReport,
+    /// Not set for --check, or when the file was already canonical.
+    output: Option<PathBuf>,
+}
+
+fn fmt(
+    json: bool,
+    input: PathBuf,
+    output: Option<PathBuf>,
+    check: bool,
+) -> Result<Outcome, AppError> {
+    let (formatted, report) = format_csv(&std::fs::read(&input)?)?;
+    let output = match output {
+        Some(output) if !check => Some(output),
+        // Leave an already canonical input untouched, timestamp and all.
+        None if !check && !report.canonical => Some(input.clone()),
+        _ => None,
+    };
+    if let Some(output) = &output {
+        std::fs::write(output, &formatted)?;
+    }
+
+    let canonical = report.canonical;
+    if json {
+        print_json(&FmtOutput { report, output })?;
+    } else {
+        for change in &report.changed {
+            for field in &change.changes {
+                println!(
+                    "ID {} {}: {} -> {}",
+                    change.id, field.field, field.old, field.new
+                );
+            }
+        }
+        if report.reordered {
+            println!("Records are not sorted by ID");
+        }
+        match (&output, canonical) {
+            (Some(output), _) => println!("Wrote {} records to {:?}", report.records, output),
+            (None, true) => println!("{:?} is already formatted", input),
+            (None, false) => println!("{:?} is not formatted; run tile-inventory fmt", input),
+        }
+    }
+    if check && !canonical {
+        Ok(Outcome::Problems)
+    } else {
+        Ok(Outcome::Clean)
+    }
+}
@@ -1,5 +1,9 @@
-use csv::{Reader, ReaderBuilder, Writer};
+use csv::{Reader, ReaderBuilder, StringRecord, WriterBuilder};
 use data::inventory::TileInventory;
+use data::overrides::CoordinateOverride;
+use serde::de::DeserializeOwned;
+use std::fs::File;
+use std::io::Write;
 use std::path::Path;
 use thiserror::Error;
 
@@ -11,6 +15,8 @@ pub enum CsvError {
     Io(#[from] std::io::Error),
     #[error("Failed to deserialize record at line {line}: {source}")]
     Deserialize { line: usize, source: csv::Error },
+    #[error("Rewriting the CSV would drop the columns {0:?}")]
+    UnknownColumns(Vec<String>),
 }
 
 pub fn parse_csv_str(input_bytes: &[u8]) -> Result<Vec<TileInventory>, CsvError> {
@@ -53,29 +59,116 @@ pub fn parse_csv<P: AsRef<Path>>(input_path: P) -> Result<Vec<TileInventory>, Cs
     Ok(inventory)
 }
 
+/// Reads an `id,latitude,longitude,reason` coordinate overrides file.
+pub fn parse_overrides_str(input_bytes: &[u8]) -> Result<Vec<CoordinateOverride>, CsvError> {
+    read_records(
+        ReaderBuilder::new()
+            .has_headers(true)
+            .from_reader(input_bytes),
+    )
+}
+
+pub fn parse_overrides<P: AsRef<Path>>(input_path: P) -> Result<Vec<CoordinateOverride>, CsvError> {
+    read_records(Reader::from_path(input_path)?)
+}
+
+fn read_records<T: DeserializeOwned, R: std::io::Read>(
+    mut reader: Reader<R>,
+) -> Result<Vec<T>, CsvError> {
+    reader
+        .deserialize()
+        .enumerate()
+        .map(|(index, result)| {
+            result.map_err(|err| CsvError::Deserialize {
+                line: index + 2,
+                source: err,
+            })
+        })
+        .collect()
+}
+
+/// The columns `write_csv` writes, in order.
+pub const CSV_HEADERS: [&str; 24] = [
+    "ID",
+    "Street Sign",
+    "Street Address",
+    "Sign Condition",
+    "Number of Tiles Damaged",
+    "Grout Condition",
+    "Type of Wall",
+    "Building Occupant",
+    "Title Owner on Builder",
+    "Photo 1",
+    "Photo 2",
+    "Photo 3",
+    "Photo 4",
+    "Photo 5",
+    "latitude",
+    "longitude",
+    "exif_latitude",
+    "exif_longitude",
+    "geocode_provider",
+    "geocode_display_name",
+    "geocode_match_type",
+    "geocode_confidence",
+    "geocode_timestamp",
+    "Notes",
+];
+
+/// Columns of a CSV's header row that `TileInventory` doesn't have, and that `write_csv`
+/// would therefore drop.
+pub fn unknown_columns_str(input_bytes: &[u8]) -> Result<Vec<String>, CsvError> {
+    let mut reader = ReaderBuilder::new().from_reader(input_bytes);
+    Ok(unknown_columns_in(reader.headers()?))
+}
+
+pub fn unknown_columns<P: AsRef<Path>>(input_path: P) -> Result<Vec<String>, CsvError> {
+    let mut reader = Reader::from_path(input_path)?;
+    Ok(unknown_columns_in(reader.headers()?))
+}
+
+/// The `CSV_HEADERS` a CSV's header row has, in `CSV_HEADERS` order.
+pub fn known_columns_str(input_bytes: &[u8]) -> Result<Vec<&'static str>, CsvError> {
+    let mut reader = ReaderBuilder::new().from_reader(input_bytes);
+    let headers = reader.headers()?;
+    Ok(CSV_HEADERS
+        .into_iter()
+        .filter(|column| headers.iter().any(|header| header == *column))
+        .collect())
+}
+
+fn unknown_columns_in(headers: &StringRecord) -> Vec<String> {
+    headers
+        .iter()
+        .filter(|header| !CSV_HEADERS.contains(header))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Fails with `CsvError::UnknownColumns` if rewriting the CSV at `input_path` would lose
+/// columns. Commands that parse a CSV and write it back call this first.
+pub fn ensure_known_columns<P: AsRef<Path>>(input_path: P) -> Result<(), CsvError> {
+    let unknown = unknown_columns(input_path)?;
+    if unknown.is_empty() {
+        Ok(())
+    } else {
+        Err(CsvError::UnknownColumns(unknown))
+    }
+}
+
 pub fn write_csv<P: AsRef<Path>>(
     output_path: P,
     inventory: &[TileInventory],
 ) -> Result<(), CsvError> {
-    let mut writer = Writer::from_path(output_path)?;
-
-    // Write the header
-    writer.write_record([
-        "ID",
-        "Street Sign",
-        "Street Address",
-        "Sign Condition",
-        "Number of Tiles Damaged",
-        "Photo 1",
-        "Photo 2",
-        "Photo 3",
-        "Photo 4",
-        "Photo 5",
-        "latitude",
-        "longitude",
-    ])?;
-
-    // Write the records
+    write_csv_to(File::create(output_path)?, inventory)
+}
+
+pub fn write_csv_to<W: Write>(writer: W, inventory: &[TileInventory]) -> Result<(), CsvError> {
+    // Headers are written explicitly below; letting `serialize` add its own would emit
+    // them twice.
+    let mut writer = WriterBuilder::new().has_headers(false).from_writer(writer);
+
+    writer.write_record(CSV_HEADERS)?;
     for record in inventory {
         writer.serialize(record)?;
     }
@@ -84,71 +177,31 @@ pub fn write_csv<P: AsRef<Path>>(
     Ok(())
 }
 
-#[cfg(feature = "no-wasm")]
-pub mod geocoding {
-    use super::*;
-    use reqwest::Client;
-    use serde::Deserialize;
-    use std::time::Duration;
-    use tokio::time::sleep;
-
-    #[derive(Debug, Deserialize)]
-    struct NominatimResponse {
-        lat: String,
-        lon: String,
-    }
-
-    #[derive(Error, Debug)]
-    pub enum GeocodingError {
-        #[error("Request error: {0}")]
-        Request(#[from] reqwest::Error),
-        #[error("Failed to parse latitude or longitude")]
-        ParseCoordinate,
-    }
-
-    pub async fn geocode(address: &str) -> Result<(Option<f64>, Option<f64>), GeocodingError> {
-        let client = Client::new();
-        let url = format!(
-            "https://nominatim.openstreetmap.org/search?format=json&q={}&limit=1",
-            urlencoding::encode(address)
-        );
-        println!("{}", url);
-
-        let request = client
-            .get(&url)
-            .header("User-Agent", "TileInventoryApp/1.0");
-
-        let response = request.send().await?;
-        let response: Vec<NominatimResponse> = response.json().await?;
-        if let Some(result) = response.first() {
-            let lat = result
-                .lat
-                .parse()
-                .map_err(|_| GeocodingError::ParseCoordinate)?;
-            let lon = result
-                .lon
-                .parse()
-                .map_err(|_| GeocodingError::ParseCoordinate)?;
-            Ok((Some(lat), Some(lon)))
-        } else {
-            Ok((None, None))
-        }
-    }
-
-    pub async fn geocode_inventory(inventory: &mut [TileInventory]) -> Result<(), GeocodingError> {
-        for item in inventory.iter_mut() {
-            if item.latitude.is_none() || item.longitude.is_none() {
-                let address = format!("{}, New Orleans", item.street_address);
-                let (lat, lon) = geocode(&address).await?;
-                println!("Street: {}, Lat/Long:{:?}/{:?}", address, lat, lon);
-                item.latitude = lat;
-                item.longitude = lon;
-
-                // Respect Nominatim's usage policy (max 1 request per second)
-                sleep(Duration::from_secs(1)).await;
-            }
-        }
-
-        Ok(())
+/// Like `write_csv_to`, but with only `columns` (still in `CSV_HEADERS` order), so rewriting
+/// a file doesn't add columns it never had.
+pub fn write_csv_columns_to<W: Write>(
+    writer: W,
+    inventory: &[TileInventory],
+    columns: &[&str],
+) -> Result<(), CsvError> {
+    let mut full = Vec::new();
+    write_csv_to(&mut full, inventory)?;
+    let keep: Vec<usize> = CSV_HEADERS
+        .iter()
+        .enumerate()
+        .filter(|(_, header)| columns.contains(header))
+        .map(|(index, _)| index)
+        .collect();
+
+    // The header row comes through as the first record.
+    let mut reader = ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(full.as_slice());
+    let mut writer = WriterBuilder::new().from_writer(writer);
+    for record in reader.records() {
+        let record = record?;
+        writer.write_record(keep.iter().map(|&index| &record[index]))?;
     }
+    writer.flush()?;
+    Ok(())
 }
@@ -0,0 +1,140 @@
+use data::inventory::TileInventory;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// One column that differs between two versions of a record.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    /// The CSV column name.
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecordChange {
+    pub id: u32,
+    pub changes: Vec<FieldChange>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct InventoryDiff {
+    /// IDs only in the new inventory.
+    pub added: Vec<u32>,
+    /// IDs only in the old inventory.
+    pub removed: Vec<u32>,
+    pub changed: Vec<RecordChange>,
+}
+
+impl InventoryDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// A record's columns by CSV header, as they would be written out.
+pub(crate) fn record_fields(item: &TileInventory) -> Map<String, Value> {
+    match serde_json::to_value(item) {
+        Ok(Value::Object(fields)) => fields,
+        _ => unreachable!("TileInventory serializes to an object"),
+    }
+}
+
+/// Compares two inventories record by record, matching records by ID. Output is sorted by ID.
+pub fn diff_inventories(old: &[TileInventory], new: &[TileInventory]) -> InventoryDiff {
+    let old: BTreeMap<u32, &TileInventory> = old.iter().map(|item| (item.id, item)).collect();
+    let new: BTreeMap<u32, &TileInventory> = new.iter().map(|item| (item.id, item)).collect();
+
+    let mut diff = InventoryDiff {
+        added: new
+            .keys()
+            .filter(|id| !old.contains_key(id))
+            .copied()
+            .collect(),
+        removed: old
+            .keys()
+            .filter(|id| !new.contains_key(id))
+            .copied()
+            .collect(),
+        ..InventoryDiff::default()
+    };
+    for (id, old_item) in &old {
+        let Some(new_item) = new.get(id) else {
+            continue;
+        };
+        let old_fields = record_fields(old_item);
+        let mut new_fields = record_fields(new_item);
+        let mut changes: Vec<FieldChange> = old_fields
+            .into_iter()
+            .filter_map(|(field, old)| {
+                let new = new_fields.remove(&field).unwrap_or(Value::Null);
+                (old != new).then_some(FieldChange { field, old, new })
+            })
+            .collect();
+        changes.extend(new_fields.into_iter().map(|(field, new)| FieldChange {
+            field,
+            old: Value::Null,
+            new,
+        }));
+        if !changes.is_empty() {
+            diff.changed.push(RecordChange { id: *id, changes });
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn record(id: u32, condition: Option<&str>) -> TileInventory {
+        TileInventory {
+            sign_condition: condition.map(str::to_string),
+            ..TileInventory::new(id, "Calle Real", format!("{}00 Royal St", id))
+        }
+    }
+
+    #[test]
+    fn identical_inventories_have_no_diff() {
+        let inventory = vec![record(1, Some("Good Condition")), record(2, None)];
+        assert!(diff_inventories(&inventory, &inventory).is_empty());
+        assert!(diff_inventories(&[], &[]).is_empty());
+    }
+
+    #[test]
+    fn reports_added_removed_and_changed_columns_by_id() {
+        let old = vec![
+            record(3, Some("Good Condition")),
+            record(1, None),
+            record(2, None),
+        ];
+        let mut moved = record(3, Some("Graffiti"));
+        moved.latitude = Some(29.9584);
+        let new = vec![record(5, None), record(4, None), moved, record(1, None)];
+
+        let diff = diff_inventories(&old, &new);
+        assert!(!diff.is_empty());
+        assert_eq!(diff.added, [4, 5]);
+        assert_eq!(diff.removed, [2]);
+        assert_eq!(
+            diff.changed,
+            [RecordChange {
+                id: 3,
+                changes: vec![
+                    FieldChange {
+                        field: "Sign Condition".to_string(),
+                        old: json!("Good Condition"),
+                        new: json!("Graffiti"),
+                    },
+                    FieldChange {
+                        field: "latitude".to_string(),
+                        old: Value::Null,
+                        new: json!(29.9584),
+                    },
+                ],
+            }]
+        );
+    }
+}
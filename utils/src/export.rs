@@ -0,0 +1,429 @@
+use crate::diff::record_fields;
+use csv::WriterBuilder;
+use data::inventory::TileInventory;
+use data::projection::Crs;
+use data::table::TableColumn;
+use serde_json::{json, Map, Value};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ExportError {
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error(
+        "Can't tell the export format of {0:?}; use a .csv, .geojson, .kml or .json extension"
+    )]
+    UnknownFormat(String),
+    #[error("{format} is always in EPSG:4326, not {crs}")]
+    UnsupportedCrs { format: ExportFormat, crs: Crs },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    GeoJson,
+    /// Google Earth's format, always in WGS84.
+    Kml,
+    /// Every column of every record, for scripts.
+    Json,
+}
+
+impl ExportFormat {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, ExportError> {
+        let path = path.as_ref();
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(|extension| extension.parse().ok())
+            .ok_or_else(|| ExportError::UnknownFormat(path.display().to_string()))
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = ExportError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "geojson" => Ok(ExportFormat::GeoJson),
+            "kml" => Ok(ExportFormat::Kml),
+            "json" => Ok(ExportFormat::Json),
+            _ => Err(ExportError::UnknownFormat(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportFormat::Csv => write!(f, "CSV"),
+            ExportFormat::GeoJson => write!(f, "GeoJSON"),
+            ExportFormat::Kml => write!(f, "KML"),
+            ExportFormat::Json => write!(f, "JSON"),
+        }
+    }
+}
+
+/// Writes the inventory to `output_path` in the format its extension names, with
+/// coordinates in `crs`.
+pub fn export<P: AsRef<Path>>(
+    output_path: P,
+    inventory: &[TileInventory],
+    crs: Crs,
+) -> Result<(), ExportError> {
+    let format = ExportFormat::from_path(&output_path)?;
+    let mut writer = BufWriter::new(File::create(output_path)?);
+    write_export(&mut writer, inventory, format, crs)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes the inventory in `format`, with coordinates in `crs`.
+pub fn write_export<W: Write>(
+    writer: W,
+    inventory: &[TileInventory],
+    format: ExportFormat,
+    crs: Crs,
+) -> Result<(), ExportError> {
+    match format {
+        ExportFormat::Csv => write_projected_csv(writer, inventory, crs),
+        ExportFormat::GeoJson => write_geojson(writer, inventory, crs),
+        ExportFormat::Kml if crs != Crs::Wgs84 => Err(ExportError::UnsupportedCrs { format, crs }),
+        ExportFormat::Kml => write_kml(writer, inventory),
+        ExportFormat::Json => write_json(writer, inventory, crs),
+    }
+}
+
+/// A summary CSV for GIS use: the sign's details, its x and y in `crs` under column names
+/// that carry the unit, and the CRS itself on every row so the file can't lose it. Records
+/// without coordinates are kept with the coordinate columns empty.
+pub fn write_projected_csv<W: Write>(
+    writer: W,
+    inventory: &[TileInventory],
+    crs: Crs,
+) -> Result<(), ExportError> {
+    let mut writer = WriterBuilder::new().from_writer(writer);
+    let (x_name, y_name) = crs.axis_names();
+    writer.write_record([
+        "ID",
+        "Street Sign",
+        "Street Address",
+        "Sign Condition",
+        "Number of Tiles Damaged",
+        x_name,
+        y_name,
+        "crs",
+    ])?;
+
+    let crs_name = crs.to_string();
+    for item in inventory {
+        let (x, y) = match item.latitude.zip(item.longitude) {
+            Some((latitude, longitude)) => {
+                let (x, y) = crs.from_wgs84(latitude, longitude);
+                (x.to_string(), y.to_string())
+            }
+            None => (String::new(), String::new()),
+        };
+        writer.write_record([
+            item.id.to_string().as_str(),
+            &item.street_sign,
+            &item.street_address,
+            item.sign_condition.as_deref().unwrap_or_default(),
+            &item
+                .number_of_tiles_damaged
+                .map(|count| count.to_string())
+                .unwrap_or_default(),
+            &x,
+            &y,
+            &crs_name,
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Rows as the web app's table shows them: the chosen columns, in order, with the table's
+/// cell text.
+pub fn write_table_csv<W: Write>(
+    writer: W,
+    rows: &[&TileInventory],
+    columns: &[TableColumn],
+) -> Result<(), ExportError> {
+    let mut writer = WriterBuilder::new().from_writer(writer);
+    writer.write_record(columns.iter().map(|column| column.label()))?;
+    for item in rows {
+        writer.write_record(columns.iter().map(|column| column.text(item)))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// The inventory as a GeoJSON FeatureCollection, one Point per record and a null geometry
+/// for records without coordinates.
+///
+/// RFC 7946 only allows WGS84, so other CRSs are declared with the older (2008) `crs` member,
+/// which QGIS and ArcGIS still honour.
+pub fn to_geojson(inventory: &[TileInventory], crs: Crs) -> Value {
+    let features: Vec<Value> = inventory
+        .iter()
+        .map(|item| {
+            let geometry = match item.latitude.zip(item.longitude) {
+                Some((latitude, longitude)) => {
+                    let (x, y) = crs.from_wgs84(latitude, longitude);
+                    json!({ "type": "Point", "coordinates": [x, y] })
+                }
+                None => Value::Null,
+            };
+            json!({
+                "type": "Feature",
+                "id": item.id,
+                "geometry": geometry,
+                "properties": {
+                    "id": item.id,
+                    "street_sign": item.street_sign,
+                    "street_address": item.street_address,
+                    "sign_condition": item.sign_condition,
+                    "number_of_tiles_damaged": item.number_of_tiles_damaged,
+                    "geocode_match_type": item.geocode_match_type,
+                    "geocode_confidence": item.geocode_confidence,
+                },
+            })
+        })
+        .collect();
+
+    let mut collection = Map::new();
+    collection.insert("type".to_string(), json!("FeatureCollection"));
+    if crs != Crs::Wgs84 {
+        collection.insert(
+            "crs".to_string(),
+            json!({ "type": "name", "properties": { "name": crs.urn() } }),
+        );
+    }
+    collection.insert("features".to_string(), Value::Array(features));
+    Value::Object(collection)
+}
+
+pub fn write_geojson<W: Write>(
+    writer: W,
+    inventory: &[TileInventory],
+    crs: Crs,
+) -> Result<(), ExportError> {
+    serde_json::to_writer_pretty(writer, &to_geojson(inventory, crs))?;
+    Ok(())
+}
+
+/// The inventory as a KML document, one placemark per located record.
+pub fn write_kml<W: Write>(mut writer: W, inventory: &[TileInventory]) -> Result<(), ExportError> {
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, r#"<kml xmlns="http://www.opengis.net/kml/2.2">"#)?;
+    writeln!(writer, "<Document>")?;
+    writeln!(writer, "  <name>Spanish Tile Inventory</name>")?;
+    for item in inventory {
+        let Some((latitude, longitude)) = item.latitude.zip(item.longitude) else {
+            continue;
+        };
+        let mut description = item.street_address.clone();
+        if let Some(condition) = &item.sign_condition {
+            description.push_str(&format!("\n{}", condition));
+        }
+        if let Some(damaged) = item.number_of_tiles_damaged {
+            description.push_str(&format!("\n{} tiles damaged", damaged));
+        }
+        writeln!(writer, r#"  <Placemark id="sign-{}">"#, item.id)?;
+        writeln!(
+            writer,
+            "    <name>{}: {}</name>",
+            item.id,
+            escape_xml(&item.street_sign)
+        )?;
+        writeln!(
+            writer,
+            "    <description>{}</description>",
+            escape_xml(&description)
+        )?;
+        writeln!(
+            writer,
+            "    <Point><coordinates>{},{}</coordinates></Point>",
+            longitude, latitude
+        )?;
+        writeln!(writer, "  </Placemark>")?;
+    }
+    writeln!(writer, "</Document>")?;
+    writeln!(writer, "</kml>")?;
+    Ok(())
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Every column of every record, with the CRS declared alongside. For CRSs other than
+/// WGS84 each record also gets its projected x and y, named as in the CSV export.
+pub fn write_json<W: Write>(
+    writer: W,
+    inventory: &[TileInventory],
+    crs: Crs,
+) -> Result<(), ExportError> {
+    let records: Vec<Value> = inventory
+        .iter()
+        .map(|item| {
+            let mut fields = record_fields(item);
+            if crs != Crs::Wgs84 {
+                let (x_name, y_name) = crs.axis_names();
+                let (x, y) = item
+                    .latitude
+                    .zip(item.longitude)
+                    .map(|(latitude, longitude)| crs.from_wgs84(latitude, longitude))
+                    .unzip();
+                fields.insert(x_name.to_string(), json!(x));
+                fields.insert(y_name.to_string(), json!(y));
+            }
+            Value::Object(fields)
+        })
+        .collect();
+    serde_json::to_writer_pretty(
+        writer,
+        &json!({ "crs": crs.to_string(), "records": records }),
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inventory() -> Vec<TileInventory> {
+        vec![
+            TileInventory {
+                sign_condition: Some("Good Condition".to_string()),
+                number_of_tiles_damaged: Some(0),
+                latitude: Some(29.9584),
+                longitude: Some(-90.0644),
+                ..TileInventory::new(1, "Rue <Bourbon> & Calle", "700 Bourbon St")
+            },
+            TileInventory::new(2, "Calle de Toulouse", "500 Toulouse St"),
+        ]
+    }
+
+    fn written(format: ExportFormat, crs: Crs) -> String {
+        let mut output = Vec::new();
+        write_export(&mut output, &inventory(), format, crs).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn picks_the_format_from_the_extension() {
+        assert_eq!(
+            ExportFormat::from_path("out/signs.GeoJSON").unwrap(),
+            ExportFormat::GeoJson
+        );
+        assert_eq!(
+            ExportFormat::from_path("signs.kml").unwrap(),
+            ExportFormat::Kml
+        );
+        assert!(matches!(
+            ExportFormat::from_path("signs.txt"),
+            Err(ExportError::UnknownFormat(path)) if path == "signs.txt"
+        ));
+        assert!(ExportFormat::from_path("signs").is_err());
+    }
+
+    #[test]
+    fn csv_names_the_axes_and_the_crs() {
+        let csv = written(ExportFormat::Csv, Crs::Wgs84);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "ID,Street Sign,Street Address,Sign Condition,Number of Tiles Damaged,longitude,latitude,crs",
+                "1,Rue <Bourbon> & Calle,700 Bourbon St,Good Condition,0,-90.0644,29.9584,EPSG:4326",
+                "2,Calle de Toulouse,500 Toulouse St,,,,,EPSG:4326",
+            ]
+        );
+
+        let csv = written(ExportFormat::Csv, Crs::Utm15N);
+        assert!(csv.starts_with("ID,Street Sign,Street Address,Sign Condition,Number of Tiles Damaged,easting_m,northing_m,crs\n"));
+        let (x, y) = Crs::Utm15N.from_wgs84(29.9584, -90.0644);
+        assert!(csv.contains(&format!(",{},{},EPSG:32615\n", x, y)));
+    }
+
+    #[test]
+    fn geojson_declares_only_non_wgs84_crss() {
+        let geojson = to_geojson(&inventory(), Crs::Wgs84);
+        assert!(geojson.get("crs").is_none());
+        let features = geojson["features"].as_array().unwrap();
+        assert_eq!(
+            features[0]["geometry"],
+            json!({ "type": "Point", "coordinates": [-90.0644, 29.9584] })
+        );
+        assert_eq!(
+            features[0]["properties"]["sign_condition"],
+            "Good Condition"
+        );
+        assert_eq!(features[1]["id"], 2);
+        assert_eq!(features[1]["geometry"], Value::Null);
+
+        let projected = to_geojson(&inventory(), Crs::LouisianaSouth);
+        assert_eq!(
+            projected["crs"]["properties"]["name"],
+            "urn:ogc:def:crs:EPSG::3452"
+        );
+        let (x, y) = Crs::LouisianaSouth.from_wgs84(29.9584, -90.0644);
+        assert_eq!(
+            projected["features"][0]["geometry"]["coordinates"],
+            json!([x, y])
+        );
+    }
+
+    #[test]
+    fn kml_is_wgs84_only_and_escapes_names() {
+        let kml = written(ExportFormat::Kml, Crs::Wgs84);
+        assert_eq!(kml.matches("<Placemark").count(), 1);
+        assert!(kml.contains(r#"<Placemark id="sign-1">"#));
+        assert!(kml.contains("<name>1: Rue &lt;Bourbon&gt; &amp; Calle</name>"));
+        assert!(kml.contains(
+            "<description>700 Bourbon St\nGood Condition\n0 tiles damaged</description>"
+        ));
+        assert!(kml.contains("<coordinates>-90.0644,29.9584</coordinates>"));
+
+        let mut output = Vec::new();
+        assert!(matches!(
+            write_export(&mut output, &inventory(), ExportFormat::Kml, Crs::Utm15N),
+            Err(ExportError::UnsupportedCrs {
+                format: ExportFormat::Kml,
+                crs: Crs::Utm15N,
+            })
+        ));
+    }
+
+    #[test]
+    fn json_keeps_every_column_and_adds_projected_ones() {
+        let json: Value = serde_json::from_str(&written(ExportFormat::Json, Crs::Wgs84)).unwrap();
+        assert_eq!(json["crs"], "EPSG:4326");
+        let records = json["records"].as_array().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(
+            records[0].as_object().unwrap().len(),
+            record_fields(&inventory()[0]).len()
+        );
+        assert!(records[0].get("easting_m").is_none());
+
+        let json: Value = serde_json::from_str(&written(ExportFormat::Json, Crs::Utm15N)).unwrap();
+        let (x, y) = Crs::Utm15N.from_wgs84(29.9584, -90.0644);
+        assert_eq!(json["records"][0]["easting_m"], json!(x));
+        assert_eq!(json["records"][0]["northing_m"], json!(y));
+        assert_eq!(json["records"][1]["easting_m"], Value::Null);
+        assert_eq!(json["records"][1]["latitude"], Value::Null);
+    }
+}
@@ -0,0 +1,183 @@
+use crate::csv_parser::{
+    known_columns_str, parse_csv_str, unknown_columns_str, write_csv_columns_to, CsvError,
+};
+use crate::diff::{diff_inventories, RecordChange};
+use data::inventory::TileInventory;
+use data::validation::canonical_condition;
+use serde::Serialize;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Decimal places kept for coordinates; 7 is about a centimeter, finer than any geocoder.
+pub const COORDINATE_DECIMALS: i32 = 7;
+
+#[derive(Error, Debug)]
+pub enum FormatError {
+    #[error("{0}")]
+    Csv(#[from] CsvError),
+    #[error("Formatting would drop the columns {0:?}")]
+    UnknownColumns(Vec<String>),
+}
+
+#[derive(Debug, Serialize)]
+pub struct FormatReport {
+    pub records: usize,
+    /// Whether the records were out of ID order.
+    pub reordered: bool,
+    /// Records whose values changed, e.g. a respelled condition or a rounded coordinate.
+    pub changed: Vec<RecordChange>,
+    /// Whether the input was already byte-for-byte canonical.
+    pub canonical: bool,
+}
+
+/// Puts an inventory in canonical form: sorted by ID (keeping the order of records that
+/// share one), text trimmed, conditions spelled as on the survey, coordinates rounded to
+/// `COORDINATE_DECIMALS` and blank photo columns empty.
+pub fn canonicalize(inventory: &mut [TileInventory]) {
+    inventory.sort_by_key(|item| item.id);
+    for item in inventory {
+        canonicalize_record(item);
+    }
+}
+
+pub fn canonicalize_record(item: &mut TileInventory) {
+    item.street_sign = item.street_sign.trim().to_string();
+    item.street_address = item.street_address.trim().to_string();
+    for text in [
+        &mut item.sign_condition,
+        &mut item.grout_condition,
+        &mut item.wall_type,
+        &mut item.building_occupant,
+        &mut item.title_owner,
+        &mut item.geocode_provider,
+        &mut item.geocode_display_name,
+        &mut item.notes,
+    ] {
+        *text = text
+            .take()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty());
+    }
+    if let Some(condition) = item.sign_condition.as_deref().and_then(canonical_condition) {
+        item.sign_condition = Some(condition.to_string());
+    }
+
+    for coordinate in [
+        &mut item.latitude,
+        &mut item.longitude,
+        &mut item.exif_latitude,
+        &mut item.exif_longitude,
+    ] {
+        *coordinate = coordinate.map(round_coordinate);
+    }
+
+    for photo in item.photo_slots_mut() {
+        *photo = photo
+            .take()
+            .and_then(|path| path.to_str().map(|path| path.trim().to_string()))
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from);
+    }
+}
+
+fn round_coordinate(value: f64) -> f64 {
+    let scale = 10f64.powi(COORDINATE_DECIMALS);
+    (value * scale).round() / scale
+}
+
+/// Formats a CSV's contents, returning the canonical CSV and what changed. The file keeps
+/// the columns it has, in `CSV_HEADERS` order. Refuses files with columns `TileInventory`
+/// doesn't know, which would otherwise be lost.
+pub fn format_csv(input: &[u8]) -> Result<(Vec<u8>, FormatReport), FormatError> {
+    let unknown = unknown_columns_str(input)?;
+    if !unknown.is_empty() {
+        return Err(FormatError::UnknownColumns(unknown));
+    }
+
+    let columns = known_columns_str(input)?;
+    let original = parse_csv_str(input)?;
+    let mut inventory = original.clone();
+    canonicalize(&mut inventory);
+    let mut output = Vec::new();
+    write_csv_columns_to(&mut output, &inventory, &columns)?;
+
+    let report = FormatReport {
+        records: inventory.len(),
+        reordered: original.windows(2).any(|pair| pair[0].id > pair[1].id),
+        changed: diff_inventories(&original, &inventory).changed,
+        canonical: output == input,
+    };
+    Ok((output, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Survey columns plus coordinates, out of order and untidy.
+    const MESSY: &str = "ID,Street Sign,Street Address,Sign Condition,Number of Tiles Damaged,\
+        Grout Condition,Type of Wall,Building Occupant,Title Owner on Builder,\
+        Photo 1,Photo 2,Photo 3,Photo 4,Photo 5,latitude,longitude\n\
+        2,Calle D'Bienville,\"301 Royal St, New Orleans, LA 70130\",good condition,0,\
+        , Brick ,,,Inventory_Images/2.Photo 1.134052.jpg, ,,,,29.95512345678,-90.0668\n\
+        1, Calle D'Bienville ,\"813 Bienville St, New Orleans, LA 70112\",Falling Apart,11,\
+        ,,,,,,,,,,\n";
+
+    #[test]
+    fn formatting_is_idempotent() {
+        let (once, report) = format_csv(MESSY.as_bytes()).unwrap();
+        assert!(!report.canonical);
+        assert!(report.reordered);
+        assert_eq!(report.records, 2);
+
+        let (twice, report) = format_csv(&once).unwrap();
+        assert!(report.canonical);
+        assert!(!report.reordered);
+        assert!(report.changed.is_empty());
+        assert_eq!(once, twice);
+    }
+
+    #[test]
+    fn canonical_form() {
+        let mut inventory = parse_csv_str(MESSY.as_bytes()).unwrap();
+        canonicalize(&mut inventory);
+        assert_eq!(inventory[0].id, 1);
+        assert_eq!(inventory[0].street_sign, "Calle D'Bienville");
+        assert_eq!(
+            inventory[1].sign_condition.as_deref(),
+            Some("Good Condition")
+        );
+        assert_eq!(inventory[1].latitude, Some(29.9551235));
+        assert_eq!(inventory[1].wall_type.as_deref(), Some("Brick"));
+        assert_eq!(inventory[1].photo_2, None);
+    }
+
+    #[test]
+    fn keeps_the_input_columns_in_canonical_order() {
+        let (output, _) = format_csv(MESSY.as_bytes()).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().next(), MESSY.lines().next());
+
+        let input = "Notes,Photo 5,Photo 4,Photo 3,Photo 2,Photo 1,Street Address,ID,latitude,\
+            Street Sign\n\
+            ,,,,,3.Photo 1.jpg, 700 Bourbon St,3,29.95843,Rue Bourbon\n";
+        let (output, report) = format_csv(input.as_bytes()).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "ID,Street Sign,Street Address,Photo 1,Photo 2,Photo 3,Photo 4,Photo 5,latitude,Notes\n\
+             3,Rue Bourbon,700 Bourbon St,3.Photo 1.jpg,,,,,29.95843,\n"
+        );
+        assert!(!report.canonical);
+        assert_eq!(report.changed.len(), 1);
+    }
+
+    #[test]
+    fn refuses_unknown_columns() {
+        let input =
+            "ID,Street Sign,Street Address,Surveyor\n1,Calle D'Bienville,813 Bienville St,Ana\n";
+        assert!(matches!(
+            format_csv(input.as_bytes()),
+            Err(FormatError::UnknownColumns(columns)) if columns == ["Surveyor"]
+        ));
+    }
+}
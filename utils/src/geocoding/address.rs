@@ -0,0 +1,261 @@
+use std::fmt;
+
+/// Where addresses without a city or state are assumed to be.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    pub city: String,
+    /// Full state name; Nominatim matches "Louisiana" more reliably than "LA".
+    pub state: String,
+    /// ISO 3166-1 alpha-2 code, if lookups should be limited to one country.
+    pub country_code: Option<String>,
+}
+
+impl Default for Region {
+    fn default() -> Self {
+        Self {
+            city: "New Orleans".to_string(),
+            state: "Louisiana".to_string(),
+            country_code: Some("us".to_string()),
+        }
+    }
+}
+
+/// An address split into Nominatim's structured search fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructuredAddress {
+    /// House number and street, e.g. "813 Bienville Street".
+    pub street: Option<String>,
+    /// Name of a place without a house number, e.g. "Jackson Square".
+    pub amenity: Option<String>,
+    pub city: String,
+    pub state: String,
+    pub postalcode: Option<String>,
+    pub country_code: Option<String>,
+}
+
+impl StructuredAddress {
+    /// The street or landmark line.
+    pub fn first_line(&self) -> &str {
+        self.street
+            .as_deref()
+            .or(self.amenity.as_deref())
+            .unwrap_or_default()
+    }
+}
+
+/// The one-line form, used as the free-form query, cache key and checkpoint key:
+/// "813 Bienville Street, New Orleans, Louisiana 70112".
+impl fmt::Display for StructuredAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, {}, {}", self.first_line(), self.city, self.state)?;
+        if let Some(postalcode) = &self.postalcode {
+            write!(f, " {}", postalcode)?;
+        }
+        Ok(())
+    }
+}
+
+/// Turns the survey's addresses into structured queries.
+///
+/// The city and postal code come from the address when it has them and from the region
+/// otherwise; the state is always the region's. Street abbreviations are spelled out the way
+/// OpenStreetMap names streets: a leading or inner "St" is "Saint", a trailing one "Street".
+#[derive(Debug, Clone, Default)]
+pub struct AddressNormalizer {
+    region: Region,
+}
+
+impl AddressNormalizer {
+    pub fn new(region: Region) -> Self {
+        Self { region }
+    }
+
+    pub fn region(&self) -> &Region {
+        &self.region
+    }
+
+    pub fn normalize(&self, address: &str) -> StructuredAddress {
+        let mut parts = address
+            .split(',')
+            .map(str::trim)
+            .filter(|part| !part.is_empty());
+        let line = parts.next().unwrap_or_default();
+
+        let mut city = None;
+        let mut postalcode = None;
+        for part in parts {
+            match part.rsplit_once(' ') {
+                // "LA 70112"
+                Some((state, zip)) if is_state_code(state) && is_postal_code(zip) => {
+                    postalcode = Some(zip.to_string());
+                }
+                _ if is_state_code(part) || is_country(part) => {}
+                _ if is_postal_code(part) => postalcode = Some(part.to_string()),
+                _ if city.is_none() => city = Some(part.to_string()),
+                _ => {}
+            }
+        }
+
+        let line = expand_street_line(line);
+        let has_house_number = is_house_number(&line);
+        StructuredAddress {
+            street: has_house_number.then(|| line.clone()),
+            amenity: (!has_house_number).then_some(line),
+            city: city.unwrap_or_else(|| self.region.city.clone()),
+            state: self.region.state.clone(),
+            postalcode,
+            country_code: self.region.country_code.clone(),
+        }
+    }
+}
+
+/// Spells out abbreviations in a street line: "621 St Louis St" becomes
+/// "621 Saint Louis Street" and "222 N Rampart St" becomes "222 North Rampart Street".
+pub fn expand_street_line(line: &str) -> String {
+    let words: Vec<&str> = line
+        .split_whitespace()
+        .map(|word| word.trim_end_matches('.'))
+        .collect();
+    let last = words.len().saturating_sub(1);
+    words
+        .iter()
+        .enumerate()
+        .map(|(index, &word)| match word.to_lowercase().as_str() {
+            "st" if index == last && index > 0 && !is_house_number(words[index - 1]) => "Street",
+            "st" => "Saint",
+            "ave" if index == last => "Avenue",
+            "blvd" if index == last => "Boulevard",
+            "pl" if index == last => "Place",
+            "sq" if index == last => "Square",
+            "n" if index < last => "North",
+            "s" if index < last => "South",
+            _ => word,
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Comparison key for street names, so "St Ann St." and "Saint Ann Street" or
+/// "N Rampart St" and "North Rampart Street" are the same street. The trailing street type
+/// is dropped because the survey sometimes leaves it off ("1005 St Peter").
+pub fn normalize_street(street: &str) -> String {
+    let expanded = expand_street_line(street).to_lowercase();
+    let mut words: Vec<&str> = expanded
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+    if words.len() > 1
+        && matches!(
+            words.last(),
+            Some(&("street" | "avenue" | "boulevard" | "place"))
+        )
+    {
+        words.pop();
+    }
+    words.join(" ")
+}
+
+fn is_house_number(word: &str) -> bool {
+    word.starts_with(|c: char| c.is_ascii_digit())
+}
+
+fn is_state_code(part: &str) -> bool {
+    part.len() == 2 && part.chars().all(|c| c.is_ascii_uppercase())
+}
+
+fn is_postal_code(part: &str) -> bool {
+    let (zip, plus_four) = part.split_once('-').unwrap_or((part, "0000"));
+    zip.len() == 5
+        && zip.chars().all(|c| c.is_ascii_digit())
+        && plus_four.len() == 4
+        && plus_four.chars().all(|c| c.is_ascii_digit())
+}
+
+fn is_country(part: &str) -> bool {
+    matches!(
+        part.to_lowercase().as_str(),
+        "us" | "usa" | "united states" | "united states of america"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_a_full_address() {
+        let address =
+            AddressNormalizer::default().normalize("813 Bienville St, New Orleans, LA 70112");
+        assert_eq!(address.street.as_deref(), Some("813 Bienville Street"));
+        assert_eq!(address.amenity, None);
+        assert_eq!(address.city, "New Orleans");
+        assert_eq!(address.state, "Louisiana");
+        assert_eq!(address.postalcode.as_deref(), Some("70112"));
+        assert_eq!(address.country_code.as_deref(), Some("us"));
+        assert_eq!(
+            address.to_string(),
+            "813 Bienville Street, New Orleans, Louisiana 70112"
+        );
+    }
+
+    #[test]
+    fn fills_in_the_region() {
+        let normalizer = AddressNormalizer::new(Region {
+            city: "Metairie".to_string(),
+            state: "Louisiana".to_string(),
+            country_code: None,
+        });
+        let address = normalizer.normalize("3400 Severn Ave");
+        assert_eq!(address.street.as_deref(), Some("3400 Severn Avenue"));
+        assert_eq!(address.city, "Metairie");
+        assert_eq!(address.postalcode, None);
+        assert_eq!(address.country_code, None);
+    }
+
+    #[test]
+    fn skips_states_and_countries() {
+        let address = AddressNormalizer::default()
+            .normalize("621 St Louis St, Mandeville, LA, 70448-1234, USA");
+        assert_eq!(address.street.as_deref(), Some("621 Saint Louis Street"));
+        assert_eq!(address.city, "Mandeville");
+        assert_eq!(address.postalcode.as_deref(), Some("70448-1234"));
+    }
+
+    #[test]
+    fn places_without_a_house_number_are_amenities() {
+        let address = AddressNormalizer::default().normalize("Jackson Sq, New Orleans");
+        assert_eq!(address.street, None);
+        assert_eq!(address.amenity.as_deref(), Some("Jackson Square"));
+        assert_eq!(address.first_line(), "Jackson Square");
+    }
+
+    #[test]
+    fn expands_abbreviations_by_position() {
+        assert_eq!(
+            expand_street_line("222 N. Rampart St."),
+            "222 North Rampart Street"
+        );
+        assert_eq!(expand_street_line("St Ann St"), "Saint Ann Street");
+        // A house number before a trailing "St" means the street is named for a saint.
+        assert_eq!(expand_street_line("700 St"), "700 Saint");
+        // Direction letters are only abbreviations before the street name.
+        assert_eq!(expand_street_line("100 Avenue N"), "100 Avenue N");
+        assert_eq!(expand_street_line("1 Ave Blvd"), "1 Ave Boulevard");
+    }
+
+    #[test]
+    fn street_keys_ignore_abbreviations_and_the_street_type() {
+        let key = normalize_street("Saint Ann Street");
+        assert_eq!(key, "saint ann");
+        assert_eq!(normalize_street("St. Ann St."), key);
+        assert_eq!(normalize_street("st ann"), key);
+        assert_eq!(
+            normalize_street("N Rampart St"),
+            normalize_street("North Rampart Street")
+        );
+        assert_ne!(normalize_street("Ann Street"), key);
+        // A street named only by its type keeps it.
+        assert_eq!(normalize_street("Esplanade Ave"), "esplanade");
+        assert_eq!(normalize_street("Street"), "street");
+    }
+}
@@ -0,0 +1,631 @@
+use super::{modern_street_name, AddressNormalizer, Geocoder, GeocodingError, StructuredAddress};
+use data::geo::GeocodeResult;
+use data::inventory::TileInventory;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::future::Future;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::time::sleep;
+
+/// How often and how patiently to retry transient failures.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Total tries per address, including the first.
+    pub max_attempts: u32,
+    pub initial_delay: Duration,
+    /// The longest wait between tries, including one asked for with `Retry-After`.
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            initial_delay: Duration::from_secs(2),
+            max_delay: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `retry` (0-based), doubling each time up to `max_delay`.
+    fn delay(&self, retry: u32) -> Duration {
+        self.initial_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BatchOptions {
+    pub retry: RetryPolicy,
+    /// Turns `street_address` into the query sent to the geocoder.
+    pub normalizer: AddressNormalizer,
+    /// JSON-lines file each outcome is appended to as soon as it is known. Found and
+    /// not-found records already in it are not queried again, so a killed run can resume.
+    pub checkpoint: Option<PathBuf>,
+    pub selection: Selection,
+}
+
+/// Which records a batch looks at. The default is every record that still needs coordinates.
+#[derive(Debug, Clone, Default)]
+pub struct Selection {
+    /// Only these record IDs.
+    pub ids: Option<HashSet<u32>>,
+    /// Only records whose address, sign or the sign's modern street name contains this,
+    /// ignoring case.
+    pub street: Option<String>,
+    /// Geocode matching records again even if they already have coordinates. A record that
+    /// isn't found this time keeps the coordinates it had.
+    pub force: bool,
+}
+
+impl Selection {
+    /// Whether `item` passes the ID and street filters, regardless of its coordinates.
+    pub fn matches(&self, item: &TileInventory) -> bool {
+        if let Some(ids) = &self.ids {
+            if !ids.contains(&item.id) {
+                return false;
+            }
+        }
+        match &self.street {
+            Some(street) => {
+                let street = street.to_lowercase();
+                [
+                    Some(item.street_address.as_str()),
+                    Some(item.street_sign.as_str()),
+                    modern_street_name(&item.street_sign),
+                ]
+                .into_iter()
+                .flatten()
+                .any(|text| text.to_lowercase().contains(&street))
+            }
+            None => true,
+        }
+    }
+
+    fn needs_geocoding(&self, item: &TileInventory) -> bool {
+        self.force || item.latitude.is_none() || item.longitude.is_none()
+    }
+}
+
+/// A lookup a batch would make, as planned by `plan_queries`.
+#[derive(Debug, Clone)]
+pub struct PlannedQuery {
+    pub id: u32,
+    pub street_address: String,
+    pub query: StructuredAddress,
+    /// Whether the record already has coordinates (and is only included because of `force`).
+    pub located: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum Outcome {
+    Found {
+        latitude: f64,
+        longitude: f64,
+        result: GeocodeResult,
+    },
+    NotFound,
+    Failed {
+        error: String,
+        transient: bool,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordOutcome {
+    pub id: u32,
+    pub address: String,
+    pub attempts: u32,
+    #[serde(flatten)]
+    pub outcome: Outcome,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct BatchReport {
+    /// One entry per record that needed geocoding, in inventory order.
+    pub outcomes: Vec<RecordOutcome>,
+    /// Records that already had coordinates.
+    pub skipped: usize,
+    /// Records left out by the selection's ID and street filters.
+    pub filtered: usize,
+    /// Records answered from the checkpoint of an earlier run.
+    pub resumed: usize,
+}
+
+impl BatchReport {
+    pub fn found(&self) -> usize {
+        self.count(|outcome| matches!(outcome, Outcome::Found { .. }))
+    }
+
+    pub fn not_found(&self) -> usize {
+        self.count(|outcome| matches!(outcome, Outcome::NotFound))
+    }
+
+    pub fn failed(&self) -> usize {
+        self.count(|outcome| matches!(outcome, Outcome::Failed { .. }))
+    }
+
+    fn count(&self, predicate: impl Fn(&Outcome) -> bool) -> usize {
+        self.outcomes
+            .iter()
+            .filter(|record| predicate(&record.outcome))
+            .count()
+    }
+}
+
+/// The queries `geocode_inventory` would send for `inventory`, without sending any or
+/// touching the checkpoint.
+pub fn plan_queries(inventory: &[TileInventory], options: &BatchOptions) -> Vec<PlannedQuery> {
+    inventory
+        .iter()
+        .filter(|item| options.selection.matches(item) && options.selection.needs_geocoding(item))
+        .map(|item| PlannedQuery {
+            id: item.id,
+            street_address: item.street_address.clone(),
+            query: options.normalizer.normalize(&item.street_address),
+            located: item.latitude.is_some() && item.longitude.is_some(),
+        })
+        .collect()
+}
+
+/// Geocodes every selected record missing coordinates, or every selected record when the
+/// selection forces it.
+///
+/// A failing address no longer stops the batch: transient errors (HTTP 429/5xx, timeouts) are
+/// retried with exponential backoff, and whatever still fails is recorded in the report while
+/// the remaining records carry on. Only checkpoint I/O errors abort the run.
+pub async fn geocode_inventory<G: Geocoder>(
+    geocoder: &G,
+    inventory: &mut [TileInventory],
+    options: &BatchOptions,
+) -> Result<BatchReport, GeocodingError> {
+    let mut report = BatchReport::default();
+    let mut checkpoint = match &options.checkpoint {
+        Some(path) => Some(Checkpoint::open(path)?),
+        None => None,
+    };
+
+    for item in inventory.iter_mut() {
+        if !options.selection.matches(item) {
+            report.filtered += 1;
+            continue;
+        }
+        if !options.selection.needs_geocoding(item) {
+            report.skipped += 1;
+            continue;
+        }
+        let query = options.normalizer.normalize(&item.street_address);
+        let address = query.to_string();
+
+        let resumed = checkpoint
+            .as_mut()
+            .and_then(|checkpoint| checkpoint.previous.remove(&(item.id, address.clone())));
+        let record = match resumed {
+            Some(record) => {
+                report.resumed += 1;
+                record
+            }
+            None => {
+                let (outcome, attempts) =
+                    geocode_with_retry(geocoder, &query, &options.retry).await;
+                let record = RecordOutcome {
+                    id: item.id,
+                    address,
+                    attempts,
+                    outcome,
+                };
+                if let Some(checkpoint) = checkpoint.as_mut() {
+                    checkpoint.append(&record)?;
+                }
+                record
+            }
+        };
+
+        if let Outcome::Found {
+            latitude,
+            longitude,
+            result,
+        } = &record.outcome
+        {
+            item.latitude = Some(*latitude);
+            item.longitude = Some(*longitude);
+            item.set_geocode_result(Some(result.clone()));
+        }
+        log::info!(
+            "Street: {}, Lat/Long:{:?}/{:?} ({})",
+            record.address,
+            item.latitude,
+            item.longitude,
+            geocoder.name()
+        );
+        report.outcomes.push(record);
+    }
+
+    Ok(report)
+}
+
+async fn geocode_with_retry<G: Geocoder>(
+    geocoder: &G,
+    address: &StructuredAddress,
+    policy: &RetryPolicy,
+) -> (Outcome, u32) {
+    let label = address.to_string();
+    let (result, attempts) = with_retry(policy, &label, || geocoder.geocode(address)).await;
+    let outcome = match result {
+        Ok(Some(hit)) => Outcome::Found {
+            latitude: hit.latitude,
+            longitude: hit.longitude,
+            result: hit.result,
+        },
+        Ok(None) => Outcome::NotFound,
+        Err(err) => Outcome::Failed {
+            transient: err.is_transient(),
+            error: err.to_string(),
+        },
+    };
+    (outcome, attempts)
+}
+
+/// Runs `call` until it succeeds, fails permanently or uses up the policy's attempts,
+/// returning the last result and how many attempts were made. `label` names the request in
+/// the retry messages.
+///
+/// A server that says how long to wait, with `Retry-After`, is waited for that long instead
+/// of the policy's backoff. If it asks for longer than `max_delay`, the call fails there
+/// rather than stalling the batch; a later run can try the record again.
+pub(super) async fn with_retry<T, F, Fut>(
+    policy: &RetryPolicy,
+    label: &str,
+    mut call: F,
+) -> (Result<T, GeocodingError>, u32)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, GeocodingError>>,
+{
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        match call().await {
+            Err(err) if err.is_transient() && attempts < policy.max_attempts => {
+                let delay = err
+                    .retry_after()
+                    .unwrap_or_else(|| policy.delay(attempts - 1));
+                if delay > policy.max_delay {
+                    log::warn!(
+                        "{} for {:?}, asked to wait {:?}; giving up",
+                        err,
+                        label,
+                        delay
+                    );
+                    return (Err(err), attempts);
+                }
+                log::warn!("{} for {:?}, retrying in {:?}", err, label, delay);
+                sleep(delay).await;
+            }
+            result => return (result, attempts),
+        }
+    }
+}
+
+/// Final outcomes from earlier runs, keyed by record ID and query.
+type Checkpointed = HashMap<(u32, String), RecordOutcome>;
+
+struct Checkpoint {
+    path: PathBuf,
+    file: File,
+    previous: Checkpointed,
+}
+
+impl Checkpoint {
+    fn open(path: &Path) -> Result<Self, GeocodingError> {
+        let io_error = |source| GeocodingError::Checkpoint {
+            path: path.to_path_buf(),
+            source,
+        };
+
+        let contents = read_checkpoint_file(path)?;
+        let previous = parse_checkpoint(&contents);
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(io_error)?;
+        if !contents.is_empty() && !contents.ends_with('\n') {
+            // Keep the next record off the end of the partial line.
+            writeln!(file).map_err(io_error)?;
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            previous,
+        })
+    }
+
+    fn append(&mut self, record: &RecordOutcome) -> Result<(), GeocodingError> {
+        let line = serde_json::to_string(record).expect("outcome serializes to JSON");
+        writeln!(self.file, "{}", line)
+            .and_then(|_| self.file.flush())
+            .map_err(|source| GeocodingError::Checkpoint {
+                path: self.path.clone(),
+                source,
+            })
+    }
+}
+
+fn read_checkpoint_file(path: &Path) -> Result<String, GeocodingError> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(contents),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(source) => Err(GeocodingError::Checkpoint {
+            path: path.to_path_buf(),
+            source,
+        }),
+    }
+}
+
+fn parse_checkpoint(contents: &str) -> Checkpointed {
+    let mut previous = HashMap::new();
+    // A run killed mid-write can leave a partial last line; skip anything that doesn't
+    // parse rather than refusing to resume.
+    for line in contents.lines() {
+        let Ok(record) = serde_json::from_str::<RecordOutcome>(line) else {
+            continue;
+        };
+        if !matches!(record.outcome, Outcome::Failed { .. }) {
+            previous.insert((record.id, record.address.clone()), record);
+        }
+    }
+    previous
+}
+
+/// The outcomes a run with this checkpoint would resume instead of querying, keyed by record
+/// ID and query. A missing checkpoint has none.
+pub fn read_checkpoint(
+    path: &Path,
+) -> Result<HashMap<(u32, String), RecordOutcome>, GeocodingError> {
+    read_checkpoint_file(path).map(|contents| parse_checkpoint(&contents))
+}
+
+/// Deletes a checkpoint once its results have been written out.
+pub fn remove_checkpoint(path: &Path) -> Result<(), GeocodingError> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(GeocodingError::Checkpoint {
+            path: path.to_path_buf(),
+            source: err,
+        }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geocoding::MockGeocoder;
+    use std::time::Instant;
+
+    const BIENVILLE: &str = "813 Bienville Street, New Orleans, Louisiana 70112";
+    const ROYAL: &str = "301 Royal Street, New Orleans, Louisiana 70130";
+    const CHARTRES: &str = "500 Chartres Street, New Orleans, Louisiana 70130";
+
+    fn record(id: u32, address: &str) -> TileInventory {
+        TileInventory::new(id, "Calle D'Bienville", address)
+    }
+
+    fn inventory() -> Vec<TileInventory> {
+        vec![
+            record(1, "813 Bienville St, New Orleans, LA 70112"),
+            record(2, "301 Royal St, New Orleans, LA 70130"),
+            record(3, "500 Chartres St, New Orleans, LA 70130"),
+        ]
+    }
+
+    fn quick_retries() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(1),
+        }
+    }
+
+    fn status(status: u16, retry_after: Option<Duration>) -> GeocodingError {
+        GeocodingError::Status {
+            status,
+            retry_after,
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_transient_errors_until_they_pass() {
+        let mut calls = 0;
+        let (result, attempts) = with_retry(&quick_retries(), "test", || {
+            calls += 1;
+            let result = if calls < 3 {
+                Err(status(503, None))
+            } else {
+                Ok(calls)
+            };
+            async move { result }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(attempts, 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_the_last_attempt() {
+        let (result, attempts) = with_retry(&quick_retries(), "test", || async {
+            Err::<(), _>(status(429, None))
+        })
+        .await;
+        assert!(matches!(
+            result,
+            Err(GeocodingError::Status { status: 429, .. })
+        ));
+        assert_eq!(attempts, 3);
+    }
+
+    #[tokio::test]
+    async fn permanent_errors_are_not_retried() {
+        let (result, attempts) = with_retry(&quick_retries(), "test", || async {
+            Err::<(), _>(status(404, None))
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn waits_as_long_as_retry_after_asks() {
+        let policy = RetryPolicy {
+            max_attempts: 2,
+            initial_delay: Duration::ZERO,
+            max_delay: Duration::from_secs(1),
+        };
+        let started = Instant::now();
+        let mut calls = 0;
+        let (result, attempts) = with_retry(&policy, "test", || {
+            calls += 1;
+            let result = if calls == 1 {
+                Err(status(429, Some(Duration::from_millis(50))))
+            } else {
+                Ok(())
+            };
+            async move { result }
+        })
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(attempts, 2);
+        assert!(started.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn gives_up_when_retry_after_is_longer_than_max_delay() {
+        let started = Instant::now();
+        let mut calls = 0;
+        let (result, attempts) = with_retry(&quick_retries(), "test", || {
+            calls += 1;
+            async { Err::<(), _>(status(429, Some(Duration::from_secs(86_400)))) }
+        })
+        .await;
+        assert!(matches!(
+            result,
+            Err(GeocodingError::Status { status: 429, .. })
+        ));
+        assert_eq!((attempts, calls), (1, 1));
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn reports_each_record_and_carries_on_past_failures() {
+        let geocoder = MockGeocoder::new()
+            .with_response(BIENVILLE, 29.9557409, -90.0686785)
+            .with_failure(ROYAL, 503);
+        let mut inventory = inventory();
+        let options = BatchOptions {
+            retry: quick_retries(),
+            ..BatchOptions::default()
+        };
+        let report = geocode_inventory(&geocoder, &mut inventory, &options)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            (report.found(), report.not_found(), report.failed()),
+            (1, 1, 1)
+        );
+        assert_eq!(inventory[0].latitude, Some(29.9557409));
+        assert_eq!(inventory[1].latitude, None);
+        let failed = &report.outcomes[1];
+        assert_eq!(failed.attempts, 3);
+        assert!(matches!(
+            failed.outcome,
+            Outcome::Failed {
+                transient: true,
+                ..
+            }
+        ));
+        assert_eq!(
+            geocoder.queries(),
+            [BIENVILLE, ROYAL, ROYAL, ROYAL, CHARTRES]
+        );
+    }
+
+    #[tokio::test]
+    async fn selects_records_by_id_and_force() {
+        let geocoder = MockGeocoder::new().with_response(BIENVILLE, 29.9557409, -90.0686785);
+        let mut inventory = inventory();
+        inventory[0].latitude = Some(1.0);
+        inventory[0].longitude = Some(1.0);
+
+        let mut options = BatchOptions {
+            selection: Selection {
+                ids: Some([1, 2].into()),
+                ..Selection::default()
+            },
+            ..BatchOptions::default()
+        };
+        let report = geocode_inventory(&geocoder, &mut inventory, &options)
+            .await
+            .unwrap();
+        assert_eq!((report.skipped, report.filtered), (1, 1));
+        assert_eq!(geocoder.queries(), [ROYAL]);
+
+        options.selection.force = true;
+        geocode_inventory(&geocoder, &mut inventory, &options)
+            .await
+            .unwrap();
+        assert_eq!(inventory[0].latitude, Some(29.9557409));
+    }
+
+    #[tokio::test]
+    async fn resumes_from_the_checkpoint() {
+        let checkpoint = std::env::temp_dir().join(format!(
+            "geocode-batch-test-{}.checkpoint.jsonl",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&checkpoint);
+        let options = BatchOptions {
+            retry: quick_retries(),
+            checkpoint: Some(checkpoint.clone()),
+            ..BatchOptions::default()
+        };
+
+        // The first run finds one address, finds nothing for another and fails on the third.
+        let first = MockGeocoder::new()
+            .with_response(BIENVILLE, 29.9557409, -90.0686785)
+            .with_failure(CHARTRES, 500);
+        let report = geocode_inventory(&first, &mut inventory(), &options)
+            .await
+            .unwrap();
+        assert_eq!(report.resumed, 0);
+        assert_eq!(read_checkpoint(&checkpoint).unwrap().len(), 2);
+
+        // A killed run can leave half a line behind.
+        OpenOptions::new()
+            .append(true)
+            .open(&checkpoint)
+            .and_then(|mut file| write!(file, "{{\"id\":3,"))
+            .unwrap();
+
+        // The rerun only asks about the failed address, and keeps the found coordinates.
+        let second = MockGeocoder::new().with_response(CHARTRES, 29.9571, -90.0636);
+        let mut inventory = inventory();
+        let report = geocode_inventory(&second, &mut inventory, &options)
+            .await
+            .unwrap();
+        assert_eq!(report.resumed, 2);
+        assert_eq!(second.queries(), [CHARTRES]);
+        assert_eq!(inventory[0].latitude, Some(29.9557409));
+        assert_eq!(inventory[2].latitude, Some(29.9571));
+        assert_eq!(read_checkpoint(&checkpoint).unwrap().len(), 3);
+
+        remove_checkpoint(&checkpoint).unwrap();
+        assert!(read_checkpoint(&checkpoint).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,328 @@
+use super::{unix_timestamp, GeocodeHit, Geocoder, GeocodingError, ReverseHit, StructuredAddress};
+use data::geo::{GeocodeResult, MatchType};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// One remembered lookup. Misses are cached too, so an address Nominatim can't find isn't
+/// asked about again on every run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub raw: serde_json::Value,
+    /// Seconds since the Unix epoch when the provider answered.
+    pub timestamp: u64,
+    pub provider: String,
+    pub display_name: Option<String>,
+    /// Null only for misses, like the coordinates and `confidence`.
+    pub match_type: Option<MatchType>,
+    pub confidence: Option<f64>,
+}
+
+impl CacheEntry {
+    fn hit(&self) -> Option<GeocodeHit> {
+        Some(GeocodeHit {
+            latitude: self.latitude?,
+            longitude: self.longitude?,
+            result: GeocodeResult {
+                provider: self.provider.clone(),
+                display_name: self.display_name.clone(),
+                match_type: self.match_type?,
+                confidence: self.confidence.unwrap_or_default(),
+                timestamp: self.timestamp,
+            },
+            raw: self.raw.clone(),
+        })
+    }
+}
+
+/// Geocoding results persisted as JSON, keyed by provider and `normalize_address`, so one
+/// backend's answers are never served in place of another's.
+#[derive(Debug, Default)]
+pub struct GeocodeCache {
+    path: Option<PathBuf>,
+    entries: BTreeMap<String, CacheEntry>,
+    ttl: Option<Duration>,
+}
+
+impl GeocodeCache {
+    /// Loads the cache at `path`, starting empty if the file doesn't exist yet.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, GeocodingError> {
+        let path = path.as_ref().to_path_buf();
+        let entries = match fs::read(&path) {
+            Ok(bytes) => {
+                serde_json::from_slice(&bytes).map_err(|source| GeocodingError::CacheFormat {
+                    path: path.clone(),
+                    source,
+                })?
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(source) => return Err(GeocodingError::CacheIo { path, source }),
+        };
+        Ok(Self {
+            path: Some(path),
+            entries,
+            ttl: None,
+        })
+    }
+
+    /// A cache that is never written to disk.
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Entries older than `ttl` are ignored (and replaced on the next lookup).
+    pub fn with_ttl(mut self, ttl: Option<Duration>) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// `provider`'s entry for `address`, unless it has expired.
+    pub fn get(&self, provider: &str, address: &str) -> Option<&CacheEntry> {
+        let entry = self.entries.get(&cache_key(provider, address))?;
+        match self.ttl {
+            Some(ttl) if unix_timestamp().saturating_sub(entry.timestamp) > ttl.as_secs() => None,
+            _ => Some(entry),
+        }
+    }
+
+    pub fn insert(&mut self, address: &str, provider: &str, hit: Option<&GeocodeHit>) {
+        self.entries.insert(
+            cache_key(provider, address),
+            CacheEntry {
+                latitude: hit.map(|hit| hit.latitude),
+                longitude: hit.map(|hit| hit.longitude),
+                raw: hit.map_or(serde_json::Value::Null, |hit| hit.raw.clone()),
+                timestamp: hit.map_or_else(unix_timestamp, |hit| hit.result.timestamp),
+                provider: provider.to_string(),
+                display_name: hit.and_then(|hit| hit.result.display_name.clone()),
+                match_type: hit.map(|hit| hit.result.match_type),
+                confidence: hit.map(|hit| hit.result.confidence),
+            },
+        );
+    }
+
+    /// Writes the cache back to the file it was opened from; a no-op for in-memory caches.
+    pub fn save(&self) -> Result<(), GeocodingError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let json = serde_json::to_vec_pretty(&self.entries).map_err(|source| {
+            GeocodingError::CacheFormat {
+                path: path.clone(),
+                source,
+            }
+        })?;
+        // Write then rename so an interrupted run can't leave a truncated cache behind.
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, json)
+            .and_then(|_| fs::rename(&tmp, path))
+            .map_err(|source| GeocodingError::CacheIo {
+                path: path.clone(),
+                source,
+            })
+    }
+}
+
+/// Cache key for an address: lowercased, punctuation dropped and whitespace collapsed, so
+/// "813 Bienville St., New Orleans" and "813 bienville st, new orleans" share an entry.
+pub fn normalize_address(address: &str) -> String {
+    address
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// `provider:address`; normalized addresses have no colons, so the two can't run together.
+fn cache_key(provider: &str, address: &str) -> String {
+    format!("{}:{}", provider, normalize_address(address))
+}
+
+/// Wraps another geocoder, answering from a `GeocodeCache` before calling it.
+pub struct CachedGeocoder<G> {
+    inner: G,
+    cache: Mutex<GeocodeCache>,
+    refresh: bool,
+}
+
+impl<G: Geocoder> CachedGeocoder<G> {
+    pub fn new(inner: G, cache: GeocodeCache) -> Self {
+        Self {
+            inner,
+            cache: Mutex::new(cache),
+            refresh: false,
+        }
+    }
+
+    /// Ignore existing entries and re-query everything, updating the cache as it goes.
+    pub fn refresh(mut self, refresh: bool) -> Self {
+        self.refresh = refresh;
+        self
+    }
+
+    pub fn save(&self) -> Result<(), GeocodingError> {
+        self.lock().save()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, GeocodeCache> {
+        self.cache.lock().expect("geocoding cache poisoned")
+    }
+}
+
+impl<G: Geocoder + Sync> Geocoder for CachedGeocoder<G> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn geocode(
+        &self,
+        address: &StructuredAddress,
+    ) -> Result<Option<GeocodeHit>, GeocodingError> {
+        let key = address.to_string();
+        if !self.refresh {
+            if let Some(entry) = self.lock().get(self.inner.name(), &key) {
+                log::info!("Cache hit ({}): {}", entry.provider, key);
+                return Ok(entry.hit());
+            }
+        }
+
+        let hit = self.inner.geocode(address).await?;
+        self.lock().insert(&key, self.inner.name(), hit.as_ref());
+        Ok(hit)
+    }
+
+    /// Reverse lookups go straight to the wrapped backend; only forward results are cached.
+    async fn reverse(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<Option<ReverseHit>, GeocodingError> {
+        self.inner.reverse(latitude, longitude).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geocoding::{AddressNormalizer, MockGeocoder};
+
+    const BIENVILLE: &str = "813 Bienville Street, New Orleans, Louisiana 70112";
+
+    fn hit(timestamp: u64) -> GeocodeHit {
+        GeocodeHit {
+            latitude: 29.9557409,
+            longitude: -90.0686785,
+            result: GeocodeResult {
+                provider: "nominatim".to_string(),
+                display_name: Some(BIENVILLE.to_string()),
+                match_type: MatchType::House,
+                confidence: 0.9,
+                timestamp,
+            },
+            raw: serde_json::Value::Null,
+        }
+    }
+
+    #[test]
+    fn addresses_share_an_entry_however_they_are_spelled() {
+        assert_eq!(
+            normalize_address("813 Bienville St., New  Orleans"),
+            normalize_address("813 bienville st, new orleans")
+        );
+        let mut cache = GeocodeCache::in_memory();
+        cache.insert(BIENVILLE, "nominatim", Some(&hit(unix_timestamp())));
+        assert!(cache
+            .get(
+                "nominatim",
+                "813 BIENVILLE STREET, New Orleans, Louisiana 70112"
+            )
+            .is_some());
+    }
+
+    #[test]
+    fn providers_keep_their_own_answers() {
+        let mut cache = GeocodeCache::in_memory();
+        cache.insert(BIENVILLE, "nominatim", Some(&hit(unix_timestamp())));
+        assert!(cache.get("offline", BIENVILLE).is_none());
+        cache.insert(BIENVILLE, "offline", None);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("offline", BIENVILLE).unwrap().latitude, None);
+        assert!(cache
+            .get("nominatim", BIENVILLE)
+            .unwrap()
+            .latitude
+            .is_some());
+    }
+
+    #[test]
+    fn entries_expire_after_the_ttl() {
+        let day = 24 * 60 * 60;
+        let mut cache = GeocodeCache::in_memory();
+        cache.insert(
+            BIENVILLE,
+            "nominatim",
+            Some(&hit(unix_timestamp() - 10 * day)),
+        );
+        assert!(cache.get("nominatim", BIENVILLE).is_some());
+
+        let cache = cache.with_ttl(Some(Duration::from_secs(30 * day)));
+        assert!(cache.get("nominatim", BIENVILLE).is_some());
+        let cache = cache.with_ttl(Some(Duration::from_secs(day)));
+        assert!(cache.get("nominatim", BIENVILLE).is_none());
+    }
+
+    #[tokio::test]
+    async fn answers_from_the_cache_unless_refreshing() {
+        let query = AddressNormalizer::default().normalize("813 Bienville St");
+        let missing = AddressNormalizer::default().normalize("1 Nowhere St");
+        let mock = MockGeocoder::new().with_response(query.to_string(), 29.9557409, -90.0686785);
+        let geocoder = CachedGeocoder::new(mock, GeocodeCache::in_memory());
+
+        for _ in 0..2 {
+            let hit = geocoder.geocode(&query).await.unwrap().unwrap();
+            assert_eq!(hit.latitude, 29.9557409);
+            assert_eq!(hit.result.match_type, MatchType::House);
+            // Misses are remembered too.
+            assert!(geocoder.geocode(&missing).await.unwrap().is_none());
+        }
+        assert_eq!(geocoder.inner.queries().len(), 2);
+
+        let geocoder = geocoder.refresh(true);
+        geocoder.geocode(&query).await.unwrap();
+        assert_eq!(geocoder.inner.queries().len(), 3);
+    }
+
+    #[test]
+    fn saves_and_reopens() {
+        let path =
+            std::env::temp_dir().join(format!("geocode-cache-test-{}.json", std::process::id()));
+        let mut cache = GeocodeCache::open(&path).unwrap();
+        assert!(cache.is_empty());
+        cache.insert(BIENVILLE, "nominatim", Some(&hit(unix_timestamp())));
+        cache.insert("1 Nowhere St", "offline", None);
+        cache.save().unwrap();
+
+        let reopened = GeocodeCache::open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(reopened.len(), 2);
+        let entry = reopened.get("nominatim", BIENVILLE).unwrap();
+        assert_eq!(entry.hit(), Some(hit(entry.timestamp)));
+        assert_eq!(reopened.get("offline", "1 nowhere st").unwrap().hit(), None);
+    }
+}
@@ -0,0 +1,495 @@
+use super::{normalize_street, parse_street_line, unix_timestamp, GeocodingError};
+use csv::ReaderBuilder;
+use data::geo::{from_local_plane, haversine_distance_m, to_local_plane, GeocodeResult, MatchType};
+use data::inventory::TileInventory;
+use data::streets::modern_street_name;
+use serde::Deserialize;
+use std::path::Path;
+use thiserror::Error;
+
+/// How far the corner is moved off the centerline intersection toward the building.
+pub const CORNER_OFFSET_M: f64 = 8.0;
+
+/// Corners further than this from the geocoded building are reported rather than used;
+/// at that distance the sign or the address is more likely wrong than the geocode.
+pub const MAX_SNAP_DISTANCE_M: f64 = 150.0;
+
+#[derive(Debug, Deserialize)]
+struct CenterlineRow {
+    street: String,
+    latitude: f64,
+    longitude: f64,
+}
+
+/// A street centerline as `(latitude, longitude)` vertices.
+#[derive(Debug, Clone)]
+pub struct Street {
+    pub name: String,
+    pub points: Vec<(f64, f64)>,
+}
+
+/// Street centerlines read from a `street,latitude,longitude` CSV with one row per vertex,
+/// in order along each street, such as an OpenStreetMap extract. Corners are only as good
+/// as these lines, so use surveyed geometry rather than lines fitted to building geocodes.
+#[derive(Debug, Clone)]
+pub struct StreetNetwork {
+    streets: Vec<Street>,
+}
+
+impl StreetNetwork {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, GeocodingError> {
+        let file = std::fs::File::open(path)
+            .map_err(|err| GeocodingError::Centerlines(csv::Error::from(err)))?;
+        Self::from_reader(file)
+    }
+
+    fn from_reader<R: std::io::Read>(reader: R) -> Result<Self, GeocodingError> {
+        let mut streets: Vec<Street> = Vec::new();
+        for row in ReaderBuilder::new().from_reader(reader).deserialize() {
+            let row: CenterlineRow = row.map_err(GeocodingError::Centerlines)?;
+            let point = (row.latitude, row.longitude);
+            match streets.last_mut() {
+                Some(street) if street.name == row.street => street.points.push(point),
+                _ => streets.push(Street {
+                    name: row.street,
+                    points: vec![point],
+                }),
+            }
+        }
+        Ok(Self { streets })
+    }
+
+    /// Finds a street by name; "St Ann St." finds "Saint Ann Street".
+    pub fn street(&self, name: &str) -> Option<&Street> {
+        let key = normalize_street(name);
+        self.streets
+            .iter()
+            .find(|street| normalize_street(&street.name) == key)
+    }
+
+    /// Where two streets cross; empty if they don't.
+    pub fn intersections(&self, a: &Street, b: &Street) -> Vec<(f64, f64)> {
+        let Some(&origin) = a.points.first() else {
+            return Vec::new();
+        };
+        let plane = |point| to_local_plane(origin, point);
+
+        let mut found: Vec<(f64, f64)> = Vec::new();
+        for p in a.points.windows(2) {
+            for q in b.points.windows(2) {
+                let Some(crossing) =
+                    segment_intersection(plane(p[0]), plane(p[1]), plane(q[0]), plane(q[1]))
+                else {
+                    continue;
+                };
+                // Streets meeting at a shared vertex show up once per adjoining segment.
+                if !found.iter().any(|&seen| distance(seen, crossing) < 1.0) {
+                    found.push(crossing);
+                }
+            }
+        }
+        found
+            .into_iter()
+            .map(|point| from_local_plane(origin, point))
+            .collect()
+    }
+
+    /// Every corner along `street`, with the name of the street crossing there.
+    pub fn corners<'a>(&'a self, street: &Street) -> Vec<((f64, f64), &'a str)> {
+        self.streets
+            .iter()
+            .filter(|other| other.name != street.name)
+            .flat_map(|other| {
+                self.intersections(street, other)
+                    .into_iter()
+                    .map(move |point| (point, other.name.as_str()))
+            })
+            .collect()
+    }
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - b.0).hypot(a.1 - b.1)
+}
+
+fn segment_intersection(
+    p1: (f64, f64),
+    p2: (f64, f64),
+    q1: (f64, f64),
+    q2: (f64, f64),
+) -> Option<(f64, f64)> {
+    // Generous enough that streets meeting exactly at a vertex still count.
+    const EPSILON: f64 = 1e-6;
+    let cross = |a: (f64, f64), b: (f64, f64)| a.0 * b.1 - a.1 * b.0;
+    let r = (p2.0 - p1.0, p2.1 - p1.1);
+    let s = (q2.0 - q1.0, q2.1 - q1.1);
+    let denominator = cross(r, s);
+    if denominator.abs() < EPSILON {
+        return None;
+    }
+    let offset = (q1.0 - p1.0, q1.1 - p1.1);
+    let t = cross(offset, s) / denominator;
+    let u = cross(offset, r) / denominator;
+    let within = |value: f64| (-EPSILON..=1.0 + EPSILON).contains(&value);
+    (within(t) && within(u)).then_some((p1.0 + t * r.0, p1.1 + t * r.1))
+}
+
+#[derive(Error, Debug)]
+pub enum CornerError {
+    #[error("No modern street known for sign {0:?}")]
+    UnknownSign(String),
+    #[error("{0:?} is not in the street centerlines")]
+    UnknownStreet(String),
+    #[error("{0} and {1} don't meet")]
+    NoIntersection(String, String),
+    #[error("The address doesn't cross {0}; a geocoded coordinate is needed to pick a corner")]
+    NeedsCoordinate(String),
+    #[error("{street} & {cross_street} is {distance_m:.0} m from the geocoded building")]
+    TooFar {
+        street: String,
+        cross_street: String,
+        distance_m: f64,
+    },
+}
+
+/// Where a sign was placed and why.
+#[derive(Debug, Clone)]
+pub struct CornerMatch {
+    pub id: u32,
+    /// The modern name of the street on the sign.
+    pub sign_street: String,
+    pub cross_street: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Meters from the coordinate the record had before.
+    pub moved_m: Option<f64>,
+}
+
+/// Places signs at the corner of the street they name and the building's street.
+pub struct IntersectionGeocoder {
+    network: StreetNetwork,
+    corner_offset_m: f64,
+    max_snap_distance_m: f64,
+}
+
+impl IntersectionGeocoder {
+    pub fn new(network: StreetNetwork) -> Self {
+        Self {
+            network,
+            corner_offset_m: CORNER_OFFSET_M,
+            max_snap_distance_m: MAX_SNAP_DISTANCE_M,
+        }
+    }
+
+    /// Overrides how far the corner is pulled off the centerlines toward the building.
+    pub fn with_corner_offset(mut self, meters: f64) -> Self {
+        self.corner_offset_m = meters;
+        self
+    }
+
+    /// Overrides how far from the geocoded building a corner may be.
+    pub fn with_max_snap_distance(mut self, meters: f64) -> Self {
+        self.max_snap_distance_m = meters;
+        self
+    }
+
+    /// The corner for one record.
+    ///
+    /// The sign's street is crossed with the street of `street_address`. When the address is
+    /// on the sign's own street (or one parallel to it), the corner along the sign's street
+    /// nearest the record's geocoded coordinate is used instead. Either way the point is then
+    /// moved toward the building so it lands on the building's corner of the intersection.
+    pub fn locate(&self, item: &TileInventory) -> Result<CornerMatch, CornerError> {
+        let sign_name = modern_street_name(&item.street_sign)
+            .ok_or_else(|| CornerError::UnknownSign(item.street_sign.clone()))?;
+        let sign_street = self
+            .network
+            .street(sign_name)
+            .ok_or_else(|| CornerError::UnknownStreet(sign_name.to_string()))?;
+        let building = item.latitude.zip(item.longitude);
+
+        let address_street = parse_street_line(&item.street_address).street;
+        let mut candidates: Vec<((f64, f64), &str)> = match self.network.street(&address_street) {
+            Some(cross) if cross.name != sign_street.name => self
+                .network
+                .intersections(sign_street, cross)
+                .into_iter()
+                .map(|point| (point, cross.name.as_str()))
+                .collect(),
+            _ => Vec::new(),
+        };
+        if candidates.is_empty() {
+            if building.is_none() {
+                return Err(CornerError::NeedsCoordinate(sign_street.name.clone()));
+            }
+            candidates = self.network.corners(sign_street);
+        }
+
+        let (intersection, cross_street) = match building {
+            Some(building) => candidates.into_iter().min_by(|a, b| {
+                haversine_distance_m(a.0, building).total_cmp(&haversine_distance_m(b.0, building))
+            }),
+            None => candidates.into_iter().next(),
+        }
+        .ok_or_else(|| {
+            CornerError::NoIntersection(sign_street.name.clone(), address_street.clone())
+        })?;
+
+        let corner = match building {
+            Some(building) => {
+                let distance_m = haversine_distance_m(intersection, building);
+                if distance_m > self.max_snap_distance_m {
+                    return Err(CornerError::TooFar {
+                        street: sign_street.name.clone(),
+                        cross_street: cross_street.to_string(),
+                        distance_m,
+                    });
+                }
+                toward(intersection, building, self.corner_offset_m)
+            }
+            None => intersection,
+        };
+        Ok(CornerMatch {
+            id: item.id,
+            sign_street: sign_street.name.clone(),
+            cross_street: cross_street.to_string(),
+            latitude: corner.0,
+            longitude: corner.1,
+            moved_m: building.map(|building| haversine_distance_m(building, corner)),
+        })
+    }
+}
+
+/// `from` moved up to `meters` toward `to`, stopping at `to`.
+fn toward(from: (f64, f64), to: (f64, f64), meters: f64) -> (f64, f64) {
+    let target = to_local_plane(from, to);
+    let length = target.0.hypot(target.1);
+    if length <= meters {
+        return to;
+    }
+    let scale = meters / length;
+    from_local_plane(from, (target.0 * scale, target.1 * scale))
+}
+
+#[derive(Debug, Default)]
+pub struct CornerReport {
+    pub located: Vec<CornerMatch>,
+    pub unresolved: Vec<(u32, CornerError)>,
+}
+
+/// Moves every record that can be placed to its sign's corner, recording the intersection
+/// as the coordinate's provenance. The corner is only as good as the centerlines, so it is
+/// recorded with a street-level confidence. Records that can't be placed keep their
+/// coordinates.
+pub fn snap_to_corners(
+    geocoder: &IntersectionGeocoder,
+    inventory: &mut [TileInventory],
+) -> CornerReport {
+    let mut report = CornerReport::default();
+    for item in inventory.iter_mut() {
+        match geocoder.locate(item) {
+            Ok(corner) => {
+                item.latitude = Some(corner.latitude);
+                item.longitude = Some(corner.longitude);
+                item.set_geocode_result(Some(GeocodeResult {
+                    provider: "intersection".to_string(),
+                    display_name: Some(format!("{} & {}", corner.sign_street, corner.cross_street)),
+                    match_type: MatchType::Intersection,
+                    confidence: 0.6,
+                    timestamp: unix_timestamp(),
+                }));
+                report.located.push(corner);
+            }
+            Err(err) => report.unresolved.push((item.id, err)),
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bourbon Street running north across Bienville and Conti Streets, with a vertex where
+    /// it meets Bienville.
+    const NETWORK: &str = "street,latitude,longitude\n\
+        Bourbon Street,29.9540,-90.0680\n\
+        Bourbon Street,29.9560,-90.0680\n\
+        Bourbon Street,29.9580,-90.0680\n\
+        Bienville Street,29.9560,-90.0700\n\
+        Bienville Street,29.9560,-90.0660\n\
+        Conti Street,29.9570,-90.0700\n\
+        Conti Street,29.9570,-90.0660\n";
+
+    const BOURBON_AND_BIENVILLE: (f64, f64) = (29.9560, -90.0680);
+    const BOURBON_AND_CONTI: (f64, f64) = (29.9570, -90.0680);
+
+    fn geocoder() -> IntersectionGeocoder {
+        IntersectionGeocoder::new(StreetNetwork::from_reader(NETWORK.as_bytes()).unwrap())
+    }
+
+    fn record(sign: &str, address: &str, coordinate: Option<(f64, f64)>) -> TileInventory {
+        TileInventory {
+            latitude: coordinate.map(|coordinate| coordinate.0),
+            longitude: coordinate.map(|coordinate| coordinate.1),
+            ..TileInventory::new(1, sign, address)
+        }
+    }
+
+    fn assert_near(actual: (f64, f64), expected: (f64, f64), meters: f64) {
+        let distance = haversine_distance_m(actual, expected);
+        assert!(
+            distance < meters,
+            "{:?} is {:.2} m from {:?}",
+            actual,
+            distance,
+            expected
+        );
+    }
+
+    #[test]
+    fn segments_cross_within_both_ends() {
+        let crossing = segment_intersection((0.0, 0.0), (2.0, 2.0), (0.0, 2.0), (2.0, 0.0));
+        assert_eq!(crossing, Some((1.0, 1.0)));
+        // Meeting exactly at an end still counts.
+        let touching = segment_intersection((0.0, 0.0), (1.0, 0.0), (1.0, -1.0), (1.0, 1.0));
+        assert_eq!(touching, Some((1.0, 0.0)));
+        // Parallel, and short of each other.
+        assert_eq!(
+            segment_intersection((0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)),
+            None
+        );
+        assert_eq!(
+            segment_intersection((0.0, 0.0), (1.0, 0.0), (2.0, -1.0), (2.0, 1.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn streets_meeting_at_a_vertex_cross_once() {
+        let network = StreetNetwork::from_reader(NETWORK.as_bytes()).unwrap();
+        let bourbon = network.street("Bourbon St").unwrap();
+        let bienville = network.street("bienville street").unwrap();
+        let crossings = network.intersections(bourbon, bienville);
+        assert_eq!(crossings.len(), 1);
+        assert_near(crossings[0], BOURBON_AND_BIENVILLE, 0.01);
+        let conti = network.street("Conti St").unwrap();
+        assert!(network.intersections(bienville, conti).is_empty());
+    }
+
+    #[test]
+    fn toward_moves_the_given_distance_and_stops_at_the_target() {
+        let to = (29.9563, -90.0677);
+        let moved = toward(BOURBON_AND_BIENVILLE, to, 8.0);
+        assert!((haversine_distance_m(BOURBON_AND_BIENVILLE, moved) - 8.0).abs() < 0.05);
+        let remaining = haversine_distance_m(BOURBON_AND_BIENVILLE, to) - 8.0;
+        assert!((haversine_distance_m(moved, to) - remaining).abs() < 0.05);
+        assert_eq!(toward(BOURBON_AND_BIENVILLE, to, 1_000.0), to);
+    }
+
+    #[test]
+    fn crosses_the_sign_with_the_address_street() {
+        let corner = geocoder()
+            .locate(&record("Calle D'Bourbon", "813 Bienville St", None))
+            .unwrap();
+        assert_eq!(corner.sign_street, "Bourbon Street");
+        assert_eq!(corner.cross_street, "Bienville Street");
+        assert_near(
+            (corner.latitude, corner.longitude),
+            BOURBON_AND_BIENVILLE,
+            0.01,
+        );
+        assert_eq!(corner.moved_m, None);
+    }
+
+    #[test]
+    fn moves_the_corner_toward_the_building() {
+        let building = (29.9562, -90.0678);
+        let corner = geocoder()
+            .locate(&record(
+                "Calle D'Bourbon",
+                "813 Bienville St",
+                Some(building),
+            ))
+            .unwrap();
+        let point = (corner.latitude, corner.longitude);
+        assert!(
+            (haversine_distance_m(BOURBON_AND_BIENVILLE, point) - CORNER_OFFSET_M).abs() < 0.05
+        );
+        let moved = corner.moved_m.unwrap();
+        assert!((moved - haversine_distance_m(building, point)).abs() < 1e-9);
+        assert!(moved < haversine_distance_m(building, BOURBON_AND_BIENVILLE));
+    }
+
+    #[test]
+    fn an_address_on_the_signs_street_takes_the_nearest_corner() {
+        let geocoder = geocoder().with_corner_offset(0.0);
+        let item = record("Calle D'Bourbon", "720 Bourbon St", None);
+        assert!(matches!(
+            geocoder.locate(&item),
+            Err(CornerError::NeedsCoordinate(street)) if street == "Bourbon Street"
+        ));
+        let item = record(
+            "Calle D'Bourbon",
+            "720 Bourbon St",
+            Some((29.9568, -90.0681)),
+        );
+        let corner = geocoder.locate(&item).unwrap();
+        assert_eq!(corner.cross_street, "Conti Street");
+        assert_near((corner.latitude, corner.longitude), BOURBON_AND_CONTI, 0.01);
+    }
+
+    #[test]
+    fn reports_what_it_cant_place() {
+        let geocoder = geocoder();
+        assert!(matches!(
+            geocoder.locate(&record("Calle Imaginaria", "1 Bienville St", None)),
+            Err(CornerError::UnknownSign(_))
+        ));
+        assert!(matches!(
+            geocoder.locate(&record("Calle Real", "1 Bienville St", None)),
+            Err(CornerError::UnknownStreet(street)) if street == "Royal Street"
+        ));
+        // About 330 m south of the corner.
+        let far = record(
+            "Calle D'Bourbon",
+            "813 Bienville St",
+            Some((29.9530, -90.0680)),
+        );
+        assert!(matches!(
+            geocoder.locate(&far),
+            Err(CornerError::TooFar { .. })
+        ));
+        let wide = geocoder.with_max_snap_distance(500.0);
+        assert!(wide.locate(&far).is_ok());
+    }
+
+    #[test]
+    fn snapping_records_the_corner_and_leaves_the_rest() {
+        let building = (29.9562, -90.0678);
+        let mut inventory = vec![
+            record("Calle D'Bourbon", "813 Bienville St", Some(building)),
+            TileInventory {
+                id: 2,
+                ..record("Calle Real", "301 Royal St", Some(building))
+            },
+        ];
+        let report = snap_to_corners(&geocoder(), &mut inventory);
+        assert_eq!(report.located.len(), 1);
+        assert_eq!(report.unresolved.len(), 1);
+        assert_eq!(report.unresolved[0].0, 2);
+
+        let result = inventory[0].geocode_result().unwrap();
+        assert_eq!(result.match_type, MatchType::Intersection);
+        assert_eq!(
+            result.display_name.as_deref(),
+            Some("Bourbon Street & Bienville Street")
+        );
+        assert_ne!(inventory[0].latitude, Some(building.0));
+        assert_eq!(
+            inventory[1].latitude.zip(inventory[1].longitude),
+            Some(building)
+        );
+        assert_eq!(inventory[1].geocode_result(), None);
+    }
+}
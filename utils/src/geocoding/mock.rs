@@ -0,0 +1,184 @@
+use super::{
+    parse_street_line, unix_timestamp, GeocodeHit, Geocoder, GeocodingError, ReverseHit,
+    StructuredAddress,
+};
+use data::geo::{haversine_distance_m, GeocodeResult, MatchType};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Deterministic geocoder for tests: answers from a fixed table and records every query.
+/// Reverse lookups return the nearest address in the table.
+#[derive(Default)]
+pub struct MockGeocoder {
+    responses: HashMap<String, (f64, f64)>,
+    failures: HashMap<String, u16>,
+    queries: Mutex<Vec<String>>,
+}
+
+impl MockGeocoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answers `address` (matched exactly against the query's one-line form, e.g.
+    /// "813 Bienville Street, New Orleans, Louisiana 70112") with the given coordinate.
+    pub fn with_response(
+        mut self,
+        address: impl Into<String>,
+        latitude: f64,
+        longitude: f64,
+    ) -> Self {
+        self.responses.insert(address.into(), (latitude, longitude));
+        self
+    }
+
+    /// Fails every lookup of `address` with the given HTTP status. Reverse lookups are
+    /// failed by their query, e.g. "reverse 29.9557,-90.0686".
+    pub fn with_failure(mut self, address: impl Into<String>, status: u16) -> Self {
+        self.failures.insert(address.into(), status);
+        self
+    }
+
+    /// Every address queried so far, in order.
+    pub fn queries(&self) -> Vec<String> {
+        self.queries.lock().expect("mock geocoder poisoned").clone()
+    }
+}
+
+impl Geocoder for MockGeocoder {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    async fn geocode(
+        &self,
+        address: &StructuredAddress,
+    ) -> Result<Option<GeocodeHit>, GeocodingError> {
+        let address = address.to_string();
+        self.queries
+            .lock()
+            .expect("mock geocoder poisoned")
+            .push(address.clone());
+        if let Some(status) = self.failures.get(&address) {
+            return Err(GeocodingError::Status {
+                status: *status,
+                retry_after: None,
+            });
+        }
+        Ok(self
+            .responses
+            .get(&address)
+            .map(|&(latitude, longitude)| GeocodeHit {
+                latitude,
+                longitude,
+                result: GeocodeResult {
+                    provider: self.name().to_string(),
+                    display_name: Some(address.clone()),
+                    match_type: MatchType::House,
+                    confidence: 1.0,
+                    timestamp: unix_timestamp(),
+                },
+                raw: serde_json::Value::Null,
+            }))
+    }
+
+    async fn reverse(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<Option<ReverseHit>, GeocodingError> {
+        let query = format!("reverse {},{}", latitude, longitude);
+        self.queries
+            .lock()
+            .expect("mock geocoder poisoned")
+            .push(query.clone());
+        if let Some(status) = self.failures.get(&query) {
+            return Err(GeocodingError::Status {
+                status: *status,
+                retry_after: None,
+            });
+        }
+        let nearest = self.responses.iter().min_by(|(_, &a), (_, &b)| {
+            let from = |point| haversine_distance_m((latitude, longitude), point);
+            from(a).total_cmp(&from(b))
+        });
+        Ok(nearest.map(|(address, &(latitude, longitude))| {
+            let line = parse_street_line(address);
+            ReverseHit {
+                latitude,
+                longitude,
+                house_number: line.house_number,
+                street: Some(line.street),
+                display_name: address.clone(),
+                raw: serde_json::Value::Null,
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geocoding::AddressNormalizer;
+
+    const BIENVILLE: &str = "813 Bienville Street, New Orleans, Louisiana 70112";
+    const BARRACKS: &str = "841 Barracks Street, New Orleans, Louisiana 70116";
+
+    fn geocoder() -> MockGeocoder {
+        MockGeocoder::new()
+            .with_response(BIENVILLE, 29.9557409, -90.0686785)
+            .with_response(BARRACKS, 29.9640623, -90.06208689)
+            .with_failure("1 Nowhere Street, New Orleans, Louisiana", 503)
+    }
+
+    #[tokio::test]
+    async fn answers_from_its_table_and_records_queries() {
+        let geocoder = geocoder();
+        let normalizer = AddressNormalizer::default();
+
+        let hit = geocoder
+            .geocode(&normalizer.normalize("813 Bienville St, New Orleans, LA 70112"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((hit.latitude, hit.longitude), (29.9557409, -90.0686785));
+        assert_eq!(hit.result.provider, "mock");
+        assert_eq!(hit.result.display_name.as_deref(), Some(BIENVILLE));
+
+        let unknown = normalizer.normalize("901 Barracks St, New Orleans, LA 70116");
+        assert!(geocoder.geocode(&unknown).await.unwrap().is_none());
+        assert_eq!(
+            geocoder.queries(),
+            [
+                BIENVILLE,
+                "901 Barracks Street, New Orleans, Louisiana 70116"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn fails_with_the_configured_status() {
+        let address = AddressNormalizer::default().normalize("1 Nowhere St");
+        match geocoder().geocode(&address).await {
+            Err(GeocodingError::Status {
+                status: 503,
+                retry_after: None,
+            }) => {}
+            other => panic!("expected a 503, got {:?}", other.map(|hit| hit.is_some())),
+        }
+    }
+
+    #[tokio::test]
+    async fn reverse_finds_the_nearest_address() {
+        let geocoder = geocoder();
+        let hit = geocoder.reverse(29.9641, -90.0621).await.unwrap().unwrap();
+        assert_eq!(hit.display_name, BARRACKS);
+        assert_eq!(hit.house_number.as_deref(), Some("841"));
+        assert_eq!(geocoder.queries(), ["reverse 29.9641,-90.0621"]);
+        assert!(MockGeocoder::new()
+            .reverse(0.0, 0.0)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
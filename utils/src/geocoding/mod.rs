@@ -0,0 +1,140 @@
+mod address;
+mod batch;
+mod cache;
+mod intersection;
+mod mock;
+mod nominatim;
+mod offline;
+mod verify;
+
+pub use address::{
+    expand_street_line, normalize_street, AddressNormalizer, Region, StructuredAddress,
+};
+pub use batch::{
+    geocode_inventory, plan_queries, read_checkpoint, remove_checkpoint, BatchOptions, BatchReport,
+    Outcome, PlannedQuery, RecordOutcome, RetryPolicy, Selection,
+};
+pub use cache::{normalize_address, CacheEntry, CachedGeocoder, GeocodeCache};
+pub use data::streets::{modern_street_name, HISTORICAL_STREETS};
+pub use intersection::{
+    snap_to_corners, CornerError, CornerMatch, CornerReport, IntersectionGeocoder, Street,
+    StreetNetwork, CORNER_OFFSET_M, MAX_SNAP_DISTANCE_M,
+};
+pub use mock::MockGeocoder;
+pub use nominatim::NominatimGeocoder;
+pub use offline::{OfflineGeocoder, BUNDLED_GAZETTEER, MAX_REVERSE_DISTANCE_M};
+pub use verify::{
+    parse_street_line, verify_coordinates, write_review_csv, ReviewRow, StreetLine,
+    VerificationStatus, VerifyOptions,
+};
+
+use data::geo::GeocodeResult;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum GeocodingError {
+    #[error("Request error: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("Server answered with HTTP {status}")]
+    Status {
+        status: u16,
+        /// How long the server asked to be left alone, from its `Retry-After` header.
+        retry_after: Option<Duration>,
+    },
+    #[error("Failed to parse latitude or longitude")]
+    ParseCoordinate,
+    #[error("Failed to load gazetteer: {0}")]
+    Gazetteer(#[from] csv::Error),
+    #[error("Failed to load street centerlines: {0}")]
+    Centerlines(csv::Error),
+    #[error("Failed to access geocoding cache {path:?}: {source}")]
+    CacheIo {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Malformed geocoding cache {path:?}: {source}")]
+    CacheFormat {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error("Failed to write checkpoint {path:?}: {source}")]
+    Checkpoint {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Failed to write review CSV {path:?}: {source}")]
+    Review { path: PathBuf, source: csv::Error },
+}
+
+impl GeocodingError {
+    /// Whether retrying the same request later might succeed: rate limiting, server errors,
+    /// timeouts and dropped connections. Everything else would fail the same way again.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            GeocodingError::Status { status, .. } => *status == 429 || (500..600).contains(status),
+            GeocodingError::Request(err) => err.is_timeout() || err.is_connect(),
+            _ => false,
+        }
+    }
+
+    /// The wait the server asked for before trying again, if it named one.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            GeocodingError::Status { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+/// A backend's match for one address.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GeocodeHit {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub result: GeocodeResult,
+    /// The provider's answer as received, so cached results keep everything it said.
+    pub raw: serde_json::Value,
+}
+
+/// Seconds since the Unix epoch, for `GeocodeResult::timestamp`, cache entries and overrides.
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// A backend's idea of what is at a coordinate.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReverseHit {
+    /// Where the matched feature is, which may be some way from the point asked about.
+    pub latitude: f64,
+    pub longitude: f64,
+    pub house_number: Option<String>,
+    pub street: Option<String>,
+    pub display_name: String,
+    pub raw: serde_json::Value,
+}
+
+/// A source of coordinates for street addresses.
+pub trait Geocoder {
+    /// Short provider name, e.g. "nominatim".
+    fn name(&self) -> &str;
+
+    /// Looks up `address`, returning `None` when the backend has no match.
+    fn geocode(
+        &self,
+        address: &StructuredAddress,
+    ) -> impl Future<Output = Result<Option<GeocodeHit>, GeocodingError>> + Send;
+
+    /// Finds the address nearest to a coordinate, returning `None` when there is nothing
+    /// nearby.
+    fn reverse(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> impl Future<Output = Result<Option<ReverseHit>, GeocodingError>> + Send;
+}
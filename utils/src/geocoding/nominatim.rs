@@ -0,0 +1,292 @@
+use super::{unix_timestamp, GeocodeHit, Geocoder, GeocodingError, ReverseHit, StructuredAddress};
+use chrono::{DateTime, Utc};
+use data::geo::{GeocodeResult, MatchType};
+use reqwest::header::RETRY_AFTER;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::Value;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+
+#[derive(Debug, Deserialize)]
+struct NominatimResponse {
+    lat: String,
+    lon: String,
+    display_name: Option<String>,
+    class: Option<String>,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    addresstype: Option<String>,
+}
+
+impl NominatimResponse {
+    /// What the match landed on, and a confidence for it: Nominatim reports none of its own.
+    fn match_type(&self) -> (MatchType, f64) {
+        let class = self.class.as_deref().unwrap_or_default();
+        let kind = self.kind.as_deref().unwrap_or_default();
+        let addresstype = self.addresstype.as_deref().unwrap_or_default();
+        if class == "building" || kind == "house" || matches!(addresstype, "house" | "building") {
+            (MatchType::House, 0.9)
+        } else if class == "highway" || addresstype == "road" {
+            (MatchType::Street, 0.4)
+        } else {
+            (MatchType::Landmark, 0.6)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct NominatimReverseResponse {
+    lat: String,
+    lon: String,
+    display_name: String,
+    #[serde(default)]
+    address: NominatimAddress,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct NominatimAddress {
+    house_number: Option<String>,
+    road: Option<String>,
+    pedestrian: Option<String>,
+}
+
+/// Geocodes through a Nominatim server, by default the public OpenStreetMap instance.
+pub struct NominatimGeocoder {
+    client: Client,
+    base_url: String,
+    min_interval: Duration,
+    last_request: Mutex<Option<Instant>>,
+}
+
+impl NominatimGeocoder {
+    pub const DEFAULT_BASE_URL: &'static str = "https://nominatim.openstreetmap.org";
+
+    pub fn new() -> Self {
+        Self::with_base_url(Self::DEFAULT_BASE_URL)
+    }
+
+    /// Points the client at another server, such as a local stand-in for testing.
+    pub fn with_base_url(base_url: impl Into<String>) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            // Respect Nominatim's usage policy (max 1 request per second)
+            min_interval: Duration::from_secs(1),
+            last_request: Mutex::new(None),
+        }
+    }
+
+    /// Overrides the minimum spacing between requests.
+    pub fn with_min_interval(mut self, min_interval: Duration) -> Self {
+        self.min_interval = min_interval;
+        self
+    }
+
+    async fn throttle(&self) {
+        let mut last_request = self.last_request.lock().await;
+        if let Some(last) = *last_request {
+            let elapsed = last.elapsed();
+            if elapsed < self.min_interval {
+                sleep(self.min_interval - elapsed).await;
+            }
+        }
+        *last_request = Some(Instant::now());
+    }
+
+    async fn get(&self, url: &str) -> Result<Value, GeocodingError> {
+        self.throttle().await;
+        log::info!("{}", url);
+
+        let request = self
+            .client
+            .get(url)
+            .header("User-Agent", "TileInventoryApp/1.0");
+
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            let retry_after = response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| parse_retry_after(value, SystemTime::now()));
+            return Err(GeocodingError::Status {
+                status: response.status().as_u16(),
+                retry_after,
+            });
+        }
+        Ok(response.json().await?)
+    }
+
+    /// First match for a `/search` query, if any.
+    async fn search(&self, params: &[(&str, &str)]) -> Result<Option<Value>, GeocodingError> {
+        let query: Vec<String> = params
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, urlencoding::encode(value)))
+            .collect();
+        let url = format!(
+            "{}/search?format=json&limit=1&{}",
+            self.base_url,
+            query.join("&")
+        );
+        let response: Vec<Value> = serde_json::from_value(self.get(&url).await?)
+            .map_err(|_| GeocodingError::ParseCoordinate)?;
+        Ok(response.into_iter().next())
+    }
+}
+
+/// Nominatim's structured search parameters for `address`.
+fn structured_params(address: &StructuredAddress) -> Vec<(&'static str, &str)> {
+    let mut params = Vec::new();
+    if let Some(street) = &address.street {
+        params.push(("street", street.as_str()));
+    }
+    if let Some(amenity) = &address.amenity {
+        params.push(("amenity", amenity.as_str()));
+    }
+    params.push(("city", address.city.as_str()));
+    params.push(("state", address.state.as_str()));
+    if let Some(postalcode) = &address.postalcode {
+        params.push(("postalcode", postalcode.as_str()));
+    }
+    if let Some(country_code) = &address.country_code {
+        params.push(("countrycodes", country_code.as_str()));
+    }
+    params
+}
+
+fn parse_coordinate(value: &str) -> Result<f64, GeocodingError> {
+    value.parse().map_err(|_| GeocodingError::ParseCoordinate)
+}
+
+impl Default for NominatimGeocoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Geocoder for NominatimGeocoder {
+    fn name(&self) -> &str {
+        "nominatim"
+    }
+
+    /// Tries a structured search first and falls back to the one-line form, which copes
+    /// better with landmarks and odd street names.
+    async fn geocode(
+        &self,
+        address: &StructuredAddress,
+    ) -> Result<Option<GeocodeHit>, GeocodingError> {
+        let mut raw = self.search(&structured_params(address)).await?;
+        if raw.is_none() {
+            let line = address.to_string();
+            let mut params = vec![("q", line.as_str())];
+            if let Some(country_code) = &address.country_code {
+                params.push(("countrycodes", country_code.as_str()));
+            }
+            raw = self.search(&params).await?;
+        }
+        let Some(raw) = raw else {
+            return Ok(None);
+        };
+        let result =
+            NominatimResponse::deserialize(&raw).map_err(|_| GeocodingError::ParseCoordinate)?;
+        let (match_type, confidence) = result.match_type();
+        Ok(Some(GeocodeHit {
+            latitude: parse_coordinate(&result.lat)?,
+            longitude: parse_coordinate(&result.lon)?,
+            result: GeocodeResult {
+                provider: self.name().to_string(),
+                display_name: result.display_name,
+                match_type,
+                confidence,
+                timestamp: unix_timestamp(),
+            },
+            raw,
+        }))
+    }
+
+    async fn reverse(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<Option<ReverseHit>, GeocodingError> {
+        // Zoom 18 asks for the nearest building rather than the street or neighbourhood.
+        let url = format!(
+            "{}/reverse?format=json&lat={}&lon={}&zoom=18&addressdetails=1",
+            self.base_url, latitude, longitude
+        );
+        let raw = self.get(&url).await?;
+        // Nominatim answers `{"error": "Unable to geocode"}` when nothing is nearby.
+        if raw.get("error").is_some() {
+            return Ok(None);
+        }
+        let result = NominatimReverseResponse::deserialize(&raw)
+            .map_err(|_| GeocodingError::ParseCoordinate)?;
+        Ok(Some(ReverseHit {
+            latitude: parse_coordinate(&result.lat)?,
+            longitude: parse_coordinate(&result.lon)?,
+            house_number: result.address.house_number,
+            street: result.address.road.or(result.address.pedestrian),
+            display_name: result.display_name,
+            raw,
+        }))
+    }
+}
+
+/// Reads a `Retry-After` header: either a number of seconds, or an HTTP date (e.g.
+/// "Wed, 21 Oct 2015 07:28:00 GMT") that is turned into the time left until it from `now`.
+fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = DateTime::parse_from_rfc2822(value).ok()?;
+    // A date already past means "now".
+    Some(
+        (at.with_timezone(&Utc) - DateTime::<Utc>::from(now))
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn retry_after_in_seconds() {
+        let now = SystemTime::now();
+        assert_eq!(
+            parse_retry_after("120", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(parse_retry_after(" 0 ", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon", now), None);
+        assert_eq!(parse_retry_after("-5", now), None);
+    }
+
+    #[test]
+    fn retry_after_as_a_date() {
+        // 2015-10-21 07:28:00 UTC
+        let date = UNIX_EPOCH + Duration::from_secs(1_445_412_480);
+        let header = "Wed, 21 Oct 2015 07:28:00 GMT";
+        assert_eq!(
+            parse_retry_after(header, date - Duration::from_secs(90)),
+            Some(Duration::from_secs(90))
+        );
+        assert_eq!(
+            parse_retry_after(header, date + Duration::from_secs(90)),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 PST", date),
+            Some(Duration::from_secs(8 * 3_600))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Foo 2015 07:28:00 GMT", date),
+            None
+        );
+    }
+}
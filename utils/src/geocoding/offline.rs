@@ -0,0 +1,174 @@
+use super::{
+    expand_street_line, parse_street_line, unix_timestamp, GeocodeHit, Geocoder, GeocodingError,
+    ReverseHit, StructuredAddress,
+};
+use csv::ReaderBuilder;
+use data::geo::{haversine_distance_m, GeocodeResult, MatchType};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Address-to-coordinate table for the surveyed buildings, so geocoding works without a
+/// network connection.
+pub static BUNDLED_GAZETTEER: &[u8] = include_bytes!("../../data/gazetteer.csv");
+
+/// How far from the asked-about point the nearest gazetteer address may be for a reverse
+/// lookup to answer with it; about a French Quarter block.
+pub const MAX_REVERSE_DISTANCE_M: f64 = 100.0;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GazetteerRow {
+    address: String,
+    latitude: f64,
+    longitude: f64,
+}
+
+/// Looks addresses up in a local `address,latitude,longitude` table.
+///
+/// Only the street line of an address is compared (case, spacing and abbreviations ignored),
+/// so "813 Bienville St, New Orleans, LA 70112" and "813 bienville street" match.
+/// Reverse lookups answer with the nearest address in the table, or nothing when even that is
+/// further than `max_reverse_distance_m`.
+pub struct OfflineGeocoder {
+    entries: HashMap<String, GazetteerRow>,
+    max_reverse_distance_m: f64,
+}
+
+impl OfflineGeocoder {
+    pub fn bundled() -> Result<Self, GeocodingError> {
+        Self::from_reader(BUNDLED_GAZETTEER)
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, GeocodingError> {
+        let file = std::fs::File::open(path).map_err(csv::Error::from)?;
+        Self::from_reader(file)
+    }
+
+    fn from_reader<R: std::io::Read>(reader: R) -> Result<Self, GeocodingError> {
+        let mut entries = HashMap::new();
+        for row in ReaderBuilder::new().from_reader(reader).deserialize() {
+            let row: GazetteerRow = row?;
+            entries.entry(street_key(&row.address)).or_insert(row);
+        }
+        Ok(Self {
+            entries,
+            max_reverse_distance_m: MAX_REVERSE_DISTANCE_M,
+        })
+    }
+
+    pub fn with_max_reverse_distance(mut self, meters: f64) -> Self {
+        self.max_reverse_distance_m = meters;
+        self
+    }
+}
+
+fn street_key(address: &str) -> String {
+    expand_street_line(address.split(',').next().unwrap_or_default()).to_lowercase()
+}
+
+impl Geocoder for OfflineGeocoder {
+    fn name(&self) -> &str {
+        "offline"
+    }
+
+    async fn geocode(
+        &self,
+        address: &StructuredAddress,
+    ) -> Result<Option<GeocodeHit>, GeocodingError> {
+        Ok(self
+            .entries
+            .get(&street_key(address.first_line()))
+            .map(|row| GeocodeHit {
+                latitude: row.latitude,
+                longitude: row.longitude,
+                result: GeocodeResult {
+                    provider: self.name().to_string(),
+                    display_name: Some(row.address.clone()),
+                    // The table holds surveyed buildings, except for the odd landmark.
+                    match_type: match parse_street_line(&row.address).house_number {
+                        Some(_) => MatchType::House,
+                        None => MatchType::Landmark,
+                    },
+                    confidence: 0.9,
+                    timestamp: unix_timestamp(),
+                },
+                raw: serde_json::to_value(row).unwrap_or_default(),
+            }))
+    }
+
+    async fn reverse(
+        &self,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<Option<ReverseHit>, GeocodingError> {
+        let from = |row: &GazetteerRow| {
+            haversine_distance_m((latitude, longitude), (row.latitude, row.longitude))
+        };
+        let nearest = self
+            .entries
+            .values()
+            .min_by(|a, b| from(a).total_cmp(&from(b)))
+            .filter(|row| from(row) <= self.max_reverse_distance_m);
+        Ok(nearest.map(|row| {
+            let line = parse_street_line(&row.address);
+            ReverseHit {
+                latitude: row.latitude,
+                longitude: row.longitude,
+                house_number: line.house_number,
+                street: Some(line.street),
+                display_name: row.address.clone(),
+                raw: serde_json::to_value(row).unwrap_or_default(),
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geocoding::AddressNormalizer;
+
+    const TABLE: &str = "address,latitude,longitude\n\
+        \"841 Barracks St, New Orleans, LA 70116\",29.9640623,-90.06208689\n";
+
+    #[tokio::test]
+    async fn reverse_answers_only_within_the_maximum_distance() {
+        let geocoder = OfflineGeocoder::from_reader(TABLE.as_bytes()).unwrap();
+        let near = geocoder.reverse(29.9641, -90.0621).await.unwrap();
+        assert_eq!(near.unwrap().house_number.as_deref(), Some("841"));
+        // About a kilometre north, well past the default cap.
+        assert!(geocoder.reverse(29.9730, -90.0621).await.unwrap().is_none());
+        let wide = geocoder.with_max_reverse_distance(2_000.0);
+        assert!(wide.reverse(29.9730, -90.0621).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn the_bundled_table_matches_the_street_line() {
+        let geocoder = OfflineGeocoder::bundled().unwrap();
+        let normalizer = AddressNormalizer::default();
+        for address in [
+            "841 Barracks St, New Orleans, LA 70116",
+            "841 BARRACKS STREET",
+            "841  barracks st., New Orleans",
+        ] {
+            let hit = geocoder
+                .geocode(&normalizer.normalize(address))
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!((hit.latitude, hit.longitude), (29.9640623, -90.06208689));
+            assert_eq!(hit.result.provider, "offline");
+            assert_eq!(hit.result.match_type, MatchType::House);
+        }
+        let missing = normalizer.normalize("843 Barracks St, New Orleans, LA 70116");
+        assert!(geocoder.geocode(&missing).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn rows_without_a_house_number_are_landmarks() {
+        let geocoder = OfflineGeocoder::bundled().unwrap();
+        let address = AddressNormalizer::default().normalize("Jackson Square, New Orleans, LA");
+        let hit = geocoder.geocode(&address).await.unwrap().unwrap();
+        assert_eq!(hit.result.match_type, MatchType::Landmark);
+    }
+}
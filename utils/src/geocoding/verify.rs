@@ -0,0 +1,336 @@
+use super::batch::with_retry;
+use super::{normalize_street, Geocoder, GeocodingError, RetryPolicy};
+use csv::Writer;
+use data::geo::haversine_distance_m;
+use data::inventory::TileInventory;
+use serde::Serialize;
+use std::path::Path;
+
+/// The house number and street of an address's first line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreetLine {
+    pub house_number: Option<String>,
+    pub street: String,
+}
+
+/// Splits "813 Bienville St, New Orleans, LA 70112" into `813` and `Bienville St`. Landmarks
+/// such as "Jackson Square" have no house number.
+pub fn parse_street_line(address: &str) -> StreetLine {
+    let line = address.split(',').next().unwrap_or_default().trim();
+    match line.split_once(char::is_whitespace) {
+        Some((number, street)) if number.starts_with(|c: char| c.is_ascii_digit()) => StreetLine {
+            house_number: Some(number.to_string()),
+            street: street.trim().to_string(),
+        },
+        _ => StreetLine {
+            house_number: None,
+            street: line.to_string(),
+        },
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationStatus {
+    /// House number and street agree and the match is close by.
+    Match,
+    /// Same address, but the matched building is further away than the threshold.
+    TooFar,
+    HouseNumberMismatch,
+    StreetMismatch,
+    /// The backend found nothing at the coordinate.
+    NoResult,
+    MissingCoordinates,
+    /// The lookup failed; see `error`.
+    Failed,
+}
+
+/// One line of the review CSV.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReviewRow {
+    pub id: u32,
+    pub street_address: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub status: VerificationStatus,
+    pub reverse_house_number: Option<String>,
+    pub reverse_street: Option<String>,
+    pub reverse_display_name: Option<String>,
+    pub reverse_latitude: Option<f64>,
+    pub reverse_longitude: Option<f64>,
+    /// Meters from the stored coordinate to the feature the backend matched.
+    pub distance_m: Option<f64>,
+    pub error: Option<String>,
+}
+
+impl ReviewRow {
+    pub fn needs_review(&self) -> bool {
+        self.status != VerificationStatus::Match
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VerifyOptions {
+    pub retry: RetryPolicy,
+    /// Matches further than this from the stored coordinate are flagged `TooFar`.
+    pub max_distance_m: f64,
+}
+
+impl Default for VerifyOptions {
+    fn default() -> Self {
+        Self {
+            retry: RetryPolicy::default(),
+            max_distance_m: 25.0,
+        }
+    }
+}
+
+/// Reverse-geocodes every stored coordinate and checks the answer against the record's
+/// `street_address`. Returns one row per record, in inventory order.
+pub async fn verify_coordinates<G: Geocoder>(
+    geocoder: &G,
+    inventory: &[TileInventory],
+    options: &VerifyOptions,
+) -> Vec<ReviewRow> {
+    let mut rows = Vec::with_capacity(inventory.len());
+    for item in inventory {
+        let mut row = ReviewRow {
+            id: item.id,
+            street_address: item.street_address.clone(),
+            latitude: item.latitude,
+            longitude: item.longitude,
+            status: VerificationStatus::MissingCoordinates,
+            reverse_house_number: None,
+            reverse_street: None,
+            reverse_display_name: None,
+            reverse_latitude: None,
+            reverse_longitude: None,
+            distance_m: None,
+            error: None,
+        };
+        let (Some(latitude), Some(longitude)) = (item.latitude, item.longitude) else {
+            rows.push(row);
+            continue;
+        };
+
+        let label = format!("{},{}", latitude, longitude);
+        let (result, _) = with_retry(&options.retry, &label, || {
+            geocoder.reverse(latitude, longitude)
+        })
+        .await;
+        row.status = match result {
+            Ok(Some(hit)) => {
+                let distance =
+                    haversine_distance_m((latitude, longitude), (hit.latitude, hit.longitude));
+                let expected = parse_street_line(&item.street_address);
+                let status = compare(
+                    &expected,
+                    hit.house_number.as_deref(),
+                    hit.street.as_deref(),
+                );
+                row.reverse_house_number = hit.house_number;
+                row.reverse_street = hit.street;
+                row.reverse_display_name = Some(hit.display_name);
+                row.reverse_latitude = Some(hit.latitude);
+                row.reverse_longitude = Some(hit.longitude);
+                row.distance_m = Some(distance);
+                match status {
+                    VerificationStatus::Match if distance > options.max_distance_m => {
+                        VerificationStatus::TooFar
+                    }
+                    status => status,
+                }
+            }
+            Ok(None) => VerificationStatus::NoResult,
+            Err(err) => {
+                row.error = Some(err.to_string());
+                VerificationStatus::Failed
+            }
+        };
+
+        log::info!(
+            "ID {}: {} -> {} ({:?})",
+            row.id,
+            row.street_address,
+            row.reverse_display_name.as_deref().unwrap_or("-"),
+            row.status
+        );
+        rows.push(row);
+    }
+    rows
+}
+
+fn compare(
+    expected: &StreetLine,
+    house_number: Option<&str>,
+    street: Option<&str>,
+) -> VerificationStatus {
+    let street_matches =
+        street.is_some_and(|street| normalize_street(street) == normalize_street(&expected.street));
+    if !street_matches {
+        return VerificationStatus::StreetMismatch;
+    }
+    match (&expected.house_number, house_number) {
+        (Some(expected), Some(found)) if !expected.eq_ignore_ascii_case(found.trim()) => {
+            VerificationStatus::HouseNumberMismatch
+        }
+        (Some(_), None) => VerificationStatus::HouseNumberMismatch,
+        _ => VerificationStatus::Match,
+    }
+}
+
+/// Writes the rows as a CSV for manual correction.
+pub fn write_review_csv<P: AsRef<Path>>(path: P, rows: &[ReviewRow]) -> Result<(), GeocodingError> {
+    let path = path.as_ref();
+    let review_error = |source| GeocodingError::Review {
+        path: path.to_path_buf(),
+        source,
+    };
+    let mut writer = Writer::from_path(path).map_err(review_error)?;
+    for row in rows {
+        writer.serialize(row).map_err(review_error)?;
+    }
+    writer
+        .flush()
+        .map_err(|err| review_error(csv::Error::from(err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geocoding::MockGeocoder;
+    use std::time::Duration;
+
+    const BIENVILLE: &str = "813 Bienville Street, New Orleans, Louisiana 70112";
+    const BARRACKS: &str = "841 Barracks Street, New Orleans, Louisiana 70116";
+
+    fn located(id: u32, address: &str, coordinate: Option<(f64, f64)>) -> TileInventory {
+        TileInventory {
+            latitude: coordinate.map(|(latitude, _)| latitude),
+            longitude: coordinate.map(|(_, longitude)| longitude),
+            ..TileInventory::new(id, "Calle D'Bienville", address)
+        }
+    }
+
+    fn statuses(rows: &[ReviewRow]) -> Vec<(u32, VerificationStatus)> {
+        rows.iter().map(|row| (row.id, row.status)).collect()
+    }
+
+    #[test]
+    fn splits_the_house_number_from_the_street() {
+        assert_eq!(
+            parse_street_line("813 Bienville St, New Orleans, LA 70112"),
+            StreetLine {
+                house_number: Some("813".to_string()),
+                street: "Bienville St".to_string(),
+            }
+        );
+        assert_eq!(
+            parse_street_line("Jackson Square"),
+            StreetLine {
+                house_number: None,
+                street: "Jackson Square".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn compares_house_numbers_and_street_names() {
+        let expected = parse_street_line("1005 St Peter");
+        let compare = |number, street| compare(&expected, number, street);
+        assert_eq!(
+            compare(Some("1005"), Some("Saint Peter Street")),
+            VerificationStatus::Match
+        );
+        assert_eq!(
+            compare(Some("1007"), Some("Saint Peter Street")),
+            VerificationStatus::HouseNumberMismatch
+        );
+        assert_eq!(
+            compare(None, Some("Saint Peter Street")),
+            VerificationStatus::HouseNumberMismatch
+        );
+        // The street is checked first, since a number on another street means nothing.
+        assert_eq!(
+            compare(Some("1007"), Some("Saint Philip Street")),
+            VerificationStatus::StreetMismatch
+        );
+        assert_eq!(compare(None, None), VerificationStatus::StreetMismatch);
+        // Landmarks have no number to disagree with.
+        assert_eq!(
+            super::compare(
+                &parse_street_line("Jackson Square"),
+                Some("1"),
+                Some("Jackson Sq")
+            ),
+            VerificationStatus::Match
+        );
+    }
+
+    #[tokio::test]
+    async fn flags_each_kind_of_disagreement() {
+        let bienville = (29.9557409, -90.0686785);
+        let barracks = (29.9640623, -90.06208689);
+        // About 45 m north of 813 Bienville, still nearer it than anything else.
+        let north_of_bienville = (bienville.0 + 0.0004, bienville.1);
+        let failing = (29.9600, -90.0650);
+        let geocoder = MockGeocoder::new()
+            .with_response(BIENVILLE, bienville.0, bienville.1)
+            .with_response(BARRACKS, barracks.0, barracks.1)
+            .with_failure(format!("reverse {},{}", failing.0, failing.1), 503);
+        let inventory = vec![
+            located(1, "813 Bienville St", Some(bienville)),
+            located(2, "813 Bienville St", Some(north_of_bienville)),
+            located(3, "815 Bienville St", Some(bienville)),
+            located(4, "841 Conti St", Some(barracks)),
+            located(5, "813 Bienville St", None),
+            located(6, "841 Barracks St", Some(failing)),
+        ];
+        let options = VerifyOptions {
+            retry: RetryPolicy {
+                max_attempts: 1,
+                initial_delay: Duration::ZERO,
+                max_delay: Duration::ZERO,
+            },
+            ..VerifyOptions::default()
+        };
+
+        let rows = verify_coordinates(&geocoder, &inventory, &options).await;
+        assert_eq!(
+            statuses(&rows),
+            [
+                (1, VerificationStatus::Match),
+                (2, VerificationStatus::TooFar),
+                (3, VerificationStatus::HouseNumberMismatch),
+                (4, VerificationStatus::StreetMismatch),
+                (5, VerificationStatus::MissingCoordinates),
+                (6, VerificationStatus::Failed),
+            ]
+        );
+        assert!(!rows[0].needs_review());
+        assert!(rows[1..].iter().all(ReviewRow::needs_review));
+
+        assert_eq!(rows[0].distance_m, Some(0.0));
+        assert_eq!(rows[0].reverse_display_name.as_deref(), Some(BIENVILLE));
+        let too_far = rows[1].distance_m.unwrap();
+        assert!((40.0..50.0).contains(&too_far), "{}", too_far);
+        assert_eq!(rows[1].reverse_latitude, Some(bienville.0));
+        assert_eq!(rows[2].reverse_house_number.as_deref(), Some("813"));
+        assert_eq!(rows[3].reverse_street.as_deref(), Some("Barracks Street"));
+        assert_eq!(rows[4].distance_m, None);
+        assert!(rows[5].error.as_deref().unwrap().contains("503"));
+        // Records without coordinates are never looked up.
+        assert_eq!(geocoder.queries().len(), 5);
+
+        // A wider radius accepts the nearby match.
+        let lenient = VerifyOptions {
+            max_distance_m: 50.0,
+            ..options
+        };
+        let rows = verify_coordinates(&geocoder, &inventory[1..2], &lenient).await;
+        assert_eq!(statuses(&rows), [(2, VerificationStatus::Match)]);
+
+        let rows = verify_coordinates(&MockGeocoder::new(), &inventory[..1], &lenient).await;
+        assert_eq!(statuses(&rows), [(1, VerificationStatus::NoResult)]);
+    }
+}
@@ -1,4 +1,10 @@
 pub mod csv_parser;
-
+pub mod diff;
+pub mod export;
+pub mod fmt;
 #[cfg(feature = "no-wasm")]
-pub use csv_parser::geocoding;
+pub mod geocoding;
+#[cfg(feature = "no-wasm")]
+pub mod logging;
+pub mod merge;
+pub mod photos;
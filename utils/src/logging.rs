@@ -0,0 +1,27 @@
+use log::{LevelFilter, Log, Metadata, Record};
+
+/// Prints log messages to stderr, one per line.
+struct StderrLogger;
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("{}", record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Shows the geocoding progress messages, which go through `log`, on stderr. The command-line
+/// tools call this first thing; the terminal editor doesn't, so nothing is written over its
+/// screen.
+pub fn init_stderr() {
+    if log::set_logger(&StderrLogger).is_ok() {
+        log::set_max_level(LevelFilter::Info);
+    }
+}
@@ -0,0 +1,205 @@
+use crate::diff::record_fields;
+use data::inventory::TileInventory;
+use serde::Serialize;
+use serde_json::Value;
+use std::str::FromStr;
+
+/// Which inventory's value is kept when both have one and they differ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prefer {
+    Base,
+    Other,
+}
+
+impl FromStr for Prefer {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "base" => Ok(Prefer::Base),
+            "other" => Ok(Prefer::Other),
+            _ => Err(format!("expected \"base\" or \"other\", got {:?}", s)),
+        }
+    }
+}
+
+/// A column both inventories filled in differently.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MergeConflict {
+    pub id: u32,
+    pub field: String,
+    pub kept: Value,
+    pub discarded: Value,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct MergeReport {
+    /// IDs only in the other inventory, appended to the base.
+    pub added: Vec<u32>,
+    /// Columns empty in the base that the other inventory filled in.
+    pub filled: usize,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// Merges `other` into `base`, matching records by ID. Empty columns are filled from
+/// whichever side has a value; when both have different values, `prefer` decides and the
+/// conflict is reported.
+pub fn merge_inventories(
+    base: &mut Vec<TileInventory>,
+    other: &[TileInventory],
+    prefer: Prefer,
+) -> Result<MergeReport, serde_json::Error> {
+    let mut report = MergeReport::default();
+    for theirs in other {
+        let Some(ours) = base.iter_mut().find(|item| item.id == theirs.id) else {
+            report.added.push(theirs.id);
+            base.push(theirs.clone());
+            continue;
+        };
+
+        let mut fields = record_fields(ours);
+        for (field, their_value) in record_fields(theirs) {
+            let our_value = fields.entry(field.clone()).or_insert(Value::Null);
+            if is_empty(&their_value) || *our_value == their_value {
+                continue;
+            }
+            if is_empty(our_value) {
+                *our_value = their_value;
+                report.filled += 1;
+                continue;
+            }
+            let (kept, discarded) = match prefer {
+                Prefer::Base => (our_value.clone(), their_value),
+                Prefer::Other => {
+                    let discarded = std::mem::replace(our_value, their_value.clone());
+                    (their_value, discarded)
+                }
+            };
+            report.conflicts.push(MergeConflict {
+                id: theirs.id,
+                field,
+                kept,
+                discarded,
+            });
+        }
+        *ours = serde_json::from_value(Value::Object(fields))?;
+    }
+    Ok(report)
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(s) => s.is_empty(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::geo::MatchType;
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    fn record(id: u32) -> TileInventory {
+        TileInventory::new(id, "Calle de Chartres", format!("{}00 Chartres St", id))
+    }
+
+    /// A record with every column filled in.
+    fn complete(id: u32) -> TileInventory {
+        TileInventory {
+            sign_condition: Some("Fair Condition".to_string()),
+            number_of_tiles_damaged: Some(2),
+            grout_condition: Some("Cracked".to_string()),
+            wall_type: Some("Stucco".to_string()),
+            building_occupant: Some("Napoleon House".to_string()),
+            title_owner: Some("Impastato".to_string()),
+            photo_1: Some(PathBuf::from("Inventory_Images/1.Photo 1.134027.jpg")),
+            photo_2: Some(PathBuf::from("Inventory_Images/1.Photo 2.134101.jpg")),
+            photo_3: Some(PathBuf::from("Inventory_Images/1.Photo 3.134130.jpg")),
+            photo_4: Some(PathBuf::from("Inventory_Images/1.Photo 4.134200.jpg")),
+            photo_5: Some(PathBuf::from("Inventory_Images/1.Photo 5.134233.jpg")),
+            latitude: Some(29.9560),
+            longitude: Some(-90.0646),
+            exif_latitude: Some(29.95605),
+            exif_longitude: Some(-90.06455),
+            geocode_provider: Some("nominatim".to_string()),
+            geocode_display_name: Some("500 Chartres Street".to_string()),
+            geocode_match_type: Some(MatchType::House),
+            geocode_confidence: Some(0.875),
+            geocode_timestamp: Some(1_700_000_000),
+            notes: Some("sign partly hidden by a balcony".to_string()),
+            ..record(id)
+        }
+    }
+
+    #[test]
+    fn parses_the_preference() {
+        assert_eq!("base".parse(), Ok(Prefer::Base));
+        assert_eq!("other".parse(), Ok(Prefer::Other));
+        assert!("theirs".parse::<Prefer>().is_err());
+    }
+
+    #[test]
+    fn survives_the_json_round_trip() {
+        // Merging an empty copy changes nothing, so every column comes back from JSON intact.
+        let mut base = vec![complete(5)];
+        let report = merge_inventories(&mut base, &[record(5)], Prefer::Other).unwrap();
+        assert_eq!(record_fields(&base[0]), record_fields(&complete(5)));
+        assert_eq!((report.filled, report.conflicts.len()), (0, 0));
+
+        // And so does filling an empty record from a complete one.
+        let mut base = vec![record(5)];
+        let report = merge_inventories(&mut base, &[complete(5)], Prefer::Base).unwrap();
+        assert_eq!(record_fields(&base[0]), record_fields(&complete(5)));
+        assert_eq!(report.filled, 21);
+        assert!(report.conflicts.is_empty());
+    }
+
+    #[test]
+    fn fills_gaps_appends_new_records_and_reports_conflicts() {
+        let base = vec![
+            TileInventory {
+                sign_condition: Some("Good Condition".to_string()),
+                ..record(1)
+            },
+            record(2),
+        ];
+        let other = vec![
+            TileInventory {
+                sign_condition: Some("Graffiti".to_string()),
+                notes: Some("repainted".to_string()),
+                ..record(1)
+            },
+            record(3),
+        ];
+
+        let mut kept_base = base.clone();
+        let report = merge_inventories(&mut kept_base, &other, Prefer::Base).unwrap();
+        assert_eq!(report.added, [3]);
+        assert_eq!(report.filled, 1);
+        assert_eq!(
+            report.conflicts,
+            [MergeConflict {
+                id: 1,
+                field: "Sign Condition".to_string(),
+                kept: json!("Good Condition"),
+                discarded: json!("Graffiti"),
+            }]
+        );
+        let ids: Vec<u32> = kept_base.iter().map(|item| item.id).collect();
+        assert_eq!(ids, [1, 2, 3]);
+        assert_eq!(
+            kept_base[0].sign_condition.as_deref(),
+            Some("Good Condition")
+        );
+        assert_eq!(kept_base[0].notes.as_deref(), Some("repainted"));
+
+        let mut took_other = base;
+        let report = merge_inventories(&mut took_other, &other, Prefer::Other).unwrap();
+        assert_eq!(report.conflicts[0].kept, json!("Graffiti"));
+        assert_eq!(report.conflicts[0].discarded, json!("Good Condition"));
+        assert_eq!(took_other[0].sign_condition.as_deref(), Some("Graffiti"));
+    }
+}
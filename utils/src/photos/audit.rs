@@ -0,0 +1,486 @@
+use super::filename::{find_id_mismatches, IdMismatch};
+use super::normalize_path;
+use data::inventory::TileInventory;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum PhotoAuditError {
+    #[error("Failed to read image directory {path:?}: {source}")]
+    ReadDir {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+/// A single photo column of a single record.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PhotoRef {
+    pub id: u32,
+    /// 1-based photo column, matching the "Photo N" headers.
+    pub slot: usize,
+    pub path: PathBuf,
+}
+
+/// A reference that only resolves once case and whitespace are ignored.
+#[derive(Debug, Clone, Serialize)]
+pub struct PathMismatch {
+    pub reference: PhotoRef,
+    pub actual: PathBuf,
+}
+
+/// A file referenced from more than one photo column.
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateReference {
+    pub path: PathBuf,
+    pub references: Vec<PhotoRef>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub enum UnreadableReason {
+    Empty,
+    NotAnImage,
+    Io(String),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UnreadableImage {
+    pub path: PathBuf,
+    pub reason: UnreadableReason,
+}
+
+/// A suggested rewrite of one photo column.
+#[derive(Debug, Clone, Serialize)]
+pub struct PhotoFix {
+    pub reference: PhotoRef,
+    pub replacement: PathBuf,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct PhotoAuditReport {
+    pub missing: Vec<PhotoRef>,
+    pub orphaned: Vec<PathBuf>,
+    pub mismatched: Vec<PathMismatch>,
+    pub duplicates: Vec<DuplicateReference>,
+    pub unreadable: Vec<UnreadableImage>,
+    pub id_mismatches: Vec<IdMismatch>,
+    pub fixes: Vec<PhotoFix>,
+}
+
+impl PhotoAuditReport {
+    pub fn is_clean(&self) -> bool {
+        self.missing.is_empty()
+            && self.orphaned.is_empty()
+            && self.mismatched.is_empty()
+            && self.duplicates.is_empty()
+            && self.unreadable.is_empty()
+            && self.id_mismatches.is_empty()
+    }
+}
+
+/// Checks every photo column in `inventory` against the files in `images_dir`.
+///
+/// Photo paths in the CSVs are relative (e.g. `Inventory_Images/1.Photo 1.134027.jpg`), so
+/// `images_dir` is given relative to `base_dir`, the directory those paths resolve from.
+pub fn audit_photos(
+    inventory: &[TileInventory],
+    base_dir: &Path,
+    images_dir: &Path,
+) -> Result<PhotoAuditReport, PhotoAuditError> {
+    let images_dir = normalize_path(images_dir);
+    let files = list_images(base_dir, &images_dir)?;
+    let by_loose_name: HashMap<String, &PathBuf> =
+        files.iter().map(|file| (loose_key(file), file)).collect();
+
+    let mut report = PhotoAuditReport::default();
+    let mut references: BTreeMap<PathBuf, Vec<PhotoRef>> = BTreeMap::new();
+
+    for item in inventory {
+        for (index, photo) in item.photo_slots().into_iter().enumerate() {
+            let Some(photo) = photo else { continue };
+            let reference = PhotoRef {
+                id: item.id,
+                slot: index + 1,
+                path: photo.clone(),
+            };
+            let path = normalize_path(photo);
+
+            if files.contains(&path) {
+                references.entry(path).or_default().push(reference);
+            } else if let Some(actual) = by_loose_name.get(&loose_key(&path)) {
+                references
+                    .entry((*actual).clone())
+                    .or_default()
+                    .push(reference.clone());
+                report.fixes.push(PhotoFix {
+                    reference: reference.clone(),
+                    replacement: (*actual).clone(),
+                });
+                report.mismatched.push(PathMismatch {
+                    reference,
+                    actual: (*actual).clone(),
+                });
+            } else {
+                report.missing.push(reference);
+            }
+        }
+    }
+
+    for reference in &report.missing {
+        let path = normalize_path(&reference.path);
+        if let Some(closest) = closest_match(&path, &files, &references) {
+            report.fixes.push(PhotoFix {
+                reference: reference.clone(),
+                replacement: closest.clone(),
+            });
+        }
+    }
+
+    for file in &files {
+        if !references.contains_key(file) {
+            report.orphaned.push(file.clone());
+        }
+        if let Some(reason) = check_readable(&base_dir.join(file)) {
+            report.unreadable.push(UnreadableImage {
+                path: file.clone(),
+                reason,
+            });
+        }
+    }
+
+    report.duplicates = references
+        .into_iter()
+        .filter(|(_, refs)| refs.len() > 1)
+        .map(|(path, references)| DuplicateReference { path, references })
+        .collect();
+    report.id_mismatches = find_id_mismatches(inventory);
+
+    Ok(report)
+}
+
+/// Rewrites the photo columns named in `fixes`, returning how many were changed.
+pub fn apply_fixes(inventory: &mut [TileInventory], fixes: &[PhotoFix]) -> usize {
+    let mut applied = 0;
+    for fix in fixes {
+        let Some(item) = inventory
+            .iter_mut()
+            .find(|item| item.id == fix.reference.id)
+        else {
+            continue;
+        };
+        let Some(slot) = item
+            .photo_slots_mut()
+            .into_iter()
+            .nth(fix.reference.slot.wrapping_sub(1))
+        else {
+            continue;
+        };
+        if slot.as_ref() == Some(&fix.reference.path) {
+            *slot = Some(fix.replacement.clone());
+            applied += 1;
+        }
+    }
+    applied
+}
+
+/// Regular files in `images_dir`, as paths relative to `base_dir`, sorted by name.
+pub(super) fn list_images(
+    base_dir: &Path,
+    images_dir: &Path,
+) -> Result<Vec<PathBuf>, PhotoAuditError> {
+    let dir = base_dir.join(images_dir);
+    let read_dir_error = |source| PhotoAuditError::ReadDir {
+        path: dir.clone(),
+        source,
+    };
+
+    let mut files = Vec::new();
+    for entry in fs::read_dir(&dir).map_err(read_dir_error)? {
+        let entry = entry.map_err(read_dir_error)?;
+        let name = entry.file_name();
+        if name.to_string_lossy().starts_with('.') {
+            continue;
+        }
+        if entry.file_type().map_err(read_dir_error)?.is_file() {
+            files.push(images_dir.join(name));
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Lowercased with whitespace runs collapsed, so "1.photo  1.134027.JPG" matches
+/// "1.Photo 1.134027.jpg".
+fn loose_key(path: &Path) -> String {
+    path.to_string_lossy()
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The file with the smallest edit distance to `path`, if it is close enough to be a typo.
+/// Files no other record references are preferred, since a broken reference usually points
+/// at an orphan.
+fn closest_match<'a>(
+    path: &Path,
+    files: &'a [PathBuf],
+    references: &BTreeMap<PathBuf, Vec<PhotoRef>>,
+) -> Option<&'a PathBuf> {
+    let file_name = |path: &Path| loose_key(Path::new(path.file_name().unwrap_or_default()));
+    let target = file_name(path);
+    let max_distance = target.chars().count() / 5;
+    files
+        .iter()
+        .map(|file| (levenshtein(&target, &file_name(file)), file))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, file)| (references.contains_key(*file), *distance))
+        .map(|(_, file)| file)
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+fn check_readable(path: &Path) -> Option<UnreadableReason> {
+    let mut header = [0u8; 4];
+    let read = File::open(path).and_then(|mut file| file.read(&mut header));
+    match read {
+        Err(err) => Some(UnreadableReason::Io(err.to_string())),
+        Ok(0) => Some(UnreadableReason::Empty),
+        Ok(n) if !is_image_header(&header[..n]) => Some(UnreadableReason::NotAnImage),
+        Ok(_) => None,
+    }
+}
+
+fn is_image_header(header: &[u8]) -> bool {
+    header.starts_with(&[0xFF, 0xD8, 0xFF]) || header.starts_with(&[0x89, b'P', b'N', b'G'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JPEG: &[u8] = &[0xFF, 0xD8, 0xFF, 0xE0, 0x00];
+    const PNG: &[u8] = &[0x89, b'P', b'N', b'G', 0x0D];
+
+    /// A fresh `Inventory_Images` under the temp directory, holding `files`.
+    fn images(test: &str, files: &[(&str, &[u8])]) -> PathBuf {
+        let base =
+            std::env::temp_dir().join(format!("photo-audit-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(base.join("Inventory_Images")).unwrap();
+        for (name, contents) in files {
+            fs::write(base.join("Inventory_Images").join(name), contents).unwrap();
+        }
+        base
+    }
+
+    fn record(id: u32, photos: &[&str]) -> TileInventory {
+        let mut item = TileInventory::new(id, "Calle D'Bienville", "813 Bienville St");
+        for (slot, photo) in item.photo_slots_mut().into_iter().zip(photos) {
+            *slot = Some(PathBuf::from(photo));
+        }
+        item
+    }
+
+    fn reference(id: u32, slot: usize, path: &str) -> PhotoRef {
+        PhotoRef {
+            id,
+            slot,
+            path: PathBuf::from(path),
+        }
+    }
+
+    #[test]
+    fn reports_each_kind_of_problem() {
+        let base = images(
+            "report",
+            &[
+                ("1.Photo 1.100000.jpg", JPEG),
+                ("2.Photo 1.200000.jpg", JPEG),
+                ("3.Photo 1.300000.jpg", b""),
+                ("3.Photo 2.300001.jpg", b"not a photo"),
+                ("4.Photo 1.400000.png", PNG),
+                (".DS_Store", b""),
+            ],
+        );
+        let inventory = vec![
+            record(
+                1,
+                &[
+                    "Inventory_Images/1.Photo 1.100000.jpg",
+                    "./Inventory_Images/1.Photo 1.100000.jpg",
+                ],
+            ),
+            record(2, &["Inventory_Images/2.photo  1.200000.JPG"]),
+            record(
+                3,
+                &[
+                    "Inventory_Images/3.Photo 1.300000.jpg",
+                    "Inventory_Images/3.Photo 2.300010.jpg",
+                ],
+            ),
+            record(5, &["Inventory_Images/4.Photo 2.235959.jpg"]),
+        ];
+        let report = audit_photos(&inventory, &base, Path::new("Inventory_Images")).unwrap();
+        fs::remove_dir_all(&base).unwrap();
+
+        assert_eq!(
+            report.missing,
+            [
+                reference(3, 2, "Inventory_Images/3.Photo 2.300010.jpg"),
+                reference(5, 1, "Inventory_Images/4.Photo 2.235959.jpg"),
+            ]
+        );
+        assert_eq!(
+            report.orphaned,
+            [
+                PathBuf::from("Inventory_Images/3.Photo 2.300001.jpg"),
+                PathBuf::from("Inventory_Images/4.Photo 1.400000.png"),
+            ]
+        );
+        assert_eq!(report.mismatched.len(), 1);
+        assert_eq!(
+            report.mismatched[0].reference,
+            reference(2, 1, "Inventory_Images/2.photo  1.200000.JPG")
+        );
+        assert_eq!(
+            report.mismatched[0].actual,
+            PathBuf::from("Inventory_Images/2.Photo 1.200000.jpg")
+        );
+
+        assert_eq!(report.duplicates.len(), 1);
+        assert_eq!(
+            report.duplicates[0].path,
+            PathBuf::from("Inventory_Images/1.Photo 1.100000.jpg")
+        );
+        assert_eq!(report.duplicates[0].references.len(), 2);
+
+        let unreadable: Vec<_> = report
+            .unreadable
+            .iter()
+            .map(|image| (image.path.to_string_lossy().into_owned(), &image.reason))
+            .collect();
+        assert_eq!(unreadable.len(), 2);
+        assert!(matches!(
+            unreadable[0],
+            (ref path, UnreadableReason::Empty) if path.ends_with("3.Photo 1.300000.jpg")
+        ));
+        assert!(matches!(
+            unreadable[1],
+            (ref path, UnreadableReason::NotAnImage) if path.ends_with("3.Photo 2.300001.jpg")
+        ));
+
+        // Record 5's photo is named for sign 4.
+        assert_eq!(report.id_mismatches.len(), 1);
+        assert_eq!(report.id_mismatches[0].embedded_id, 4);
+        assert!(!report.is_clean());
+
+        // The case fix, and the typo fix to the orphan; 235959 is too far from anything.
+        let fixes: Vec<_> = report
+            .fixes
+            .iter()
+            .map(|fix| {
+                (
+                    fix.reference.id,
+                    fix.replacement.to_string_lossy().into_owned(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            fixes,
+            [
+                (2, "Inventory_Images/2.Photo 1.200000.jpg".to_string()),
+                (3, "Inventory_Images/3.Photo 2.300001.jpg".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_tidy_directory_is_clean() {
+        let base = images("clean", &[("1.Photo 1.100000.jpg", JPEG)]);
+        let inventory = vec![record(1, &["Inventory_Images/1.Photo 1.100000.jpg"])];
+        let report = audit_photos(&inventory, &base, Path::new("Inventory_Images")).unwrap();
+        fs::remove_dir_all(&base).unwrap();
+        assert!(report.is_clean(), "{:?}", report);
+        assert!(report.fixes.is_empty());
+    }
+
+    #[test]
+    fn closest_match_prefers_orphans_within_a_fifth_of_the_name() {
+        let files = vec![
+            PathBuf::from("Inventory_Images/1.Photo 1.100001.jpg"),
+            PathBuf::from("Inventory_Images/1.Photo 1.100012.jpg"),
+        ];
+        let target = Path::new("Inventory_Images/1.Photo 1.100002.jpg");
+        let mut references = BTreeMap::new();
+        assert_eq!(closest_match(target, &files, &references), Some(&files[0]));
+
+        // The nearer file is taken, so the next nearest unreferenced one wins.
+        references.insert(files[0].clone(), vec![reference(1, 1, "x")]);
+        assert_eq!(closest_match(target, &files, &references), Some(&files[1]));
+
+        // "1.photo 1.100002.jpg" is 20 characters, so up to 4 edits.
+        let files = vec![PathBuf::from("Inventory_Images/1.Photo 1.154321.jpg")];
+        assert_eq!(
+            levenshtein("1.photo 1.100002.jpg", "1.photo 1.154321.jpg"),
+            5
+        );
+        assert_eq!(closest_match(target, &files, &BTreeMap::new()), None);
+        let files = vec![PathBuf::from("Inventory_Images/1.Photo 1.104321.jpg")];
+        assert_eq!(
+            closest_match(target, &files, &BTreeMap::new()),
+            Some(&files[0])
+        );
+    }
+
+    #[test]
+    fn loose_keys_ignore_case_and_spacing() {
+        assert_eq!(
+            loose_key(Path::new("Inventory_Images/1.photo  1.134027.JPG")),
+            loose_key(Path::new("Inventory_Images/1.Photo 1.134027.jpg"))
+        );
+        assert_ne!(
+            loose_key(Path::new("1.Photo 1.134027.jpg")),
+            loose_key(Path::new("1.Photo 11.34027.jpg"))
+        );
+    }
+
+    #[test]
+    fn applies_fixes_to_the_columns_they_were_made_for() {
+        let mut inventory = vec![record(1, &["a.jpg", "b.jpg"]), record(2, &["c.jpg"])];
+        let fix = |id, slot, from: &str, to: &str| PhotoFix {
+            reference: reference(id, slot, from),
+            replacement: PathBuf::from(to),
+        };
+        let fixes = [
+            fix(1, 2, "b.jpg", "B.jpg"),
+            // Stale: the column no longer holds the path the fix was made for.
+            fix(2, 1, "old.jpg", "C.jpg"),
+            // No such record, and no such column.
+            fix(9, 1, "a.jpg", "A.jpg"),
+            fix(1, 0, "a.jpg", "A.jpg"),
+        ];
+        assert_eq!(apply_fixes(&mut inventory, &fixes), 1);
+        assert_eq!(inventory[0].photo_2, Some(PathBuf::from("B.jpg")));
+        assert_eq!(inventory[0].photo_1, Some(PathBuf::from("a.jpg")));
+        assert_eq!(inventory[1].photo_1, Some(PathBuf::from("c.jpg")));
+    }
+}
@@ -0,0 +1,326 @@
+use super::open_upright;
+use data::inventory::TileInventory;
+use data::photos::{derivative_path, thumbnail_path, DERIVATIVE_WIDTHS, THUMBNAIL_WIDTH};
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+const JPEG_QUALITY: u8 = 80;
+
+#[derive(Error, Debug)]
+pub enum DerivativeError {
+    #[error("IO error for {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Image error for {path:?}: {source}")]
+    Image {
+        path: PathBuf,
+        source: image::ImageError,
+    },
+    #[error("Failed to write manifest: {0}")]
+    Manifest(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DerivedImage {
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub original: String,
+    pub thumbnail: DerivedImage,
+    pub derivatives: Vec<DerivedImage>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DerivativeManifest {
+    pub entries: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Default)]
+pub struct DerivativeOptions {
+    /// Regenerate derivatives even when they are newer than the original.
+    pub force: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct DerivativeReport {
+    pub manifest: DerivativeManifest,
+    pub generated: usize,
+    pub skipped: usize,
+    pub failed: Vec<DerivativeError>,
+}
+
+/// Writes a thumbnail and one JPEG per `DERIVATIVE_WIDTHS` entry for every photo the
+/// inventory references, then a manifest describing them.
+///
+/// Paths are resolved against `base_dir`. Images are rotated upright from their EXIF
+/// orientation and never upscaled: a photo narrower than a target width is re-encoded at its
+/// own size so the file names the web viewer expects always exist. Photos whose derivatives
+/// are all newer than the original are skipped unless `options.force` is set.
+pub fn generate_derivatives(
+    inventory: &[TileInventory],
+    base_dir: &Path,
+    options: &DerivativeOptions,
+) -> Result<DerivativeReport, DerivativeError> {
+    let originals: BTreeSet<String> = inventory
+        .iter()
+        .flat_map(|item| item.photos())
+        .map(|photo| photo.to_string_lossy().into_owned())
+        .collect();
+
+    let derivatives_dir = base_dir.join(data::photos::DERIVATIVES_DIR);
+    fs::create_dir_all(&derivatives_dir).map_err(|source| DerivativeError::Io {
+        path: derivatives_dir.clone(),
+        source,
+    })?;
+
+    let mut report = DerivativeReport::default();
+    for original in originals {
+        match derive_one(base_dir, &original, options) {
+            Ok((entry, generated)) => {
+                if generated {
+                    report.generated += 1;
+                } else {
+                    report.skipped += 1;
+                }
+                report.manifest.entries.push(entry);
+            }
+            Err(err) => report.failed.push(err),
+        }
+    }
+
+    let manifest_path = derivatives_dir.join(MANIFEST_FILE);
+    let file = File::create(&manifest_path).map_err(|source| DerivativeError::Io {
+        path: manifest_path.clone(),
+        source,
+    })?;
+    serde_json::to_writer_pretty(BufWriter::new(file), &report.manifest)?;
+
+    Ok(report)
+}
+
+fn derive_one(
+    base_dir: &Path,
+    original: &str,
+    options: &DerivativeOptions,
+) -> Result<(ManifestEntry, bool), DerivativeError> {
+    let source_path = base_dir.join(original);
+    let targets: Vec<(String, u32)> = std::iter::once((thumbnail_path(original), THUMBNAIL_WIDTH))
+        .chain(
+            DERIVATIVE_WIDTHS
+                .iter()
+                .map(|width| (derivative_path(original, *width), *width)),
+        )
+        .collect();
+
+    let io_error = |path: &Path| {
+        let path = path.to_path_buf();
+        move |source| DerivativeError::Io { path, source }
+    };
+    let image_error = |path: &Path| {
+        let path = path.to_path_buf();
+        move |source| DerivativeError::Image { path, source }
+    };
+
+    let up_to_date = !options.force
+        && targets
+            .iter()
+            .all(|(target, _)| is_newer(&base_dir.join(target), &source_path));
+
+    let mut derived = Vec::with_capacity(targets.len());
+    if up_to_date {
+        for (target, _) in &targets {
+            let target_path = base_dir.join(target);
+            let (width, height) =
+                image::image_dimensions(&target_path).map_err(image_error(&target_path))?;
+            derived.push(DerivedImage {
+                path: target.clone(),
+                width,
+                height,
+            });
+        }
+        return Ok((manifest_entry(original, derived), false));
+    }
+
+    let image = load_upright(&source_path)?;
+    for (target, width) in &targets {
+        let target_path = base_dir.join(target);
+        let resized = if image.width() > *width {
+            image.resize(*width, u32::MAX, FilterType::Lanczos3)
+        } else {
+            image.clone()
+        };
+        if let Some(parent) = target_path.parent() {
+            fs::create_dir_all(parent).map_err(io_error(parent))?;
+        }
+        let file = File::create(&target_path).map_err(io_error(&target_path))?;
+        let encoder = JpegEncoder::new_with_quality(BufWriter::new(file), JPEG_QUALITY);
+        resized
+            .to_rgb8()
+            .write_with_encoder(encoder)
+            .map_err(image_error(&target_path))?;
+        derived.push(DerivedImage {
+            path: target.clone(),
+            width: resized.width(),
+            height: resized.height(),
+        });
+    }
+    Ok((manifest_entry(original, derived), true))
+}
+
+/// Splits the thumbnail (always generated first) from the `srcset` derivatives.
+fn manifest_entry(original: &str, mut derived: Vec<DerivedImage>) -> ManifestEntry {
+    let thumbnail = derived.remove(0);
+    ManifestEntry {
+        original: original.to_string(),
+        thumbnail,
+        derivatives: derived,
+    }
+}
+
+/// Upright, since the orientation tag is lost when the derivative is re-encoded.
+fn load_upright(path: &Path) -> Result<DynamicImage, DerivativeError> {
+    open_upright(path).map_err(|err| match err {
+        ImageError::IoError(source) => DerivativeError::Io {
+            path: path.to_path_buf(),
+            source,
+        },
+        source => DerivativeError::Image {
+            path: path.to_path_buf(),
+            source,
+        },
+    })
+}
+
+fn is_newer(target: &Path, source: &Path) -> bool {
+    let modified = |path: &Path| fs::metadata(path).and_then(|meta| meta.modified()).ok();
+    match (modified(target), modified(source)) {
+        (Some(target), Some(source)) => target >= source,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    fn with_photos(id: u32, photos: &[&str]) -> TileInventory {
+        let mut item = TileInventory::new(id, "Calle Real", "301 Royal St");
+        for (slot, photo) in item.photo_slots_mut().into_iter().zip(photos) {
+            *slot = Some(PathBuf::from(photo));
+        }
+        item
+    }
+
+    fn sizes(images: &[DerivedImage]) -> Vec<(u32, u32)> {
+        images
+            .iter()
+            .map(|image| (image.width, image.height))
+            .collect()
+    }
+
+    #[test]
+    fn writes_derivatives_and_a_manifest() {
+        let dir = std::env::temp_dir().join(format!("derivatives-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("Inventory_Images")).unwrap();
+        let wide = "Inventory_Images/1.Photo 1.134027.png";
+        let narrow = "Inventory_Images/2.Photo 1.140000.png";
+        RgbImage::from_pixel(800, 400, Rgb([200, 40, 40]))
+            .save(dir.join(wide))
+            .unwrap();
+        RgbImage::from_pixel(100, 50, Rgb([40, 40, 200]))
+            .save(dir.join(narrow))
+            .unwrap();
+        let inventory = vec![
+            with_photos(2, &[narrow, "Inventory_Images/missing.jpg"]),
+            // Shared with record 2, so derived once.
+            with_photos(1, &[wide, narrow]),
+        ];
+
+        let report = generate_derivatives(&inventory, &dir, &DerivativeOptions::default()).unwrap();
+        assert_eq!((report.generated, report.skipped), (2, 0));
+        assert_eq!(report.failed.len(), 1);
+        assert!(matches!(
+            &report.failed[0],
+            DerivativeError::Io { path, .. } if path.ends_with("missing.jpg")
+        ));
+
+        let originals: Vec<&str> = report
+            .manifest
+            .entries
+            .iter()
+            .map(|entry| entry.original.as_str())
+            .collect();
+        assert_eq!(originals, [wide, narrow]);
+        let [wide_entry, narrow_entry] = &report.manifest.entries[..] else {
+            panic!("expected two entries");
+        };
+        assert_eq!(wide_entry.thumbnail.path, thumbnail_path(wide));
+        assert_eq!(
+            (wide_entry.thumbnail.width, wide_entry.thumbnail.height),
+            (160, 80)
+        );
+        let paths: Vec<&str> = wide_entry
+            .derivatives
+            .iter()
+            .map(|image| image.path.as_str())
+            .collect();
+        assert_eq!(
+            paths,
+            DERIVATIVE_WIDTHS.map(|width| derivative_path(wide, width))
+        );
+        // Never upscaled: targets wider than the original keep its size.
+        assert_eq!(
+            sizes(&wide_entry.derivatives),
+            [(320, 160), (640, 320), (800, 400)]
+        );
+        assert_eq!(
+            (narrow_entry.thumbnail.width, narrow_entry.thumbnail.height),
+            (100, 50)
+        );
+        assert_eq!(sizes(&narrow_entry.derivatives), [(100, 50); 3]);
+        for image in &wide_entry.derivatives {
+            assert_eq!(
+                image::image_dimensions(dir.join(&image.path)).unwrap(),
+                (image.width, image.height)
+            );
+        }
+
+        let written: DerivativeManifest = serde_json::from_reader(
+            File::open(dir.join(data::photos::DERIVATIVES_DIR).join(MANIFEST_FILE)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(written.entries.len(), 2);
+        assert_eq!(
+            sizes(&written.entries[0].derivatives),
+            sizes(&wide_entry.derivatives)
+        );
+
+        // Everything is newer than the originals now.
+        let again = generate_derivatives(&inventory, &dir, &DerivativeOptions::default()).unwrap();
+        assert_eq!((again.generated, again.skipped), (0, 2));
+        assert_eq!(
+            sizes(&again.manifest.entries[0].derivatives),
+            sizes(&wide_entry.derivatives)
+        );
+        let forced =
+            generate_derivatives(&inventory, &dir, &DerivativeOptions { force: true }).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!((forced.generated, forced.skipped), (2, 0));
+    }
+}
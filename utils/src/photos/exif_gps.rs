@@ -0,0 +1,289 @@
+use data::geo::{haversine_distance_m, median_coordinate, CoordinateSource};
+use data::inventory::TileInventory;
+use exif::{In, Reader, Tag, Value};
+use serde::Serialize;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ExifGpsError {
+    #[error("IO error for {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Invalid EXIF data in {path:?}: {source}")]
+    Exif { path: PathBuf, source: exif::Error },
+}
+
+/// Reads the GPS position of a photo as `(latitude, longitude)`, or `None` when the photo has
+/// no EXIF block or no GPS tags.
+pub fn read_gps(path: &Path) -> Result<Option<(f64, f64)>, ExifGpsError> {
+    let file = File::open(path).map_err(|source| ExifGpsError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let exif = match Reader::new().read_from_container(&mut BufReader::new(file)) {
+        Ok(exif) => exif,
+        Err(exif::Error::NotFound(_)) | Err(exif::Error::BlankValue(_)) => return Ok(None),
+        Err(source) => {
+            return Err(ExifGpsError::Exif {
+                path: path.to_path_buf(),
+                source,
+            })
+        }
+    };
+
+    let degrees = |tag| match exif.get_field(tag, In::PRIMARY).map(|field| &field.value) {
+        Some(Value::Rational(parts)) if parts.len() == 3 => {
+            Some(parts[0].to_f64() + parts[1].to_f64() / 60.0 + parts[2].to_f64() / 3600.0)
+        }
+        _ => None,
+    };
+    let hemisphere = |tag| match exif.get_field(tag, In::PRIMARY).map(|field| &field.value) {
+        Some(Value::Ascii(values)) => values.first().and_then(|value| value.first()).copied(),
+        _ => None,
+    };
+
+    let (Some(latitude), Some(longitude)) = (degrees(Tag::GPSLatitude), degrees(Tag::GPSLongitude))
+    else {
+        return Ok(None);
+    };
+    // Phones write 0/0 when they had no fix yet; that is "unknown", not the Gulf of Guinea.
+    if latitude == 0.0 && longitude == 0.0 {
+        return Ok(None);
+    }
+    let latitude = match hemisphere(Tag::GPSLatitudeRef) {
+        Some(b'S') => -latitude,
+        _ => latitude,
+    };
+    let longitude = match hemisphere(Tag::GPSLongitudeRef) {
+        Some(b'W') => -longitude,
+        _ => longitude,
+    };
+    Ok(Some((latitude, longitude)))
+}
+
+/// A record's photo-derived coordinate next to its geocoded one.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExifLocation {
+    pub id: u32,
+    pub source: CoordinateSource,
+    pub latitude: f64,
+    pub longitude: f64,
+    /// How many of the record's photos carried GPS tags.
+    pub photos_with_gps: usize,
+    pub geocoded: Option<(f64, f64)>,
+    /// Meters between the EXIF and geocoded coordinates, when both exist.
+    pub distance_m: Option<f64>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ExifGpsReport {
+    pub locations: Vec<ExifLocation>,
+    /// Records with photos, none of which carry GPS tags.
+    pub without_gps: Vec<u32>,
+    #[serde(skip)]
+    pub failed: Vec<ExifGpsError>,
+}
+
+/// Fills `exif_latitude`/`exif_longitude` on every record whose photos carry GPS tags, using
+/// the median of the photos so one bad fix doesn't drag the marker. Photo paths are resolved
+/// against `base_dir`.
+pub fn locate_from_photos(inventory: &mut [TileInventory], base_dir: &Path) -> ExifGpsReport {
+    let mut report = ExifGpsReport::default();
+
+    for item in inventory.iter_mut() {
+        let mut points = Vec::new();
+        for photo in item.photos() {
+            match read_gps(&base_dir.join(photo)) {
+                Ok(Some(point)) => points.push(point),
+                Ok(None) => {}
+                Err(err) => report.failed.push(err),
+            }
+        }
+
+        let Some((latitude, longitude)) = median_coordinate(&points) else {
+            if item.photos().next().is_some() {
+                report.without_gps.push(item.id);
+            }
+            continue;
+        };
+        item.exif_latitude = Some(latitude);
+        item.exif_longitude = Some(longitude);
+
+        let geocoded = item.latitude.zip(item.longitude);
+        report.locations.push(ExifLocation {
+            id: item.id,
+            source: CoordinateSource::Exif,
+            latitude,
+            longitude,
+            photos_with_gps: points.len(),
+            geocoded,
+            distance_m: geocoded
+                .map(|geocoded| haversine_distance_m(geocoded, (latitude, longitude))),
+        });
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exif::experimental::Writer;
+    use exif::{Field, Rational};
+    use std::fs;
+    use std::io::Cursor;
+
+    const BIENVILLE: (f64, f64) = (29.9557409, -90.0686785);
+
+    fn temp_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("exif-gps-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Degrees, minutes and seconds to a ten-thousandth of a second, as cameras write them.
+    fn dms(degrees: f64) -> Value {
+        let degrees = degrees.abs();
+        let minutes = degrees.fract() * 60.0;
+        let seconds = minutes.fract() * 60.0;
+        Value::Rational(vec![
+            Rational::from((degrees.trunc() as u32, 1)),
+            Rational::from((minutes.trunc() as u32, 1)),
+            Rational::from(((seconds * 10_000.0).round() as u32, 10_000)),
+        ])
+    }
+
+    /// Writes a TIFF-wrapped EXIF block, with GPS tags when `gps` is given.
+    fn write_photo(path: &Path, gps: Option<(f64, f64)>) {
+        let field = |tag, value| Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value,
+        };
+        let mut fields = vec![field(Tag::Make, Value::Ascii(vec![b"Test".to_vec()]))];
+        if let Some((latitude, longitude)) = gps {
+            let hemisphere = |positive: bool, letters: [u8; 2]| {
+                Value::Ascii(vec![vec![letters[usize::from(!positive)]]])
+            };
+            fields.extend([
+                field(Tag::GPSLatitude, dms(latitude)),
+                field(Tag::GPSLatitudeRef, hemisphere(latitude >= 0.0, *b"NS")),
+                field(Tag::GPSLongitude, dms(longitude)),
+                field(Tag::GPSLongitudeRef, hemisphere(longitude >= 0.0, *b"EW")),
+            ]);
+        }
+        let mut writer = Writer::new();
+        for field in &fields {
+            writer.push_field(field);
+        }
+        let mut bytes = Cursor::new(Vec::new());
+        writer.write(&mut bytes, true).unwrap();
+        fs::write(path, bytes.into_inner()).unwrap();
+    }
+
+    fn assert_near(actual: (f64, f64), expected: (f64, f64)) {
+        assert!(
+            haversine_distance_m(actual, expected) < 0.01,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn reads_hemispheres_and_ignores_missing_fixes() {
+        let dir = temp_dir("read");
+        let photo = dir.join("photo.jpg");
+
+        write_photo(&photo, Some(BIENVILLE));
+        assert_near(read_gps(&photo).unwrap().unwrap(), BIENVILLE);
+        write_photo(&photo, Some((-33.8568, 151.2153)));
+        assert_near(read_gps(&photo).unwrap().unwrap(), (-33.8568, 151.2153));
+
+        write_photo(&photo, Some((0.0, 0.0)));
+        assert_eq!(read_gps(&photo).unwrap(), None);
+        write_photo(&photo, None);
+        assert_eq!(read_gps(&photo).unwrap(), None);
+        // A JPEG without an EXIF block at all.
+        fs::write(&photo, [0xFF, 0xD8, 0xFF, 0xD9]).unwrap();
+        assert_eq!(read_gps(&photo).unwrap(), None);
+
+        assert!(matches!(
+            read_gps(&dir.join("absent.jpg")),
+            Err(ExifGpsError::Io { .. })
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn stores_the_median_of_each_records_photos() {
+        let dir = temp_dir("median");
+        let photos = [
+            ("1a.jpg", Some((29.9550, -90.0690))),
+            ("1b.jpg", Some((29.9560, -90.0680))),
+            // A stray fix nearly three kilometers off, which the mean would follow.
+            ("1c.jpg", Some((29.9750, -90.0500))),
+            ("2a.jpg", Some((29.9580, -90.0650))),
+            ("2b.jpg", Some((29.9590, -90.0640))),
+            ("3a.jpg", None),
+        ];
+        for (name, gps) in photos {
+            write_photo(&dir.join(name), gps);
+        }
+        let with_photos = |id, names: &[&str]| {
+            let mut item = TileInventory::new(id, "Calle D'Bienville", "813 Bienville St");
+            for (slot, name) in item.photo_slots_mut().into_iter().zip(names) {
+                *slot = Some(PathBuf::from(name));
+            }
+            item
+        };
+        let mut inventory = vec![
+            TileInventory {
+                latitude: Some(BIENVILLE.0),
+                longitude: Some(BIENVILLE.1),
+                ..with_photos(1, &["1a.jpg", "1b.jpg", "1c.jpg"])
+            },
+            with_photos(2, &["2a.jpg", "2b.jpg"]),
+            with_photos(3, &["3a.jpg"]),
+            with_photos(4, &[]),
+            with_photos(5, &["5a.jpg"]),
+        ];
+
+        let report = locate_from_photos(&mut inventory, &dir);
+        fs::remove_dir_all(&dir).unwrap();
+
+        // Odd counts take the middle value of each coordinate.
+        let exif = |item: &TileInventory| item.exif_latitude.zip(item.exif_longitude);
+        assert_near(exif(&inventory[0]).unwrap(), (29.9560, -90.0680));
+        // Even counts average the middle two.
+        assert_near(exif(&inventory[1]).unwrap(), (29.9585, -90.0645));
+        assert_eq!(exif(&inventory[2]), None);
+        assert_eq!(exif(&inventory[3]), None);
+        assert_eq!(exif(&inventory[4]), None);
+        // The geocoded coordinates are left alone.
+        assert_eq!(inventory[0].latitude, Some(BIENVILLE.0));
+
+        let ids: Vec<u32> = report
+            .locations
+            .iter()
+            .map(|location| location.id)
+            .collect();
+        assert_eq!(ids, [1, 2]);
+        let first = &report.locations[0];
+        assert_eq!(first.photos_with_gps, 3);
+        assert_eq!(first.geocoded, Some(BIENVILLE));
+        let expected = haversine_distance_m(BIENVILLE, (first.latitude, first.longitude));
+        assert!((first.distance_m.unwrap() - expected).abs() < 1e-9);
+        assert_eq!(report.locations[1].distance_m, None);
+
+        // Record 4 has no photos to look at; record 5's photo doesn't exist.
+        assert_eq!(report.without_gps, [3, 5]);
+        assert_eq!(report.failed.len(), 1);
+    }
+}
@@ -0,0 +1,274 @@
+use super::audit::{list_images, PhotoAuditError, PhotoRef};
+use super::normalize_path;
+use data::inventory::TileInventory;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum PhotoNameError {
+    #[error("\"{0}\" does not match \"<ID>.Photo <N>.<HHMMSS>.jpg\"")]
+    Pattern(String),
+    #[error("\"{0}\" has an invalid capture time")]
+    Time(String),
+}
+
+/// Time of day a photo was taken, as embedded in its file name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub struct TimeOfDay {
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}:{:02}", self.hour, self.minute, self.second)
+    }
+}
+
+/// The parts of an `Inventory_Images` file name such as `1.Photo 3.134028.jpg`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct PhotoFileName {
+    pub sign_id: u32,
+    /// 1-based photo slot, matching the "Photo N" columns.
+    pub slot: u8,
+    pub captured_at: TimeOfDay,
+}
+
+impl FromStr for PhotoFileName {
+    type Err = PhotoNameError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let pattern_error = || PhotoNameError::Pattern(name.to_string());
+
+        let stem = name
+            .strip_suffix(".jpg")
+            .or_else(|| name.strip_suffix(".JPG"))
+            .or_else(|| name.strip_suffix(".jpeg"))
+            .ok_or_else(pattern_error)?;
+        let mut parts = stem.split('.');
+        let (Some(id), Some(photo), Some(time), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(pattern_error());
+        };
+
+        let sign_id = id.parse().map_err(|_| pattern_error())?;
+        let slot = photo
+            .strip_prefix("Photo ")
+            .and_then(|slot| slot.parse().ok())
+            .ok_or_else(pattern_error)?;
+
+        if time.len() != 6 || !time.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(pattern_error());
+        }
+        let field = |range: std::ops::Range<usize>| time[range].parse::<u8>().unwrap_or(u8::MAX);
+        let captured_at = TimeOfDay {
+            hour: field(0..2),
+            minute: field(2..4),
+            second: field(4..6),
+        };
+        if captured_at.hour > 23 || captured_at.minute > 59 || captured_at.second > 59 {
+            return Err(PhotoNameError::Time(name.to_string()));
+        }
+
+        Ok(PhotoFileName {
+            sign_id,
+            slot,
+            captured_at,
+        })
+    }
+}
+
+impl PhotoFileName {
+    /// Parses the file name component of `path`.
+    pub fn from_path(path: &Path) -> Result<Self, PhotoNameError> {
+        path.file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .parse()
+    }
+}
+
+/// A photo column whose file name embeds a different sign ID than the record holding it.
+#[derive(Debug, Clone, Serialize)]
+pub struct IdMismatch {
+    pub reference: PhotoRef,
+    pub embedded_id: u32,
+}
+
+/// Photo references whose `<ID>.` prefix disagrees with the record they belong to.
+/// References that don't follow the naming convention are skipped.
+pub fn find_id_mismatches(inventory: &[TileInventory]) -> Vec<IdMismatch> {
+    let mut mismatches = Vec::new();
+    for item in inventory {
+        for (index, photo) in item.photo_slots().into_iter().enumerate() {
+            let Some(photo) = photo else { continue };
+            let Ok(name) = PhotoFileName::from_path(photo) else {
+                continue;
+            };
+            if name.sign_id != item.id {
+                mismatches.push(IdMismatch {
+                    reference: PhotoRef {
+                        id: item.id,
+                        slot: index + 1,
+                        path: photo.clone(),
+                    },
+                    embedded_id: name.sign_id,
+                });
+            }
+        }
+    }
+    mismatches
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct RebuildReport {
+    /// Records whose photo columns changed.
+    pub updated: Vec<u32>,
+    /// Files that don't follow the naming convention.
+    pub unparsed: Vec<PathBuf>,
+    /// Files whose sign ID has no record in the inventory.
+    pub unknown_ids: Vec<PathBuf>,
+    /// Files with a slot outside "Photo 1" to "Photo 5".
+    pub out_of_range: Vec<PathBuf>,
+    /// Files that lost their slot to a later capture with the same ID and slot.
+    pub superseded: Vec<PathBuf>,
+}
+
+/// Rewrites every record's photo columns from the files in `images_dir` alone.
+///
+/// Paths are written relative to `base_dir`, the same way the CSVs store them. When a sign
+/// was photographed in more than one session the latest capture fills the slot, since that is
+/// the retake the surveyors kept.
+pub fn rebuild_photo_columns(
+    inventory: &mut [TileInventory],
+    base_dir: &Path,
+    images_dir: &Path,
+) -> Result<RebuildReport, PhotoAuditError> {
+    let images_dir = normalize_path(images_dir);
+    let mut report = RebuildReport::default();
+    let mut by_id: BTreeMap<u32, [Option<(TimeOfDay, PathBuf)>; 5]> = BTreeMap::new();
+
+    for file in list_images(base_dir, &images_dir)? {
+        let Ok(name) = PhotoFileName::from_path(&file) else {
+            report.unparsed.push(file);
+            continue;
+        };
+        if !inventory.iter().any(|item| item.id == name.sign_id) {
+            report.unknown_ids.push(file);
+            continue;
+        }
+        let Some(slot) = by_id
+            .entry(name.sign_id)
+            .or_default()
+            .get_mut(usize::from(name.slot).wrapping_sub(1))
+        else {
+            report.out_of_range.push(file);
+            continue;
+        };
+        match slot {
+            Some((captured_at, _)) if *captured_at >= name.captured_at => {
+                report.superseded.push(file);
+            }
+            _ => {
+                if let Some((_, existing)) = slot.replace((name.captured_at, file)) {
+                    report.superseded.push(existing);
+                }
+            }
+        }
+    }
+
+    for item in inventory.iter_mut() {
+        let rebuilt = by_id.remove(&item.id).unwrap_or_default();
+        let mut changed = false;
+        for (column, photo) in item.photo_slots_mut().into_iter().zip(rebuilt) {
+            let photo = photo.map(|(_, path)| path);
+            if *column != photo {
+                *column = photo;
+                changed = true;
+            }
+        }
+        if changed {
+            report.updated.push(item.id);
+        }
+    }
+    report.superseded.sort();
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_survey_file_names() {
+        let name: PhotoFileName = "1.Photo 3.134028.jpg".parse().unwrap();
+        assert_eq!(
+            name,
+            PhotoFileName {
+                sign_id: 1,
+                slot: 3,
+                captured_at: TimeOfDay {
+                    hour: 13,
+                    minute: 40,
+                    second: 28,
+                },
+            }
+        );
+        assert_eq!(name.captured_at.to_string(), "13:40:28");
+
+        let upper: PhotoFileName = "106.Photo 1.180735.JPG".parse().unwrap();
+        assert_eq!((upper.sign_id, upper.slot), (106, 1));
+    }
+
+    #[test]
+    fn parses_the_file_name_of_a_path() {
+        let name = PhotoFileName::from_path(Path::new("./Inventory_Images/66.Photo 2.134531.jpg"))
+            .unwrap();
+        assert_eq!((name.sign_id, name.slot), (66, 2));
+    }
+
+    #[test]
+    fn rejects_other_names() {
+        for name in [
+            "IMG_1234.jpg",
+            "1.Photo 3.134028.png",
+            "1.Photo 3.jpg",
+            "1.Photo 3.134028.extra.jpg",
+            "x.Photo 3.134028.jpg",
+            "1.Picture 3.134028.jpg",
+            "1.Photo three.134028.jpg",
+            "1.Photo 3.13402.jpg",
+            "1.Photo 3.13402a.jpg",
+        ] {
+            assert_eq!(
+                name.parse::<PhotoFileName>(),
+                Err(PhotoNameError::Pattern(name.to_string())),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_impossible_times() {
+        for name in [
+            "1.Photo 1.240000.jpg",
+            "1.Photo 1.126000.jpg",
+            "1.Photo 1.120060.jpg",
+        ] {
+            assert_eq!(
+                name.parse::<PhotoFileName>(),
+                Err(PhotoNameError::Time(name.to_string())),
+                "{}",
+                name
+            );
+        }
+    }
+}
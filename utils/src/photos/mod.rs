@@ -0,0 +1,35 @@
+pub mod audit;
+#[cfg(feature = "no-wasm")]
+pub mod derivatives;
+#[cfg(feature = "no-wasm")]
+pub mod exif_gps;
+pub mod filename;
+#[cfg(feature = "no-wasm")]
+pub mod similarity;
+
+use std::path::{Component, Path, PathBuf};
+
+/// Directory the inventory CSVs reference photos from, relative to the repo root.
+pub const IMAGES_DIR: &str = "Inventory_Images";
+
+/// Decodes `path` and applies its EXIF orientation, so phone photos taken sideways come out
+/// upright.
+#[cfg(feature = "no-wasm")]
+pub(crate) fn open_upright(path: &Path) -> image::ImageResult<image::DynamicImage> {
+    use image::{DynamicImage, ImageDecoder, ImageReader};
+
+    let mut decoder = ImageReader::open(path)?
+        .with_guessed_format()?
+        .into_decoder()?;
+    let orientation = decoder.orientation()?;
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+    Ok(image)
+}
+
+/// Drops `.` components so `./Inventory_Images/x.jpg` and `Inventory_Images/x.jpg` compare equal.
+pub(crate) fn normalize_path(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .collect()
+}
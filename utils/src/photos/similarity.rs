@@ -0,0 +1,308 @@
+use super::audit::PhotoRef;
+use super::open_upright;
+use data::inventory::TileInventory;
+use image::imageops::FilterType;
+use image::DynamicImage;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+#[error("Failed to hash {path:?}: {source}")]
+pub struct HashError {
+    pub path: PathBuf,
+    pub source: image::ImageError,
+}
+
+/// Bits in each hash, and so the largest meaningful threshold.
+pub const HASH_BITS: u32 = 64;
+
+/// Reads a `--threshold` argument, refusing distances no two hashes can be apart.
+pub fn parse_threshold(value: &str) -> Result<u32, String> {
+    let threshold: u32 = value.parse().map_err(|err| format!("{}", err))?;
+    if threshold > HASH_BITS {
+        return Err(format!(
+            "{} is more than the {} bits in a hash",
+            threshold, HASH_BITS
+        ));
+    }
+    Ok(threshold)
+}
+
+/// 64-bit perceptual hashes of one image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PerceptualHash {
+    /// Average hash: each bit is whether an 8x8 grayscale cell is brighter than the mean.
+    pub ahash: u64,
+    /// Difference hash: each bit is whether a cell is brighter than its right neighbour.
+    pub dhash: u64,
+}
+
+impl PerceptualHash {
+    pub fn of(image: &DynamicImage) -> Self {
+        let small = image.resize_exact(8, 8, FilterType::Triangle).to_luma8();
+        let mean = small.pixels().map(|p| u32::from(p[0])).sum::<u32>() / 64;
+        let ahash = small.pixels().fold(0u64, |hash, p| {
+            (hash << 1) | u64::from(u32::from(p[0]) > mean)
+        });
+
+        let wide = image.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+        let mut dhash = 0u64;
+        for y in 0..8 {
+            for x in 0..8 {
+                let brighter = wide.get_pixel(x, y)[0] > wide.get_pixel(x + 1, y)[0];
+                dhash = (dhash << 1) | u64::from(brighter);
+            }
+        }
+
+        PerceptualHash { ahash, dhash }
+    }
+
+    /// Hashes the photo as displayed, after its EXIF orientation, so a copy saved rotated
+    /// upright still matches the sideways original.
+    pub fn open(path: &Path) -> Result<Self, HashError> {
+        let image = open_upright(path).map_err(|source| HashError {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Ok(Self::of(&image))
+    }
+
+    /// The larger of the two Hamming distances, so both hashes have to agree.
+    pub fn distance(&self, other: &Self) -> u32 {
+        let ahash = (self.ahash ^ other.ahash).count_ones();
+        let dhash = (self.dhash ^ other.dhash).count_ones();
+        ahash.max(dhash)
+    }
+}
+
+/// Photos that are near-identical to each other.
+#[derive(Debug, Clone, Serialize)]
+pub struct SimilarGroup {
+    pub photos: Vec<PhotoRef>,
+    /// Largest distance between any two directly matched photos in the group.
+    pub max_distance: u32,
+    /// Sign IDs in the group; more than one suggests a photo filed under the wrong sign.
+    pub ids: Vec<u32>,
+}
+
+impl SimilarGroup {
+    pub fn spans_records(&self) -> bool {
+        self.ids.len() > 1
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SimilarityReport {
+    pub groups: Vec<SimilarGroup>,
+    #[serde(skip)]
+    pub failed: Vec<HashError>,
+}
+
+/// Hashes every referenced photo and groups those within `threshold` bits of each other.
+///
+/// Grouping is transitive: if A matches B and B matches C, all three land in one group even
+/// when A and C are further apart. A file referenced from several columns is only hashed once,
+/// and those references always share a group.
+pub fn find_similar_photos(
+    inventory: &[TileInventory],
+    base_dir: &Path,
+    threshold: u32,
+) -> SimilarityReport {
+    let mut report = SimilarityReport::default();
+    let mut hashes: HashMap<PathBuf, Option<PerceptualHash>> = HashMap::new();
+    let mut photos: Vec<(PhotoRef, PerceptualHash)> = Vec::new();
+
+    for item in inventory {
+        for (index, photo) in item.photo_slots().into_iter().enumerate() {
+            let Some(photo) = photo else { continue };
+            let hash = *hashes.entry(photo.clone()).or_insert_with(|| {
+                PerceptualHash::open(&base_dir.join(photo))
+                    .map_err(|err| report.failed.push(err))
+                    .ok()
+            });
+            if let Some(hash) = hash {
+                let reference = PhotoRef {
+                    id: item.id,
+                    slot: index + 1,
+                    path: photo.clone(),
+                };
+                photos.push((reference, hash));
+            }
+        }
+    }
+
+    let mut parent: Vec<usize> = (0..photos.len()).collect();
+    let mut max_distance = vec![0u32; photos.len()];
+    for i in 0..photos.len() {
+        for j in (i + 1)..photos.len() {
+            let distance = photos[i].1.distance(&photos[j].1);
+            if distance <= threshold {
+                let (a, b) = (find(&mut parent, i), find(&mut parent, j));
+                let merged = max_distance[a].max(max_distance[b]).max(distance);
+                parent[b] = a;
+                max_distance[a] = merged;
+            }
+        }
+    }
+
+    let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for i in 0..photos.len() {
+        let root = find(&mut parent, i);
+        groups.entry(root).or_default().push(i);
+    }
+    report.groups = groups
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(root, members)| {
+            let ids: BTreeSet<u32> = members.iter().map(|&i| photos[i].0.id).collect();
+            SimilarGroup {
+                photos: members.iter().map(|&i| photos[i].0.clone()).collect(),
+                max_distance: max_distance[root],
+                ids: ids.into_iter().collect(),
+            }
+        })
+        .collect();
+
+    report
+}
+
+fn find(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GrayImage, Luma};
+    use std::fs;
+
+    /// A 64x64 image whose brightness at `(x, y)` is `shade(x, y)`.
+    fn image(shade: impl Fn(u32, u32) -> u8) -> DynamicImage {
+        DynamicImage::ImageLuma8(GrayImage::from_fn(64, 64, |x, y| Luma([shade(x, y)])))
+    }
+
+    fn gradient(x: u32) -> u8 {
+        (x * 255 / 63) as u8
+    }
+
+    /// Dark left of `edge`, bright from it on.
+    fn step(edge: u32) -> DynamicImage {
+        image(|x, _| if x < edge { 20 } else { 230 })
+    }
+
+    #[test]
+    fn hashes_a_gradient() {
+        let left_to_right = PerceptualHash::of(&image(|x, _| gradient(x)));
+        // Each row is four cells darker than the mean, then four brighter.
+        assert_eq!(left_to_right.ahash, 0x0F0F_0F0F_0F0F_0F0F);
+        // No cell is brighter than the one to its right...
+        assert_eq!(left_to_right.dhash, 0);
+        // ...until the gradient is reversed.
+        let right_to_left = PerceptualHash::of(&image(|x, _| gradient(63 - x)));
+        assert_eq!(right_to_left.ahash, !left_to_right.ahash);
+        assert_eq!(right_to_left.dhash, u64::MAX);
+        assert_eq!(left_to_right.distance(&right_to_left), HASH_BITS);
+    }
+
+    #[test]
+    fn small_changes_keep_the_hash_close() {
+        let original = PerceptualHash::of(&image(|x, y| gradient(x) / 2 + y as u8));
+        let brighter = PerceptualHash::of(&image(|x, y| gradient(x) / 2 + y as u8 + 40));
+        assert_eq!(original.distance(&brighter), 0);
+        let different = PerceptualHash::of(&image(
+            |x, y| if (x / 8 + y / 8) % 2 == 0 { 0 } else { 255 },
+        ));
+        assert!(original.distance(&different) > 10);
+    }
+
+    #[test]
+    fn distance_is_the_larger_of_the_two() {
+        let a = PerceptualHash {
+            ahash: 0b1111,
+            dhash: 0,
+        };
+        let b = PerceptualHash {
+            ahash: 0,
+            dhash: 0b11,
+        };
+        assert_eq!(a.distance(&b), 4);
+        assert_eq!(b.distance(&a), 4);
+        assert_eq!(a.distance(&a), 0);
+    }
+
+    #[test]
+    fn thresholds_stay_within_the_hash() {
+        assert_eq!(parse_threshold("0"), Ok(0));
+        assert_eq!(parse_threshold("64"), Ok(64));
+        assert!(parse_threshold("65").is_err());
+        assert!(parse_threshold("-1").is_err());
+        assert!(parse_threshold("ten").is_err());
+    }
+
+    #[test]
+    fn groups_matches_transitively_and_across_records() {
+        let dir = std::env::temp_dir().join(format!("similarity-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let images = [
+            ("a.png", step(16)),
+            ("b.png", step(32)),
+            ("c.png", step(48)),
+            ("reversed.png", image(|x, _| gradient(63 - x))),
+        ];
+        for (name, image) in &images {
+            image.save(dir.join(name)).unwrap();
+        }
+        fs::write(dir.join("broken.png"), b"not a png").unwrap();
+
+        // A matches B and B matches C, but A and C are too far apart to match directly.
+        let hash = |index: usize| PerceptualHash::of(&images[index].1);
+        let threshold = hash(0).distance(&hash(1)).max(hash(1).distance(&hash(2)));
+        assert!(hash(0).distance(&hash(2)) > threshold);
+        assert!(hash(0).distance(&hash(3)) > threshold);
+
+        let with_photos = |id, names: &[&str]| {
+            let mut item = TileInventory::new(id, "Calle Real", "301 Royal St");
+            for (slot, name) in item.photo_slots_mut().into_iter().zip(names) {
+                *slot = Some(PathBuf::from(name));
+            }
+            item
+        };
+        let inventory = vec![
+            with_photos(1, &["a.png", "reversed.png"]),
+            with_photos(2, &["b.png", "broken.png"]),
+            with_photos(3, &["c.png"]),
+            // The same file twice is one photo in two columns, and always matches itself.
+            with_photos(4, &["reversed.png"]),
+        ];
+        let report = find_similar_photos(&inventory, &dir, threshold);
+        let strict = find_similar_photos(&inventory, &dir, threshold - 1);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.groups.len(), 2);
+        let members = |group: &SimilarGroup| -> Vec<(u32, usize)> {
+            group
+                .photos
+                .iter()
+                .map(|photo| (photo.id, photo.slot))
+                .collect()
+        };
+        assert_eq!(members(&report.groups[0]), [(1, 1), (2, 1), (3, 1)]);
+        assert_eq!(report.groups[0].ids, [1, 2, 3]);
+        assert_eq!(report.groups[0].max_distance, threshold);
+        assert!(report.groups[0].spans_records());
+        assert_eq!(members(&report.groups[1]), [(1, 2), (4, 1)]);
+        assert_eq!(report.groups[1].max_distance, 0);
+
+        // Just under the threshold, only the identical pair is left.
+        assert_eq!(strict.groups.len(), 1);
+        assert_eq!(members(&strict.groups[0]), [(1, 2), (4, 1)]);
+    }
+}